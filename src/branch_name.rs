@@ -0,0 +1,317 @@
+//! ブランチ名テンプレートモジュール
+//!
+//! このモジュールの役割：
+//! - `branch_template`（例: `{prefix}{name}-{date}`）の解析と検証
+//! - `twin add`で作成するブランチ名の生成（`{seq}`は既存のブランチと重複しない番号）
+//! - 既存のブランチ名からテンプレートの`{name}`部分（エージェント名）の抽出
+
+#![allow(dead_code)]
+use crate::core::{ConfigSettings, TwinError, TwinResult, DEFAULT_BRANCH_PREFIX};
+use chrono::NaiveDate;
+
+/// `branch_template`が未設定の場合に使用するテンプレート
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "{prefix}{name}";
+
+/// `{seq}`で試す番号の上限
+const MAX_SEQUENCE: usize = 1000;
+
+/// テンプレートの構成要素
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    /// そのまま出力する文字列（`{prefix}`は展開済み）
+    Literal(String),
+    /// `twin add`に渡された名前
+    Name,
+    /// 作成日（YYYYMMDD）
+    Date,
+    /// 実行したユーザー名
+    User,
+    /// 既存のブランチと重複しない番号（1から）
+    Seq,
+}
+
+/// ブランチ名テンプレート
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchTemplate {
+    parts: Vec<Part>,
+}
+
+impl Default for BranchTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_BRANCH_TEMPLATE, DEFAULT_BRANCH_PREFIX)
+            .expect("default branch template is valid")
+    }
+}
+
+impl BranchTemplate {
+    /// テンプレートを解析する
+    ///
+    /// `{prefix}`は区切り文字で終わっていなければ`/`を補って展開する（`agent` → `agent/`）。
+    pub fn parse(template: &str, prefix: &str) -> TwinResult<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            literal.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                return Err(TwinError::config(
+                    format!("Unclosed placeholder in branch_template: {template}"),
+                    None,
+                ));
+            };
+            let placeholder = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+
+            let part = match placeholder {
+                "prefix" => {
+                    literal.push_str(&normalize_prefix(prefix));
+                    continue;
+                }
+                "name" => Part::Name,
+                "date" => Part::Date,
+                "user" => Part::User,
+                "seq" => Part::Seq,
+                other => {
+                    return Err(TwinError::config(
+                        format!(
+                            "Unknown placeholder {{{other}}} in branch_template \
+                             (available: prefix, name, date, user, seq)"
+                        ),
+                        None,
+                    ))
+                }
+            };
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(part);
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        if !parts.contains(&Part::Name) {
+            return Err(TwinError::config(
+                format!("branch_template must contain {{name}}: {template}"),
+                None,
+            ));
+        }
+        Ok(Self { parts })
+    }
+
+    /// `twin add`で適用するテンプレートを設定から取得する
+    ///
+    /// `branch_template`も`branch_prefix`も設定されていない場合は、指定された名前をそのまま使うためNoneを返す。
+    pub fn from_settings(settings: &ConfigSettings) -> TwinResult<Option<Self>> {
+        if settings.branch_template.is_none() && settings.branch_prefix.is_none() {
+            return Ok(None);
+        }
+        Self::for_listing(settings).map(Some)
+    }
+
+    /// 一覧表示でエージェント名を抽出するテンプレートを設定から取得する（未設定の項目はデフォルト値）
+    pub fn for_listing(settings: &ConfigSettings) -> TwinResult<Self> {
+        Self::parse(
+            settings
+                .branch_template
+                .as_deref()
+                .unwrap_or(DEFAULT_BRANCH_TEMPLATE),
+            settings.branch_prefix(),
+        )
+    }
+
+    /// `{seq}`を含むか
+    pub fn has_sequence(&self) -> bool {
+        self.parts.contains(&Part::Seq)
+    }
+
+    /// ブランチ名を生成する
+    pub fn render(&self, name: &str, user: &str, date: NaiveDate, seq: usize) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(literal) => literal.clone(),
+                Part::Name => name.to_string(),
+                Part::Date => date.format("%Y%m%d").to_string(),
+                Part::User => user.to_string(),
+                Part::Seq => seq.to_string(),
+            })
+            .collect()
+    }
+
+    /// `twin add`で作成するブランチ名を決定する
+    ///
+    /// 既にテンプレートに一致する名前はそのまま使う。`{seq}`を含む場合は、`exists`が
+    /// falseを返す（既存のブランチと重複しない）最初の番号を使う。
+    pub fn apply(
+        &self,
+        name: &str,
+        mut exists: impl FnMut(&str) -> TwinResult<bool>,
+    ) -> TwinResult<String> {
+        if self.extract_name(name).is_some() {
+            return Ok(name.to_string());
+        }
+
+        let user = current_user();
+        let date = chrono::Local::now().date_naive();
+        if !self.has_sequence() {
+            return Ok(self.render(name, &user, date, 0));
+        }
+        for seq in 1..=MAX_SEQUENCE {
+            let branch = self.render(name, &user, date, seq);
+            if !exists(&branch)? {
+                return Ok(branch);
+            }
+        }
+        Err(TwinError::git(format!(
+            "Failed to generate unique branch name for: {name}"
+        )))
+    }
+
+    /// ブランチ名がテンプレートに一致する場合、`{name}`の部分を返す
+    pub fn extract_name(&self, branch: &str) -> Option<String> {
+        match_parts(&self.parts, branch)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+    }
+}
+
+/// ブランチ名に使う現在のユーザー名（英小文字・数字・`.`・`_`・`-`以外は`-`に置換）
+pub fn current_user() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let user: String = user
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if user.is_empty() {
+        "user".to_string()
+    } else {
+        user
+    }
+}
+
+/// 区切り文字で終わっていないプレフィックスに`/`を補う
+fn normalize_prefix(prefix: &str) -> String {
+    if prefix.is_empty() || prefix.ends_with(['/', '-', '_', '.']) {
+        prefix.to_string()
+    } else {
+        format!("{prefix}/")
+    }
+}
+
+/// 構成要素と文字列を先頭から照合し、一致した場合は`{name}`の部分を返す
+fn match_parts<'a>(parts: &[Part], text: &'a str) -> Option<&'a str> {
+    let Some((part, rest)) = parts.split_first() else {
+        return text.is_empty().then_some("");
+    };
+
+    match part {
+        Part::Literal(literal) => match_parts(rest, text.strip_prefix(literal.as_str())?),
+        Part::Date => {
+            let date = text.get(..8)?;
+            if !date.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            match_parts(rest, &text[8..])
+        }
+        Part::Seq | Part::User | Part::Name => {
+            // 後続の要素と一致する長さを短い順に試す（`{name}`以外の要素は1文字以上）
+            let min = usize::from(*part != Part::Name);
+            for (end, _) in text.char_indices().chain([(text.len(), ' ')]) {
+                if end < min {
+                    continue;
+                }
+                let value = &text[..end];
+                let valid = match part {
+                    Part::Seq => value.bytes().all(|b| b.is_ascii_digit()),
+                    Part::User => !value.contains('/'),
+                    _ => true,
+                };
+                if !valid {
+                    break;
+                }
+                if let Some(name) = match_parts(rest, &text[end..]) {
+                    return Some(if *part == Part::Name { value } else { name });
+                }
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 9).unwrap()
+    }
+
+    #[test]
+    fn test_default_template() {
+        let template = BranchTemplate::default();
+        assert_eq!(template.render("claude", "me", date(), 0), "agent/claude");
+        assert_eq!(
+            template.extract_name("agent/claude"),
+            Some("claude".to_string())
+        );
+        assert_eq!(template.extract_name("feature/claude"), None);
+        assert_eq!(template.extract_name("agent/"), None);
+    }
+
+    #[test]
+    fn test_render_and_extract_with_placeholders() {
+        let template =
+            BranchTemplate::parse("{prefix}{user}/{name}-{date}-{seq}", "work-").unwrap();
+        let branch = template.render("fix/login", "alice", date(), 2);
+        assert_eq!(branch, "work-alice/fix/login-20260309-2");
+        assert_eq!(
+            template.extract_name(&branch),
+            Some("fix/login".to_string())
+        );
+        assert_eq!(template.extract_name("work-alice/fix-login"), None);
+    }
+
+    #[test]
+    fn test_apply_uses_first_free_sequence() {
+        let template = BranchTemplate::parse("{prefix}{name}-{seq}", "agent").unwrap();
+        let existing = ["agent/claude-1".to_string(), "agent/claude-2".to_string()];
+        let branch = template
+            .apply("claude", |branch| Ok(existing.iter().any(|b| b == branch)))
+            .unwrap();
+        assert_eq!(branch, "agent/claude-3");
+
+        // テンプレートに一致する名前はそのまま使う
+        let branch = template.apply("agent/claude-1", |_| Ok(true)).unwrap();
+        assert_eq!(branch, "agent/claude-1");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(BranchTemplate::parse("{prefix}{unknown}", "agent").is_err());
+        assert!(BranchTemplate::parse("{prefix}{date}", "agent").is_err());
+        assert!(BranchTemplate::parse("{prefix}{name", "agent").is_err());
+    }
+
+    #[test]
+    fn test_from_settings_requires_configuration() {
+        let mut settings = ConfigSettings::default();
+        assert_eq!(BranchTemplate::from_settings(&settings).unwrap(), None);
+
+        settings.branch_prefix = Some("feature".to_string());
+        let template = BranchTemplate::from_settings(&settings).unwrap().unwrap();
+        assert_eq!(template.render("x", "me", date(), 0), "feature/x");
+    }
+}
@@ -0,0 +1,235 @@
+//! 整合性チェックモジュール
+//!
+//! このモジュールの役割：
+//! - ワークツリーの状態からの問題（リンク切れ、不足しているマッピングなど）の検出
+//! - 問題ごとの説明と修復方法の提示
+//! - `twin repair`で自動修復できる問題の判定
+
+#![allow(dead_code)]
+use crate::mapping::MappingHealth;
+use crate::status::WorktreeStatus;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 問題の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// リンク先が存在しないシンボリックリンク
+    BrokenLink,
+    /// 設定と異なるソースを指しているシンボリックリンク
+    WrongSource,
+    /// 設定に追加された後に適用されていないマッピング
+    MissingMapping,
+    /// シンボリックリンクが実ファイルに置き換えられている
+    ReplacedByFile,
+    /// マッピングのソースが存在しない
+    SourceMissing,
+    /// ディレクトリが存在しない（gitがprunableとしている）
+    Prunable,
+    /// `.git`ファイルのリンクが壊れている
+    BrokenGitLink,
+}
+
+impl IssueKind {
+    /// 種類の文字列表現
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueKind::BrokenLink => "broken_link",
+            IssueKind::WrongSource => "wrong_source",
+            IssueKind::MissingMapping => "missing_mapping",
+            IssueKind::ReplacedByFile => "replaced_by_file",
+            IssueKind::SourceMissing => "source_missing",
+            IssueKind::Prunable => "prunable",
+            IssueKind::BrokenGitLink => "broken_git_link",
+        }
+    }
+
+    /// `twin repair`で自動修復できるか
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            IssueKind::BrokenLink
+                | IssueKind::WrongSource
+                | IssueKind::MissingMapping
+                | IssueKind::BrokenGitLink
+        )
+    }
+}
+
+/// 検出された問題
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Issue {
+    /// 問題の種類
+    pub kind: IssueKind,
+    /// 対象のワークツリーのパス
+    pub worktree: PathBuf,
+    /// マッピングのターゲット（マッピングの問題の場合）
+    pub target: Option<PathBuf>,
+    /// マッピングのソース（マッピングの問題の場合）
+    pub source: Option<PathBuf>,
+}
+
+impl Issue {
+    /// 問題の説明
+    pub fn description(&self) -> String {
+        let target = self
+            .target
+            .as_ref()
+            .map(|target| {
+                target
+                    .strip_prefix(&self.worktree)
+                    .unwrap_or(target)
+                    .display()
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let source = self
+            .source
+            .as_ref()
+            .map(|source| source.display().to_string())
+            .unwrap_or_default();
+
+        match self.kind {
+            IssueKind::BrokenLink => format!("リンク切れ: {target}"),
+            IssueKind::WrongSource => {
+                format!("リンク先が設定と異なります: {target} (期待値: {source})")
+            }
+            IssueKind::MissingMapping => format!("マッピングが適用されていません: {target}"),
+            IssueKind::ReplacedByFile => {
+                format!("シンボリックリンクが実ファイルに置き換えられています: {target}")
+            }
+            IssueKind::SourceMissing => format!("マッピングのソースが存在しません: {source}"),
+            IssueKind::Prunable => "ディレクトリが存在しません".to_string(),
+            IssueKind::BrokenGitLink => ".gitファイルのリンクが壊れています".to_string(),
+        }
+    }
+
+    /// 修復方法
+    pub fn hint(&self, worktree_name: &str) -> String {
+        match self.kind {
+            kind if kind.is_repairable() => format!("`twin repair {worktree_name}` で修復できます"),
+            IssueKind::ReplacedByFile => {
+                "内容を確認し、不要であれば削除してから `twin repair` を実行してください"
+                    .to_string()
+            }
+            IssueKind::SourceMissing => {
+                "ソースファイルを作成するか、設定からマッピングを削除してください".to_string()
+            }
+            IssueKind::Prunable => {
+                "`twin prune` で管理情報と残った副作用を削除できます".to_string()
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// ワークツリーの状態から問題を検出する
+pub fn find_issues(status: &WorktreeStatus) -> Vec<Issue> {
+    let issue = |kind| Issue {
+        kind,
+        worktree: status.path.clone(),
+        target: None,
+        source: None,
+    };
+
+    if status.prunable {
+        return vec![issue(IssueKind::Prunable)];
+    }
+
+    let mut issues = Vec::new();
+    if status.git_link_broken {
+        issues.push(issue(IssueKind::BrokenGitLink));
+    }
+
+    for mapping in &status.mappings {
+        let kind = match mapping.health {
+            MappingHealth::Valid => continue,
+            MappingHealth::ReplacedByFile => IssueKind::ReplacedByFile,
+            // ソースがなければ作り直せない
+            _ if !mapping.source.exists() => IssueKind::SourceMissing,
+            MappingHealth::BrokenLink => IssueKind::BrokenLink,
+            MappingHealth::WrongSource => IssueKind::WrongSource,
+            MappingHealth::Missing => IssueKind::MissingMapping,
+        };
+        issues.push(Issue {
+            target: Some(mapping.target.clone()),
+            source: Some(mapping.source.clone()),
+            ..issue(kind)
+        });
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::MappingStatus;
+    use tempfile::TempDir;
+
+    fn status(mappings: Vec<MappingStatus>) -> WorktreeStatus {
+        WorktreeStatus {
+            path: PathBuf::from("/work/agent-1"),
+            branch: Some("agent-1".to_string()),
+            head: "abc".to_string(),
+            is_main: false,
+            locked: false,
+            prunable: false,
+            git_link_broken: false,
+            changed: 0,
+            untracked: 0,
+            upstream: None,
+            base: None,
+            mappings,
+            config_outdated: false,
+        }
+    }
+
+    #[test]
+    fn test_find_issues_classifies_mappings() {
+        let temp = TempDir::new().unwrap();
+        let existing = temp.path().join(".env");
+        std::fs::write(&existing, "A=1").unwrap();
+        let mapping = |source: &PathBuf, target: &str, health| MappingStatus {
+            mapping_type: "symlink".to_string(),
+            source: source.clone(),
+            target: PathBuf::from("/work/agent-1").join(target),
+            health,
+        };
+
+        let issues = find_issues(&status(vec![
+            mapping(&existing, ".env", MappingHealth::Valid),
+            mapping(&existing, "a", MappingHealth::BrokenLink),
+            mapping(&existing, "b", MappingHealth::Missing),
+            mapping(&temp.path().join("gone"), "c", MappingHealth::BrokenLink),
+            mapping(&existing, "d", MappingHealth::ReplacedByFile),
+        ]));
+
+        let kinds: Vec<_> = issues.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                IssueKind::BrokenLink,
+                IssueKind::MissingMapping,
+                IssueKind::SourceMissing,
+                IssueKind::ReplacedByFile,
+            ]
+        );
+        assert!(issues[0].description().ends_with(": a"));
+        assert!(issues[0].hint("agent-1").contains("twin repair agent-1"));
+        assert!(!IssueKind::ReplacedByFile.is_repairable());
+    }
+
+    #[test]
+    fn test_prunable_worktree_has_single_issue() {
+        let mut status = status(Vec::new());
+        status.prunable = true;
+        status.git_link_broken = true;
+
+        let issues = find_issues(&status);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, IssueKind::Prunable);
+        assert!(issues[0].hint("wt").contains("twin prune"));
+    }
+}
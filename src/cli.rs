@@ -4,6 +4,10 @@
 pub mod commands;
 mod output;
 
+use crate::core::{TwinError, TwinResult};
+use crate::filter::WorktreeFilter;
+use crate::list::{ListColumn, ListSort};
+use crate::plan::PlanFormat;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -15,6 +19,18 @@ use std::path::PathBuf;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// 何も変更せずに実行計画を表示（--dry-run=json でJSON形式）
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    pub dry_run: Option<PlanFormat>,
 }
 
 /// 利用可能なサブコマンドの定義
@@ -34,6 +50,27 @@ pub enum Commands {
     #[command(alias = "delete")]
     Remove(RemoveArgs),
 
+    /// ワークツリーのgitの状態とファイルマッピングの状態を表示
+    Status(StatusArgs),
+
+    /// すべてのワークツリーの副作用の整合性をチェック
+    Check(CheckArgs),
+
+    /// ワークツリーの副作用を再構築して整合性を回復
+    Repair(RepairArgs),
+
+    /// 現在の設定のマッピングを既存のワークツリーに反映
+    Sync(SyncArgs),
+
+    /// 存在しないワークツリーの管理情報と、孤立した副作用を削除
+    Prune(PruneArgs),
+
+    /// フックの実行ログを表示
+    Logs(LogsArgs),
+
+    /// 中断されたadd/removeを一覧表示・再開・ロールバック
+    Recover(RecoverArgs),
+
     /// 設定を管理
     Config(ConfigArgs),
 
@@ -44,8 +81,46 @@ pub enum Commands {
     Init(InitArgs),
 }
 
+impl Commands {
+    /// グローバルな`--dry-run`をサブコマンドに渡す
+    ///
+    /// 読み取り専用のコマンドはそのまま実行し、実行計画を表示できない変更を伴うコマンドはエラーにする。
+    pub fn set_dry_run(&mut self, format: PlanFormat) -> TwinResult<()> {
+        let name = match self {
+            Commands::Add(args) | Commands::Create(args) => {
+                args.dry_run = Some(format);
+                return Ok(());
+            }
+            Commands::Remove(args) => {
+                args.dry_run = Some(format);
+                return Ok(());
+            }
+            Commands::Sync(args) => {
+                args.dry_run = Some(format);
+                return Ok(());
+            }
+            Commands::Prune(args) => {
+                args.dry_run = Some(format);
+                return Ok(());
+            }
+            Commands::List(_) | Commands::Status(_) | Commands::Check(_) | Commands::Logs(_) => {
+                return Ok(())
+            }
+            Commands::Repair(_) => "repair",
+            Commands::Recover(_) => "recover",
+            Commands::Config(_) => "config",
+            Commands::Tui => "tui",
+            Commands::Init(_) => "init",
+        };
+
+        Err(TwinError::invalid_argument(format!(
+            "--dry-run is not supported for `twin {name}`"
+        )))
+    }
+}
+
 /// addコマンドの引数（twin独自の使いやすい順序）
-#[derive(Parser)]
+#[derive(Parser, Default)]
 pub struct AddArgs {
     /// ブランチ名またはコミット
     pub branch: String,
@@ -108,6 +183,10 @@ pub struct AddArgs {
     /// twin固有: 副作用をスキップしてgit worktreeのみ実行
     #[arg(long)]
     pub git_only: bool,
+
+    /// グローバルな`--dry-run`の出力形式（mainで設定される）
+    #[arg(skip)]
+    pub dry_run: Option<PlanFormat>,
 }
 
 /// listコマンドの引数
@@ -116,18 +195,41 @@ pub struct ListArgs {
     /// 出力フォーマット (table, json, simple)
     #[arg(short, long, default_value = "table")]
     pub format: String,
+
+    /// 表示する列（カンマ区切り、省略時はbranch,status,commit,upstream,path）
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "COLUMNS")]
+    pub columns: Vec<ListColumn>,
+
+    /// 並び順（メインのワークツリーは常に先頭）
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort: Option<ListSort>,
+
+    /// 降順で並べる
+    #[arg(long, requires = "sort")]
+    pub reverse: bool,
+
+    /// ahead/behindを比較するブランチ（省略時はメインのワークツリーのブランチ）
+    #[arg(long, value_name = "BRANCH")]
+    pub base: Option<String>,
+
+    #[command(flatten)]
+    pub filter: WorktreeFilter,
 }
 
 /// removeコマンドの引数（git worktree removeと互換）
-#[derive(Parser)]
+#[derive(Parser, Default)]
 pub struct RemoveArgs {
-    /// 削除するワークツリーのパスまたは名前
-    pub worktree: String,
+    /// 削除するワークツリーのパスまたは名前（省略時は絞り込み条件に一致するすべてのワークツリー）
+    pub worktree: Option<String>,
 
-    /// 確認なしで強制削除
+    /// 確認なしで強制削除（絞り込み条件での削除では未コミットの変更があるワークツリーも削除する）
     #[arg(short, long)]
     pub force: bool,
 
+    /// 確認プロンプトを表示しない（未コミットの変更があるワークツリーは削除しない）
+    #[arg(short, long)]
+    pub yes: bool,
+
     /// 設定ファイルのパス
     #[arg(short, long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -139,6 +241,144 @@ pub struct RemoveArgs {
     /// 出力を抑制
     #[arg(short, long)]
     pub quiet: bool,
+
+    #[command(flatten)]
+    pub filter: WorktreeFilter,
+
+    /// グローバルな`--dry-run`の出力形式（mainで設定される）
+    #[arg(skip)]
+    pub dry_run: Option<PlanFormat>,
+}
+
+/// statusコマンドの引数
+#[derive(Parser)]
+pub struct StatusArgs {
+    /// 表示するワークツリーのパスまたは名前（省略時はすべて）
+    pub worktree: Option<String>,
+
+    /// ahead/behindを比較するベースブランチ（省略時はメインのワークツリーのブランチ）
+    #[arg(long, value_name = "BRANCH")]
+    pub base: Option<String>,
+
+    /// 出力フォーマット (table, json, simple)
+    #[arg(short, long, default_value = "table")]
+    pub format: String,
+
+    /// 設定ファイルのパス
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// checkコマンドの引数
+#[derive(Parser)]
+pub struct CheckArgs {
+    /// 設定ファイルのパス
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// repairコマンドの引数
+#[derive(Parser)]
+pub struct RepairArgs {
+    /// 修復するワークツリーのパスまたは名前
+    pub worktree: Option<String>,
+
+    /// メイン以外のすべてのワークツリーを修復
+    #[arg(long, conflicts_with = "worktree")]
+    pub all: bool,
+
+    /// 設定ファイルのパス
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// 出力を抑制
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// syncコマンドの引数
+#[derive(Parser)]
+pub struct SyncArgs {
+    /// 同期するワークツリーのパスまたは名前
+    pub worktree: Option<String>,
+
+    /// メイン以外のすべてのワークツリーを同期
+    #[arg(long, conflicts_with = "worktree")]
+    pub all: bool,
+
+    /// 設定から削除されたマッピングのリンクも削除
+    #[arg(long)]
+    pub prune: bool,
+
+    /// 設定ファイルのパス
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// 出力を抑制
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// グローバルな`--dry-run`の出力形式（mainで設定される）
+    #[arg(skip)]
+    pub dry_run: Option<PlanFormat>,
+}
+
+/// pruneコマンドの引数
+#[derive(Parser)]
+pub struct PruneArgs {
+    /// 設定ファイルのパス
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// 出力を抑制
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// 条件を指定した場合は、一致するprunableなワークツリーだけを整理する
+    #[command(flatten)]
+    pub filter: WorktreeFilter,
+
+    /// グローバルな`--dry-run`の出力形式（mainで設定される）
+    #[arg(skip)]
+    pub dry_run: Option<PlanFormat>,
+}
+
+/// logsコマンドの引数
+#[derive(Parser)]
+pub struct LogsArgs {
+    /// ログを表示するワークツリーのパスまたは名前
+    pub worktree: String,
+
+    /// フックタイプで絞り込む（pre_create, post_create, pre_remove, post_remove）
+    #[arg(long, value_name = "HOOK_TYPE")]
+    pub hook_type: Option<crate::hooks::HookType>,
+
+    /// 失敗したフックのみ表示
+    #[arg(long)]
+    pub failed: bool,
+}
+
+/// recoverコマンドの引数
+#[derive(Parser)]
+pub struct RecoverArgs {
+    /// 対象の操作ID（前方一致可、省略時は一覧を表示）
+    pub id: Option<String>,
+
+    /// 成功済みのステップを逆順に元に戻す
+    #[arg(long, conflicts_with = "finish")]
+    pub rollback: bool,
+
+    /// 残りのステップを実行して操作を完了させる
+    #[arg(long)]
+    pub finish: bool,
+
+    /// 中断されたすべての操作を対象にする
+    #[arg(long, conflicts_with = "id")]
+    pub all: bool,
+
+    /// フックの出力などを抑制
+    #[arg(short, long)]
+    pub quiet: bool,
 }
 
 /// configコマンドの引数
@@ -1,6 +1,8 @@
 use crate::cli::output::OutputFormatter;
 use crate::cli::*;
-use crate::core::{Config, TwinError, TwinResult};
+use crate::config::Config;
+use crate::core::{TwinError, TwinResult};
+use crate::plan::Plan;
 
 // 後方互換性のためのcreateコマンドハンドラー
 pub async fn handle_create(args: AddArgs) -> TwinResult<()> {
@@ -8,17 +10,22 @@ pub async fn handle_create(args: AddArgs) -> TwinResult<()> {
 }
 
 pub async fn handle_add(args: AddArgs) -> TwinResult<()> {
-    use crate::git::GitManager;
-    use crate::hooks::{HookContext, HookExecutor, HookType};
-    use crate::symlink::create_symlink_manager;
+    let git = crate::git::GitManager::new(std::path::Path::new("."))?;
+    handle_add_with(args, git).await
+}
+
+/// 指定した`GitManager`（とそのバックエンド）でworktreeを作成する
+pub async fn handle_add_with(mut args: AddArgs, mut git: crate::git::GitManager) -> TwinResult<()> {
+    use crate::branch_name::BranchTemplate;
+    use crate::core::OperationType;
+    use crate::hook_log::HookLog;
+    use crate::hooks::{HookContext, HookExecutor};
+    use crate::journal::{Journal, JournalEntry};
+    use crate::manifest::Manifest;
     use std::path::PathBuf;
 
-    // 設定を読み込む
-    let config = if let Some(config_path) = &args.config {
-        Config::from_path(config_path)?
-    } else {
-        Config::new()
-    };
+    // 設定を読み込む（グローバル → プロジェクト → --config の順にマージ）
+    let config = Config::load(args.config.as_deref()).await?;
 
     // ワークツリーのパスを決定
     // パスが指定されていない場合は、worktree_base設定を使用
@@ -37,8 +44,23 @@ pub async fn handle_add(args: AddArgs) -> TwinResult<()> {
         }
     };
 
-    // Git worktreeを作成
-    let mut git = GitManager::new(std::path::Path::new("."))?;
+    // 並行して実行されるtwinコマンドとブランチ・worktreeの作成が競合しないようロックを保持する
+    let _lock = if args.dry_run.is_some() {
+        None
+    } else {
+        Some(lock_repository(&mut git).await?)
+    };
+
+    // 作成するブランチ名にテンプレートを適用（既存のブランチをチェックアウトする場合はそのまま）
+    let template = BranchTemplate::from_settings(&config.settings)?;
+    git.set_branch_template(BranchTemplate::for_listing(&config.settings)?);
+    if let Some(template) = template {
+        if let Some(name) = args.new_branch.as_mut().or(args.force_branch.as_mut()) {
+            *name = template.apply(name, |branch| git.branch_exists(branch))?;
+        } else if !args.detach && !git.branch_exists(&args.branch)? {
+            args.branch = template.apply(&args.branch, |branch| git.branch_exists(branch))?;
+        }
+    }
 
     // git worktree addの引数を構築
     let mut worktree_args = Vec::new();
@@ -133,9 +155,24 @@ pub async fn handle_add(args: AddArgs) -> TwinResult<()> {
 
     // git_onlyモードの場合は副作用をスキップ
     if args.git_only {
+        if let Some(format) = args.dry_run {
+            let mut plan = Plan::new("add");
+            git.set_dry_run(true);
+            git.add_worktree_with_options(&worktree_args)?;
+            plan.git_commands(&mut git);
+            return plan.print(format);
+        }
         let output = git.add_worktree_with_options(&worktree_args)?;
+        // 副作用を適用していないことを記録し、removeで設定のマッピングを削除しないようにする
+        let branch = args
+            .new_branch
+            .as_ref()
+            .or(args.force_branch.as_ref())
+            .unwrap_or(&args.branch);
+        Manifest::new(&worktree_path_absolute, branch, &config.settings)
+            .save(&git.get_twin_dir()?)?;
         if !args.quiet {
-            print!("{}", String::from_utf8_lossy(&output.stdout));
+            print!("{output}");
         }
         return Ok(());
     }
@@ -148,54 +185,253 @@ pub async fn handle_add(args: AddArgs) -> TwinResult<()> {
         .cloned()
         .unwrap_or_else(|| args.branch.clone());
 
+    let project_root = project_root(&git)?;
+
     // フック実行の準備
-    let hook_executor = HookExecutor::new();
-    let hook_context = HookContext::new(
+    let mut hook_executor = HookExecutor::new();
+    hook_executor.set_quiet(args.quiet);
+    hook_executor.set_log(HookLog::for_worktree(
+        &git.get_twin_dir()?,
+        &worktree_path_absolute,
+    ));
+    let mut hook_context = HookContext::new(
         branch_name.clone(), // agent_nameの代わりにブランチ名を使用
         worktree_path_absolute.clone(),
         branch_name.clone(),
-        git.get_repo_path().to_path_buf(),
+        project_root.clone(),
+    );
+    // 既存ブランチをチェックアウトする場合以外は、現在のHEADから作成される
+    let start_point = if branch_exists && args.new_branch.is_none() && args.force_branch.is_none() {
+        args.branch.as_str()
+    } else {
+        "HEAD"
+    };
+    if let Ok(commit) = git.resolve_commit(start_point) {
+        hook_context.set_commit(commit);
+    }
+    if let Ok(base_branch) = git.get_current_branch() {
+        hook_context.set_base_branch(base_branch);
+    }
+
+    // git worktree addで新しく作成されるブランチ（ロールバック時に削除する）
+    let created_branch = match (&args.new_branch, &args.force_branch) {
+        (Some(branch), _) => Some(branch.clone()),
+        (None, Some(branch)) => (!git.branch_exists(branch)?).then(|| branch.clone()),
+        (None, None) => (!branch_exists && !args.detach).then(|| args.branch.clone()),
+    };
+
+    if let Some(format) = args.dry_run {
+        hook_executor.set_dry_run(true);
+        git.set_dry_run(true);
+        return plan_add(
+            &config,
+            &mut git,
+            &hook_executor,
+            &hook_context,
+            &worktree_args,
+        )?
+        .print(format);
+    }
+
+    // 各ステップをジャーナルに記録し、必須のステップが失敗した場合は逆順にロールバックする
+    let mut entry = JournalEntry::new(
+        OperationType::CreateEnvironment,
+        &worktree_path_absolute,
+        &branch_name,
+        &project_root,
     );
+    entry.config_path = args.config.as_ref().map(|path| project_root.join(path));
+    entry.git_args = worktree_args.iter().map(|arg| arg.to_string()).collect();
+    entry.created_branch = created_branch;
+    entry.commit = hook_context.commit.clone();
+    entry.base_branch = hook_context.base_branch.clone();
+    let mut operation = Journal::new(&git.get_twin_dir()?).begin(entry)?;
 
-    // pre_createフックを実行
-    if !config.settings.hooks.pre_create.is_empty() {
-        for hook in &config.settings.hooks.pre_create {
-            match hook_executor.execute(HookType::PreCreate, hook, &hook_context) {
-                Ok(result) => {
-                    if !result.success && !hook.continue_on_error {
-                        return Err(TwinError::hook(
-                            format!("Pre-create hook failed: {}", hook.command),
-                            "pre_create",
-                            result.exit_code,
-                        ));
-                    }
-                }
-                Err(e) if !hook.continue_on_error => return Err(e),
-                Err(e) => eprintln!("Warning: Pre-create hook failed: {e}"),
+    let output = match run_add_steps(
+        &mut operation,
+        &config,
+        &mut git,
+        &hook_executor,
+        &hook_context,
+        args.quiet,
+    ) {
+        Ok(output) => output,
+        Err(e) => return Err(rollback_operation(operation, e, &mut git)),
+    };
+    operation.complete()?;
+
+    // パス表示やcdコマンド表示の処理
+    if args.print_path {
+        println!("{}", worktree_path_absolute.display());
+    } else if args.cd_command {
+        println!("cd \"{}\"", worktree_path_absolute.display());
+    } else if !args.quiet {
+        // git worktreeの出力をそのまま表示
+        if let Some(output) = output {
+            print!("{output}");
+        }
+        if !config.settings.files.is_empty() {
+            println!("✓ ファイルマッピングを適用しました");
+        }
+    }
+
+    Ok(())
+}
+
+/// worktree作成の実行計画を構築する（`run_add_steps`と同じ順序で、何も変更しない）
+fn plan_add(
+    config: &Config,
+    git: &mut crate::git::GitManager,
+    hook_executor: &crate::hooks::HookExecutor,
+    hook_context: &crate::hooks::HookContext,
+    worktree_args: &[&str],
+) -> TwinResult<Plan> {
+    use crate::hooks::HookType;
+    use crate::plan::MappingAction;
+
+    let mut plan = Plan::new("add");
+    let hooks = &config.settings.hooks;
+    plan.hooks(
+        hook_executor,
+        HookType::PreCreate,
+        &hooks.pre_create,
+        hook_context,
+    )?;
+
+    git.add_worktree_with_options(worktree_args)?;
+    plan.git_commands(git);
+
+    for mapping in &config.settings.files {
+        let source = hook_context.project_root.join(&mapping.source);
+        let target = hook_context.worktree_path.join(mapping.target_path());
+        let skip_reason = if !source.exists() {
+            Some("source not found")
+        } else if mapping.skip_if_exists && (target.exists() || target.is_symlink()) {
+            Some("target already exists")
+        } else {
+            None
+        };
+        plan.mapping(
+            MappingAction::for_type(&mapping.mapping_type),
+            Some(&source),
+            &target,
+            skip_reason,
+        );
+    }
+
+    plan.hooks(
+        hook_executor,
+        HookType::PostCreate,
+        &hooks.post_create,
+        hook_context,
+    )?;
+    Ok(plan)
+}
+
+/// worktree作成の各ステップを実行する
+///
+/// ジャーナルに成功済みとして記録されているステップはスキップするため、
+/// `twin recover --finish`での再開にも使用する。git worktree addを実行した場合はその標準出力を返す。
+fn run_add_steps(
+    operation: &mut crate::journal::JournaledOperation,
+    config: &Config,
+    git: &mut crate::git::GitManager,
+    hook_executor: &crate::hooks::HookExecutor,
+    hook_context: &crate::hooks::HookContext,
+    quiet: bool,
+) -> TwinResult<Option<String>> {
+    use crate::core::OperationStep;
+    use crate::hooks::HookType;
+    use crate::manifest::Manifest;
+    use crate::mapping::{apply_mapping, MappingOutcome};
+    use crate::rollback;
+    use crate::symlink::create_symlink_manager;
+    use crate::template;
+
+    let worktree_path = operation.entry.worktree.clone();
+    let project_root = operation.entry.project_root.clone();
+
+    // pre_createフックを実行（再開時に数が合うよう、失敗を許容したフックも実行済みとして記録する）
+    let completed = operation.entry.completed_count(rollback::STEP_PRE_CREATE);
+    for hook in config.settings.hooks.pre_create.iter().skip(completed) {
+        let step = OperationStep::new(rollback::STEP_PRE_CREATE, false)
+            .with_detail(rollback::DETAIL_COMMAND, &hook.command);
+        match hook_executor.execute(HookType::PreCreate, hook, hook_context) {
+            Ok(result) if result.timed_out => eprintln!(
+                "Warning: Pre-create hook timed out after {}s: {}",
+                hook.timeout, hook.command
+            ),
+            Ok(result) if !result.success && !hook.continue_on_error => {
+                let error = TwinError::hook(
+                    format!("Pre-create hook failed: {}", hook.command),
+                    "pre_create",
+                    result.exit_code,
+                );
+                operation.fail(step, error.to_string());
+                return Err(error);
+            }
+            Ok(_) => {}
+            Err(e) if !hook.continue_on_error || e.is_interrupted() => {
+                operation.fail(step, e.to_string());
+                return Err(e);
             }
+            Err(e) => eprintln!("Warning: Pre-create hook failed: {e}"),
         }
+        operation.record(step);
     }
 
     // 通常モード: git worktreeを実行して副作用を適用
-    let output = git.add_worktree_with_options(&worktree_args)?;
-    let _worktree_info = git.get_worktree_info(&worktree_path)?;
+    let mut output = None;
+    let worktree_path_str = worktree_path.to_string_lossy().to_string();
+    if !operation.entry.is_completed(
+        rollback::STEP_ADD_WORKTREE,
+        rollback::DETAIL_PATH,
+        &worktree_path_str,
+    ) {
+        let add_step = OperationStep::new(rollback::STEP_ADD_WORKTREE, true)
+            .with_detail(rollback::DETAIL_PATH, &worktree_path_str);
+        let git_args = operation.entry.git_args.clone();
+        let git_args: Vec<&str> = git_args.iter().map(String::as_str).collect();
+        match git.add_worktree_with_options(&git_args) {
+            Ok(result) => output = Some(result),
+            Err(e) => {
+                operation.fail(add_step, e.to_string());
+                return Err(e);
+            }
+        }
+        // worktreeの作成後に失敗した場合もロールバックで削除されるよう、確認の前に成功を記録する
+        // （ロールバックは逆順なので、worktreeを削除してからブランチを削除する）
+        if let Some(branch) = operation.entry.created_branch.clone() {
+            operation.record(
+                OperationStep::new(rollback::STEP_CREATE_BRANCH, true)
+                    .with_detail(rollback::DETAIL_BRANCH, branch),
+            );
+        }
+        operation.record(add_step.clone());
+        if let Err(e) = git.get_worktree_info(&worktree_path) {
+            operation.fail(add_step, e.to_string());
+            return Err(e);
+        }
+    }
 
     // シンボリックリンクを作成（副作用）
-    if !config.settings.files.is_empty() && !args.git_only {
+    if !config.settings.files.is_empty() {
         let symlink_manager = create_symlink_manager();
-        let repo_root = git.get_repo_path();
+        let template_variables = template::context_variables(hook_context);
         let mut failed_links = Vec::new();
 
         for mapping in &config.settings.files {
-            // ソースは絶対パスに変換（repo_rootが"."の場合は現在のディレクトリを使用）
-            let source = if repo_root == std::path::Path::new(".") {
-                std::env::current_dir()?.join(&mapping.path)
-            } else if repo_root.is_absolute() {
-                repo_root.join(&mapping.path)
-            } else {
-                std::env::current_dir()?.join(repo_root).join(&mapping.path)
-            };
-            let target = worktree_path_absolute.join(&mapping.path);
+            let source = project_root.join(&mapping.source);
+            let target = worktree_path.join(mapping.target_path());
+
+            // 再開時は適用済みのマッピングをスキップ
+            if operation.entry.is_completed(
+                rollback::STEP_APPLY_MAPPING,
+                rollback::DETAIL_TARGET,
+                &target.to_string_lossy(),
+            ) {
+                continue;
+            }
 
             // ソースファイルが存在しない場合はスキップ
             if !source.exists() {
@@ -203,244 +439,1805 @@ pub async fn handle_add(args: AddArgs) -> TwinResult<()> {
                     "⚠️  Warning: Source file not found, skipping: {}",
                     source.display()
                 );
-                failed_links.push(mapping.path.clone());
+                failed_links.push(mapping.source.clone());
                 continue;
             }
 
+            let step = OperationStep::new(rollback::STEP_APPLY_MAPPING, true)
+                .with_detail(rollback::DETAIL_MAPPING_TYPE, mapping.mapping_type.as_str())
+                .with_detail(rollback::DETAIL_SOURCE, source.to_string_lossy())
+                .with_detail(rollback::DETAIL_TARGET, target.to_string_lossy());
+
             // ターゲットディレクトリを作成
             if let Some(parent) = target.parent() {
                 if let Err(e) = std::fs::create_dir_all(parent) {
-                    eprintln!(
-                        "⚠️  Warning: Failed to create directory {}: {}",
-                        parent.display(),
-                        e
+                    let error = TwinError::io(
+                        format!("Failed to create directory {}: {e}", parent.display()),
+                        Some(parent.to_path_buf()),
                     );
-                    failed_links.push(mapping.path.clone());
-                    continue;
+                    operation.fail(step, error.to_string());
+                    return Err(error);
                 }
             }
 
-            // シンボリックリンクを作成（エラー時は警告を表示して継続）
-            match symlink_manager.create_symlink(&source, &target) {
-                Ok(_) => {
-                    if !args.quiet {
-                        eprintln!(
-                            "✓ Created symlink: {} -> {}",
-                            target.display(),
-                            source.display()
-                        );
+            // マッピングタイプに応じてリンクまたはコピーを作成
+            match apply_mapping(
+                mapping,
+                &source,
+                &target,
+                symlink_manager.as_ref(),
+                &template_variables,
+            ) {
+                Ok(outcome) => {
+                    if outcome != MappingOutcome::Skipped {
+                        operation.record(step);
+                    }
+                    if !quiet {
+                        match outcome {
+                            MappingOutcome::Linked => eprintln!(
+                                "✓ Created symlink: {} -> {}",
+                                target.display(),
+                                source.display()
+                            ),
+                            MappingOutcome::Copied => {
+                                eprintln!("✓ Copied: {} -> {}", source.display(), target.display())
+                            }
+                            MappingOutcome::Rendered => eprintln!(
+                                "✓ Rendered template: {} -> {}",
+                                source.display(),
+                                target.display()
+                            ),
+                            MappingOutcome::Skipped => {
+                                eprintln!("- Skipped (already exists): {}", target.display())
+                            }
+                        }
                     }
                 }
                 Err(e) => {
-                    eprintln!(
-                        "⚠️  Warning: Failed to create symlink for {}: {}",
-                        mapping.path.display(),
-                        e
-                    );
-                    failed_links.push(mapping.path.clone());
+                    operation.fail(step, e.to_string());
+                    return Err(e);
                 }
             }
         }
 
-        // 失敗したリンクがある場合の警告
-        if !failed_links.is_empty() && !args.quiet {
-            eprintln!("⚠️  {} symlink(s) could not be created", failed_links.len());
-            eprintln!("   The worktree was created successfully, but some symlinks failed.");
+        // 失敗したマッピングがある場合の警告
+        if !failed_links.is_empty() && !quiet {
+            eprintln!("⚠️  {} mapping(s) could not be applied", failed_links.len());
+            eprintln!("   The worktree was created successfully, but some mappings failed.");
         }
     }
 
     // post_createフックを実行
-    if !config.settings.hooks.post_create.is_empty() {
-        for hook in &config.settings.hooks.post_create {
-            match hook_executor.execute(HookType::PostCreate, hook, &hook_context) {
-                Ok(result) => {
-                    if !result.success && !hook.continue_on_error {
-                        eprintln!("Error: Post-create hook failed: {}", hook.command);
-                        // post_createで失敗してもworktreeは既に作成済みなので、警告のみ
-                    }
-                }
-                Err(e) => eprintln!("Warning: Post-create hook failed: {e}"),
+    let completed = operation.entry.completed_count(rollback::STEP_POST_CREATE);
+    for hook in config.settings.hooks.post_create.iter().skip(completed) {
+        let step = OperationStep::new(rollback::STEP_POST_CREATE, false)
+            .with_detail(rollback::DETAIL_COMMAND, &hook.command);
+        match hook_executor.execute(HookType::PostCreate, hook, hook_context) {
+            Ok(result) if result.timed_out => eprintln!(
+                "Warning: Post-create hook timed out after {}s: {}",
+                hook.timeout, hook.command
+            ),
+            Ok(result) if !result.success && !hook.continue_on_error => {
+                let error = TwinError::hook(
+                    format!("Post-create hook failed: {}", hook.command),
+                    "post_create",
+                    result.exit_code,
+                );
+                operation.fail(step, error.to_string());
+                return Err(error);
+            }
+            Ok(_) => {}
+            Err(e) if !hook.continue_on_error || e.is_interrupted() => {
+                operation.fail(step, e.to_string());
+                return Err(e);
             }
+            Err(e) => eprintln!("Warning: Post-create hook failed: {e}"),
         }
+        operation.record(step);
     }
 
-    // パス表示やcdコマンド表示の処理
-    if args.print_path {
-        println!("{}", worktree_path_absolute.display());
-    } else if args.cd_command {
-        println!("cd \"{}\"", worktree_path_absolute.display());
-    } else if !args.quiet {
-        // git worktreeの出力をそのまま表示
-        print!("{}", String::from_utf8_lossy(&output.stdout));
-        if !config.settings.files.is_empty() {
-            println!("✓ シンボリックリンクを作成しました");
+    // 適用した副作用をマニフェストに記録（remove/status/syncは現在の設定ではなくこれを参照する）
+    let manifest = Manifest::from_journal(&operation.entry, &config.settings);
+    if let Err(e) = git
+        .get_twin_dir()
+        .and_then(|twin_dir| manifest.save(&twin_dir))
+    {
+        eprintln!("⚠️  Warning: Failed to write manifest: {e}");
+    }
+
+    Ok(output)
+}
+
+/// マッピングのソースとフックの`project_root`の基準になるディレクトリ（GitManagerのパスを絶対パスにしたもの）
+///
+/// add・repair・syncで同じディレクトリからファイルを取得するよう、ここで一元的に解決する。
+fn project_root(git: &crate::git::GitManager) -> TwinResult<std::path::PathBuf> {
+    // repo_rootが"."の場合は現在のディレクトリを使用
+    let repo_root = git.get_repo_path();
+    Ok(if repo_root == std::path::Path::new(".") {
+        std::env::current_dir()?
+    } else if repo_root.is_absolute() {
+        repo_root.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(repo_root)
+    })
+}
+
+/// リポジトリ単位のロックを取得する（すべてのworktreeで共有され、ドロップ時に解放される）
+async fn lock_repository(git: &mut crate::git::GitManager) -> TwinResult<crate::utils::FileLock> {
+    let lock = crate::utils::FileLock::for_repo(&git.get_twin_dir()?);
+    lock.acquire().await?;
+    Ok(lock)
+}
+
+/// 成功済みのステップを逆順にロールバックして元のエラーを返す
+///
+/// すべて元に戻せた場合はジャーナルを削除し、戻せなかった場合は`twin recover`用に残す。
+fn rollback_operation(
+    operation: crate::journal::JournaledOperation,
+    error: TwinError,
+    git: &mut crate::git::GitManager,
+) -> TwinError {
+    let report = crate::rollback::rollback(operation.state(), git);
+    print_rollback_report(operation.state(), &report);
+
+    if report.is_clean() {
+        if let Err(e) = operation.complete() {
+            eprintln!("⚠️  Warning: Failed to remove journal: {e}");
         }
+    } else {
+        eprintln!(
+            "   `twin recover {}` で再度ロールバックできます",
+            operation.entry.id
+        );
     }
 
-    Ok(())
+    error
+}
+
+/// ロールバックの結果を表示
+fn print_rollback_report(
+    state: &crate::core::PartialFailureState,
+    report: &crate::rollback::RollbackReport,
+) {
+    if report.is_empty() {
+        return;
+    }
+
+    match &state.failed_step {
+        Some(step) => eprintln!(
+            "↩️  {} のため、ロールバックしました",
+            crate::rollback::describe(step)
+        ),
+        None => eprintln!("↩️  ロールバックしました"),
+    }
+    for description in &report.undone {
+        eprintln!("   ✓ 元に戻しました: {description}");
+    }
+    for (description, e) in &report.failed {
+        eprintln!("   ✗ 元に戻せませんでした: {description}: {e}");
+    }
+    for description in &report.not_undoable {
+        eprintln!("   - 実行済み（元に戻せません）: {description}");
+    }
 }
 
 pub async fn handle_list(args: ListArgs) -> TwinResult<()> {
-    use crate::git::GitManager;
+    let git = crate::git::GitManager::new(std::path::Path::new("."))?;
+    handle_list_with(args, git).await
+}
 
-    // git worktree list を使用
-    let mut git = GitManager::new(std::path::Path::new("."))?;
-    let worktrees = git.list_worktrees()?;
+/// 指定した`GitManager`（とそのバックエンド）でworktreeの一覧を表示する
+pub async fn handle_list_with(args: ListArgs, mut git: crate::git::GitManager) -> TwinResult<()> {
+    use crate::branch_name::BranchTemplate;
+    use crate::list::{collect_entries, sort_entries, DEFAULT_COLUMNS};
+
+    // git worktree list を使用（エージェント名は設定のブランチ名テンプレートから抽出する）
+    let config = Config::load(None).await?;
+    git.set_branch_template(BranchTemplate::for_listing(&config.settings)?);
+    let mut worktrees = git.list_worktrees()?;
+    git.attach_branch_info(&mut worktrees)?;
+    git.attach_created_at(&mut worktrees)?;
+
+    // 指定された列と並び替え・絞り込みに必要な情報だけを収集する
+    let columns = if args.columns.is_empty() {
+        DEFAULT_COLUMNS.to_vec()
+    } else {
+        args.columns.clone()
+    };
+    let mut needed = columns.clone();
+    needed.extend(args.sort.map(|sort| sort.column()));
+    let base = args.base.clone().or_else(|| main_branch(&worktrees));
+    let main = worktrees.first().map(|main| main.path.clone());
+    needed.extend(args.filter.required_columns());
+    let entries = collect_entries(&mut git, worktrees, &needed, base.as_deref());
+    let mut entries = args.filter.apply(&mut git, entries)?;
+    if let Some(sort) = args.sort {
+        sort_entries(&mut entries, main.as_deref(), sort, args.reverse);
+    }
 
     let formatter = OutputFormatter::new(&args.format);
-    formatter.format_worktrees(&worktrees)?;
+    formatter.format_worktrees(&entries, &columns)?;
 
     Ok(())
 }
 
 pub async fn handle_remove(args: RemoveArgs) -> TwinResult<()> {
-    use crate::git::GitManager;
-    use crate::hooks::{HookContext, HookExecutor, HookType};
-    use crate::symlink::create_symlink_manager;
+    let git = crate::git::GitManager::new(std::path::Path::new("."))?;
+    handle_remove_with(args, git).await
+}
+
+/// 指定した`GitManager`（とそのバックエンド）でworktreeを削除する
+pub async fn handle_remove_with(
+    args: RemoveArgs,
+    mut git: crate::git::GitManager,
+) -> TwinResult<()> {
     use std::path::PathBuf;
 
-    // Worktreeのパスかブランチ名で削除
-    let mut git = GitManager::new(std::path::Path::new("."))?;
+    let name = match &args.worktree {
+        Some(_) if args.filter.has_criteria() => {
+            return Err(TwinError::invalid_argument(
+                "ワークツリーの指定と絞り込み条件は同時に指定できません",
+            ));
+        }
+        Some(name) => name.clone(),
+        None if args.filter.has_criteria() => {
+            return remove_matching_worktrees(args, git).await;
+        }
+        None => {
+            return Err(TwinError::invalid_argument(
+                "削除するワークツリーか、絞り込み条件（--merged、--older-thanなど）を指定してください",
+            ));
+        }
+    };
 
+    // Worktreeのパスかブランチ名で削除
     // まずworktree一覧を取得して、対応するパスを探す
     let worktrees = git.list_worktrees()?;
     let worktree = worktrees.iter().find(|w| {
-        w.branch == args.worktree
-            || w.path.file_name().map(|n| n.to_string_lossy()) == Some(args.worktree.clone().into())
-            || w.path.to_string_lossy() == args.worktree
+        w.branch == name
+            || w.path.file_name().map(|n| n.to_string_lossy()) == Some(name.clone().into())
+            || w.path.to_string_lossy() == name
     });
 
     let path = if let Some(wt) = worktree {
         wt.path.clone()
     } else {
         // パスとして解釈してみる
-        PathBuf::from(&args.worktree)
+        PathBuf::from(&name)
+    };
+
+    // 確認プロンプト（ドライランでは何も削除しないため確認しない）
+    if !args.force
+        && !args.yes
+        && args.dry_run.is_none()
+        && !confirm(&format!("Worktree '{}' を削除しますか？", path.display()))?
+    {
+        println!("削除をキャンセルしました");
+        return Ok(());
+    }
+
+    // 確認後にロックを取得する（入力待ちの間は他のコマンドを妨げない）
+    let _lock = if args.dry_run.is_some() {
+        None
+    } else {
+        Some(lock_repository(&mut git).await?)
     };
 
-    // 確認プロンプト
-    if !args.force {
-        use std::io::{self, Write};
-        print!("Worktree '{}' を削除しますか？ [y/N]: ", path.display());
-        io::stdout().flush()?;
+    // 設定を読み込む（グローバル → プロジェクト → --config の順にマージ）
+    let config = Config::load(args.config.as_deref()).await?;
+
+    if let Some(plan) = remove_environment(&args, &config, &mut git, worktree, &path)? {
+        plan.print(args.dry_run.unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+/// 絞り込み条件に一致するメイン以外のworktreeをまとめて削除する
+///
+/// `--force`を指定しない限り、未コミットの変更があるworktreeは警告を表示して削除しない。
+async fn remove_matching_worktrees(
+    args: RemoveArgs,
+    mut git: crate::git::GitManager,
+) -> TwinResult<()> {
+    use crate::list::ListColumn;
+
+    if args.filter.main {
+        return Err(TwinError::invalid_argument(
+            "メインのワークツリーは削除できません",
+        ));
+    }
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+    let config = Config::load(args.config.as_deref()).await?;
+    let mut targets = Vec::new();
+    for (_, entry) in select_worktrees(&mut git, &config, &args.filter, &[ListColumn::Dirty])?
+        .into_iter()
+        .filter(|(is_main, _)| !is_main)
+    {
+        if !args.force && entry.dirty.is_some_and(|dirty| dirty > 0) {
+            println!(
+                "⚠️  Worktree '{}' には未コミットの変更があるため削除しません（--forceで削除）",
+                entry.worktree.path.display()
+            );
+            continue;
+        }
+        targets.push(entry.worktree);
+    }
+    if targets.is_empty() {
+        if !args.quiet {
+            println!("条件に一致するワークツリーはありません");
+        }
+        return Ok(());
+    }
 
-        if !input.trim().eq_ignore_ascii_case("y") {
+    // 確認プロンプトは対象を一覧表示してから1回だけ表示する
+    if !args.yes && args.dry_run.is_none() {
+        for worktree in &targets {
+            println!("  {}", worktree.path.display());
+        }
+        if !confirm(&format!("{}個のWorktreeを削除しますか？", targets.len()))? {
             println!("削除をキャンセルしました");
             return Ok(());
         }
     }
 
-    // 設定を読み込む
-    let config = if let Some(config_path) = &args.config {
-        Config::from_path(config_path)?
+    // 確認後にロックを取得し、確認の間に他のコマンドが変更した可能性があるため条件を再確認する
+    let _lock = if args.dry_run.is_some() {
+        None
     } else {
-        Config::new()
+        let lock = lock_repository(&mut git).await?;
+        let current: Vec<_> = select_worktrees(&mut git, &config, &args.filter, &[])?
+            .into_iter()
+            .filter(|(is_main, _)| !is_main)
+            .map(|(_, entry)| entry.worktree.path)
+            .collect();
+        targets.retain(|worktree| {
+            let matched = current.contains(&worktree.path);
+            if !matched {
+                println!(
+                    "⚠️  Worktree '{}' は条件に一致しなくなったため削除しません",
+                    worktree.path.display()
+                );
+            }
+            matched
+        });
+        Some(lock)
     };
 
+    // 1つの削除に失敗しても残りの削除を続ける
+    let mut plan = Plan::new("remove");
+    let mut failures = 0;
+    for worktree in &targets {
+        match remove_environment(&args, &config, &mut git, Some(worktree), &worktree.path) {
+            Ok(Some(step_plan)) => plan.steps.extend(step_plan.steps),
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!(
+                    "✗ Worktree '{}' を削除できませんでした: {e}",
+                    worktree.path.display()
+                );
+                failures += 1;
+            }
+        }
+    }
+    if let Some(format) = args.dry_run {
+        plan.print(format)?;
+    }
+
+    if failures > 0 {
+        return Err(TwinError::other(format!(
+            "{failures} worktree(s) could not be removed"
+        )));
+    }
+    Ok(())
+}
+
+/// 確認プロンプトを表示し、`y`が入力されたかを返す
+fn confirm(message: &str) -> TwinResult<bool> {
+    use std::io::{self, Write};
+    print!("{message} [y/N]: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// 1つのworktreeをフック・マッピングの片付けと一緒に削除する（ドライランでは実行計画を返す）
+fn remove_environment(
+    args: &RemoveArgs,
+    config: &Config,
+    git: &mut crate::git::GitManager,
+    worktree: Option<&crate::git::WorktreeInfo>,
+    path: &std::path::Path,
+) -> TwinResult<Option<Plan>> {
+    use crate::core::OperationType;
+    use crate::hook_log::HookLog;
+    use crate::hooks::{HookContext, HookExecutor};
+    use crate::journal::{Journal, JournalEntry};
+    use crate::manifest::Manifest;
+
+    let path = path.to_path_buf();
+
     // フック実行の準備（削除時はブランチ名かパス名を使用）
-    let branch_name = worktree.map(|w| w.branch.clone()).unwrap_or_else(|| {
-        path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("worktree")
-            .to_string()
-    });
+    let branch_name = worktree
+        .map(|w| w.branch.trim_start_matches("refs/heads/").to_string())
+        .unwrap_or_else(|| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("worktree")
+                .to_string()
+        });
 
-    let hook_executor = HookExecutor::new();
-    let hook_context = HookContext::new(
+    let repo_root = std::env::current_dir()?.join(git.get_repo_path());
+    let mut hook_executor = HookExecutor::new();
+    hook_executor.set_quiet(args.quiet);
+    hook_executor.set_log(HookLog::for_worktree(&git.get_twin_dir()?, &path));
+    let mut hook_context = HookContext::new(
         branch_name.clone(),
         path.clone(),
         branch_name.clone(),
-        git.get_repo_path().to_path_buf(),
+        repo_root.clone(),
+    );
+    if let Some(worktree) = worktree {
+        hook_context.set_commit(worktree.commit.clone());
+    }
+    if let Ok(base_branch) = git.get_current_branch() {
+        hook_context.set_base_branch(base_branch);
+    }
+
+    if args.dry_run.is_some() {
+        hook_executor.set_dry_run(true);
+        git.set_dry_run(true);
+        return plan_remove(
+            config,
+            git,
+            &hook_executor,
+            &hook_context,
+            args.force,
+            args.git_only,
+        )
+        .map(Some);
+    }
+
+    // git_onlyモードの場合は副作用をスキップ
+    if args.git_only {
+        git.remove_worktree(&path, args.force)?;
+        Manifest::delete(&git.get_twin_dir()?, &path)?;
+        println!("✓ Worktree '{}' を削除しました", path.display());
+        return Ok(None);
+    }
+
+    // 各ステップをジャーナルに記録し、worktreeの削除に失敗した場合は削除したリンクを元に戻す
+    let mut entry = JournalEntry::new(
+        OperationType::RemoveEnvironment,
+        &path,
+        &branch_name,
+        &repo_root,
     );
+    entry.config_path = args.config.as_ref().map(|config| repo_root.join(config));
+    entry.force = args.force;
+    entry.commit = hook_context.commit.clone();
+    entry.base_branch = hook_context.base_branch.clone();
+    let mut operation = Journal::new(&git.get_twin_dir()?).begin(entry)?;
 
-    // pre_removeフックを実行
-    if !config.settings.hooks.pre_remove.is_empty() && !args.git_only {
-        for hook in &config.settings.hooks.pre_remove {
-            match hook_executor.execute(HookType::PreRemove, hook, &hook_context) {
-                Ok(result) => {
-                    if !result.success && !hook.continue_on_error {
-                        return Err(TwinError::hook(
-                            format!("Pre-remove hook failed: {}", hook.command),
-                            "pre_remove",
-                            result.exit_code,
-                        ));
-                    }
+    if let Err(e) = run_remove_steps(
+        &mut operation,
+        config,
+        git,
+        &hook_executor,
+        &hook_context,
+        args.quiet,
+    ) {
+        return Err(rollback_operation(operation, e, git));
+    }
+    operation.complete()?;
+
+    println!("✓ Worktree '{}' を削除しました", path.display());
+
+    Ok(None)
+}
+
+/// 絞り込み条件に一致するworktreeを選択する（`remove`と`prune`で共通、メインのworktreeかどうかと合わせて返す）
+///
+/// `columns`には条件の判定に必要な列に加えて収集しておく列を指定する。
+fn select_worktrees(
+    git: &mut crate::git::GitManager,
+    config: &Config,
+    filter: &crate::filter::WorktreeFilter,
+    columns: &[crate::list::ListColumn],
+) -> TwinResult<Vec<(bool, crate::list::ListEntry)>> {
+    use crate::branch_name::BranchTemplate;
+    use crate::list::collect_entries;
+
+    git.set_branch_template(BranchTemplate::for_listing(&config.settings)?);
+    let mut worktrees = git.list_worktrees()?;
+    git.attach_branch_info(&mut worktrees)?;
+    let main = worktrees.first().map(|main| main.path.clone());
+    let mut needed = filter.required_columns();
+    needed.extend_from_slice(columns);
+    let entries = collect_entries(git, worktrees, &needed, None);
+    Ok(filter
+        .apply(git, entries)?
+        .into_iter()
+        .map(|entry| (Some(&entry.worktree.path) == main.as_ref(), entry))
+        .collect())
+}
+
+/// 削除時に片付けるマッピングとそのソース
+///
+/// マニフェストがあれば実際に適用したマッピングを、なければ現在の設定のマッピングを対象にする。
+fn removal_mappings<'a>(
+    manifest: Option<&'a crate::manifest::Manifest>,
+    config: &Config,
+    repo_root: &std::path::Path,
+) -> Vec<(
+    crate::core::FileMapping,
+    std::path::PathBuf,
+    Option<&'a crate::manifest::AppliedMapping>,
+)> {
+    match manifest {
+        Some(manifest) => manifest
+            .mappings
+            .iter()
+            .map(|applied| {
+                (
+                    applied.to_file_mapping(),
+                    applied.source.clone(),
+                    Some(applied),
+                )
+            })
+            .collect(),
+        None => config
+            .settings
+            .files
+            .iter()
+            .map(|mapping| (mapping.clone(), repo_root.join(&mapping.source), None))
+            .collect(),
+    }
+}
+
+/// worktree削除の実行計画を構築する（`run_remove_steps`と同じ順序で、何も変更しない）
+fn plan_remove(
+    config: &Config,
+    git: &mut crate::git::GitManager,
+    hook_executor: &crate::hooks::HookExecutor,
+    hook_context: &crate::hooks::HookContext,
+    force: bool,
+    git_only: bool,
+) -> TwinResult<Plan> {
+    use crate::core::MappingType;
+    use crate::hooks::HookType;
+    use crate::manifest::Manifest;
+    use crate::mapping::same_content;
+    use crate::plan::MappingAction;
+    use crate::template;
+
+    let path = hook_context.worktree_path.clone();
+    let twin_dir = git.get_twin_dir()?;
+    let manifest = Manifest::load(&twin_dir, &path)?;
+    let hooks = &config.settings.hooks;
+    let mut plan = Plan::new("remove");
+
+    if !git_only {
+        plan.hooks(
+            hook_executor,
+            HookType::PreRemove,
+            &hooks.pre_remove,
+            hook_context,
+        )?;
+
+        let template_variables = template::context_variables(hook_context);
+        // 操作IDは削除の実行時に決まる
+        let backup_root = crate::mapping::backup_root(&twin_dir, &path, "<operation-id>");
+        for (mapping, source, applied) in
+            removal_mappings(manifest.as_ref(), config, &hook_context.project_root)
+        {
+            let target = path.join(mapping.target_path());
+            if mapping.mapping_type == MappingType::Symlink {
+                let skip_reason = if target.is_symlink() {
+                    None
+                } else if target.exists() {
+                    Some("not a symlink")
+                } else {
+                    Some("target not found")
+                };
+                let source = std::fs::read_link(&target).unwrap_or(source);
+                plan.mapping(MappingAction::Unlink, Some(&source), &target, skip_reason);
+                continue;
+            }
+
+            // コピー・テンプレートは削除せず、変更されている場合のみバックアップする
+            let modified = match applied.and_then(|applied| applied.is_modified(&path)) {
+                Some(modified) => modified,
+                None if !target.exists() || target.is_symlink() => false,
+                None if mapping.mapping_type == MappingType::Copy => {
+                    !(source.exists() && same_content(&source, &target)?)
                 }
-                Err(e) if !hook.continue_on_error => return Err(e),
-                Err(e) => eprintln!("Warning: Pre-remove hook failed: {e}"),
+                None => {
+                    let expected = template::with_mapping_variables(
+                        template_variables.clone(),
+                        &mapping.variables,
+                    )
+                    .and_then(|variables| template::render_source(&source, &variables))?;
+                    std::fs::read(&target)? != expected.as_bytes()
+                }
+            };
+            let skip_reason = if !target.exists() {
+                Some("target not found")
+            } else if !modified {
+                Some("not modified")
+            } else {
+                None
+            };
+            plan.mapping(
+                MappingAction::Backup,
+                Some(&target),
+                &backup_root.join(mapping.target_path()),
+                skip_reason,
+            );
+        }
+    }
+
+    if git_only || path.exists() {
+        git.remove_worktree(&path, force)?;
+    } else {
+        git.prune_worktrees(false)?;
+    }
+    plan.git_commands(git);
+    if manifest.is_some() {
+        plan.remove(&Manifest::path_for(&twin_dir, &path), "manifest");
+    }
+
+    if !git_only {
+        plan.hooks(
+            hook_executor,
+            HookType::PostRemove,
+            &hooks.post_remove,
+            hook_context,
+        )?;
+    }
+    Ok(plan)
+}
+
+/// worktree削除の各ステップを実行する
+///
+/// ジャーナルに成功済みとして記録されているステップはスキップするため、
+/// `twin recover --finish`での再開にも使用する。
+fn run_remove_steps(
+    operation: &mut crate::journal::JournaledOperation,
+    config: &Config,
+    git: &mut crate::git::GitManager,
+    hook_executor: &crate::hooks::HookExecutor,
+    hook_context: &crate::hooks::HookContext,
+    quiet: bool,
+) -> TwinResult<()> {
+    use crate::core::{MappingType, OperationStep};
+    use crate::hooks::HookType;
+    use crate::manifest::Manifest;
+    use crate::mapping::{backup_copied, backup_rendered, backup_to};
+    use crate::rollback;
+    use crate::symlink::create_symlink_manager;
+    use crate::template;
+
+    let path = operation.entry.worktree.clone();
+    let repo_root = operation.entry.project_root.clone();
+
+    // pre_removeフックを実行（再開時に数が合うよう、失敗を許容したフックも実行済みとして記録する）
+    let completed = operation.entry.completed_count(rollback::STEP_PRE_REMOVE);
+    for hook in config.settings.hooks.pre_remove.iter().skip(completed) {
+        let step = OperationStep::new(rollback::STEP_PRE_REMOVE, false)
+            .with_detail(rollback::DETAIL_COMMAND, &hook.command);
+        match hook_executor.execute(HookType::PreRemove, hook, hook_context) {
+            Ok(result) if result.timed_out => eprintln!(
+                "Warning: Pre-remove hook timed out after {}s: {}",
+                hook.timeout, hook.command
+            ),
+            Ok(result) if !result.success && !hook.continue_on_error => {
+                let error = TwinError::hook(
+                    format!("Pre-remove hook failed: {}", hook.command),
+                    "pre_remove",
+                    result.exit_code,
+                );
+                operation.fail(step, error.to_string());
+                return Err(error);
+            }
+            Ok(_) => {}
+            Err(e) if !hook.continue_on_error || e.is_interrupted() => {
+                operation.fail(step, e.to_string());
+                return Err(e);
             }
+            Err(e) => eprintln!("Warning: Pre-remove hook failed: {e}"),
         }
+        operation.record(step);
     }
 
     // シンボリックリンクを削除（副作用のクリーンアップ）
-
-    if !config.settings.files.is_empty() && !args.git_only {
+    let path_str = path.to_string_lossy().to_string();
+    let worktree_removed = operation.entry.is_completed(
+        rollback::STEP_REMOVE_WORKTREE,
+        rollback::DETAIL_PATH,
+        &path_str,
+    );
+    let twin_dir = git.get_twin_dir()?;
+    let manifest = Manifest::load(&twin_dir, &path)?;
+    let mappings = removal_mappings(manifest.as_ref(), config, &repo_root);
+    if !mappings.is_empty() && !worktree_removed {
         let symlink_manager = create_symlink_manager();
+        let template_variables = template::context_variables(hook_context);
         let mut failed_cleanups = Vec::new();
+        let backup_root = crate::mapping::backup_root(&twin_dir, &path, &operation.entry.id);
 
-        for mapping in &config.settings.files {
-            let target = path.join(&mapping.path);
-
-            // シンボリックリンクが存在する場合のみ削除
-            if target.exists() || target.is_symlink() {
-                match symlink_manager.remove_symlink(&target) {
-                    Ok(_) => {
-                        if !args.quiet {
-                            eprintln!("✓ Removed symlink: {}", target.display());
+        for (mapping, source, applied) in &mappings {
+            let target = path.join(mapping.target_path());
+
+            // コピー・テンプレートで生成されたファイルは削除せず、変更されていればバックアップする
+            // （作成時のチェックサムがあれば、現在のソースではなく作成時の内容と比較する）
+            let relative = mapping.target_path();
+            let modified = applied.and_then(|applied| applied.is_modified(&path));
+            let backup = match (&mapping.mapping_type, modified) {
+                (MappingType::Symlink, _) => None,
+                (_, Some(true)) => Some(backup_to(&target, relative, &backup_root).map(Some)),
+                (_, Some(false)) => Some(Ok(None)),
+                (MappingType::Copy, None) => {
+                    Some(backup_copied(source, &target, relative, &backup_root))
+                }
+                (MappingType::Template, None) => Some(
+                    template::with_mapping_variables(
+                        template_variables.clone(),
+                        &mapping.variables,
+                    )
+                    .and_then(|variables| template::render_source(source, &variables))
+                    .and_then(|expected| {
+                        backup_rendered(&expected, &target, relative, &backup_root)
+                    }),
+                ),
+            };
+
+            if let Some(backup) = backup {
+                match backup {
+                    Ok(Some(backup)) => {
+                        if !quiet {
+                            eprintln!(
+                                "💾 Backed up modified file: {} -> {}",
+                                target.display(),
+                                backup.display()
+                            );
                         }
                     }
+                    Ok(None) => {}
                     Err(e) => {
-                        eprintln!(
-                            "⚠️  Warning: Failed to remove symlink {}: {}",
-                            target.display(),
-                            e
-                        );
-                        failed_cleanups.push(mapping.path.clone());
+                        eprintln!("⚠️  Warning: Failed to back up {}: {}", target.display(), e);
+                        failed_cleanups.push(relative.to_path_buf());
+                    }
+                }
+                continue;
+            }
+
+            // シンボリックリンクの場合のみ削除（ユーザーが置き換えたファイルはロールバックで上書きしないよう記録もしない）
+            if !target.is_symlink() {
+                if target.exists() && !quiet {
+                    eprintln!(
+                        "⚠️  Skipped {}: not a symlink created by twin",
+                        target.display()
+                    );
+                }
+                continue;
+            }
+
+            // ロールバック時に作成し直せるよう、リンク先を記録しておく
+            let link_source = std::fs::read_link(&target).unwrap_or_else(|_| source.clone());
+            match symlink_manager.remove_symlink(&target) {
+                Ok(_) => {
+                    operation.record(
+                        OperationStep::new(rollback::STEP_REMOVE_MAPPING, true)
+                            .with_detail(
+                                rollback::DETAIL_MAPPING_TYPE,
+                                mapping.mapping_type.as_str(),
+                            )
+                            .with_detail(rollback::DETAIL_SOURCE, link_source.to_string_lossy())
+                            .with_detail(rollback::DETAIL_TARGET, target.to_string_lossy()),
+                    );
+                    if !quiet {
+                        eprintln!("✓ Removed symlink: {}", target.display());
                     }
                 }
+                Err(e) => {
+                    eprintln!(
+                        "⚠️  Warning: Failed to remove symlink {}: {}",
+                        target.display(),
+                        e
+                    );
+                    failed_cleanups.push(mapping.target_path().to_path_buf());
+                }
             }
         }
 
-        if !failed_cleanups.is_empty() && !args.quiet {
+        if !failed_cleanups.is_empty() && !quiet {
             eprintln!(
-                "⚠️  {} symlink(s) could not be removed",
+                "⚠️  {} mapping(s) could not be cleaned up",
                 failed_cleanups.len()
             );
             eprintln!("   Proceeding with worktree removal anyway.");
         }
     }
 
-    // git worktree remove を実行
-    git.remove_worktree(&path, args.force)?;
+    // git worktree remove を実行（再開時に既に削除されていれば管理情報だけを整理する）
+    if !worktree_removed {
+        let step = OperationStep::new(rollback::STEP_REMOVE_WORKTREE, false)
+            .with_detail(rollback::DETAIL_PATH, &path_str);
+        let result = if path.exists() {
+            git.remove_worktree(&path, operation.entry.force)
+        } else {
+            git.prune_worktrees(false).map(|_| ())
+        };
+        if let Err(e) = result {
+            operation.fail(step, e.to_string());
+            return Err(e);
+        }
+        operation.record(step);
+    }
+    if let Err(e) = Manifest::delete(&twin_dir, &path) {
+        eprintln!("⚠️  Warning: Failed to delete manifest: {e}");
+    }
 
     // post_removeフックを実行
-    if !config.settings.hooks.post_remove.is_empty() && !args.git_only {
-        for hook in &config.settings.hooks.post_remove {
-            match hook_executor.execute(HookType::PostRemove, hook, &hook_context) {
-                Ok(result) => {
-                    if !result.success && !hook.continue_on_error {
-                        eprintln!("Error: Post-remove hook failed: {}", hook.command);
-                        // post_removeで失敗してもworktreeは既に削除済みなので、警告のみ
+    let completed = operation.entry.completed_count(rollback::STEP_POST_REMOVE);
+    for hook in config.settings.hooks.post_remove.iter().skip(completed) {
+        match hook_executor.execute(HookType::PostRemove, hook, hook_context) {
+            Ok(result) if result.timed_out => eprintln!(
+                "Warning: Post-remove hook timed out after {}s: {}",
+                hook.timeout, hook.command
+            ),
+            Ok(result) => {
+                if !result.success && !hook.continue_on_error {
+                    eprintln!("Error: Post-remove hook failed: {}", hook.command);
+                    // post_removeで失敗してもworktreeは既に削除済みなので、警告のみ
+                }
+            }
+            // worktreeは削除済みなので、中断された場合は残りのpost_removeフックを実行せずに終える
+            Err(e) if e.is_interrupted() => {
+                eprintln!("Warning: Post-remove hooks interrupted: {e}");
+                break;
+            }
+            Err(e) => eprintln!("Warning: Post-remove hook failed: {e}"),
+        }
+        operation.record(
+            OperationStep::new(rollback::STEP_POST_REMOVE, false)
+                .with_detail(rollback::DETAIL_COMMAND, &hook.command),
+        );
+    }
+
+    Ok(())
+}
+
+/// メインのワークツリー（一覧の先頭）のブランチ名
+fn main_branch(worktrees: &[crate::git::WorktreeInfo]) -> Option<String> {
+    worktrees
+        .first()
+        .and_then(|main| main.branch.strip_prefix("refs/heads/"))
+        .map(String::from)
+}
+
+pub async fn handle_status(args: StatusArgs) -> TwinResult<()> {
+    use crate::git::GitManager;
+
+    let config = Config::load(args.config.as_deref()).await?;
+    let mut git = GitManager::new(std::path::Path::new("."))?;
+    let worktrees = git.list_worktrees()?;
+
+    // ahead/behindはメインのワークツリーのブランチと比較する
+    let base = args.base.clone().or_else(|| main_branch(&worktrees));
+
+    let targets: Vec<_> = match &args.worktree {
+        Some(name) => vec![find_worktree(&worktrees, name)
+            .ok_or_else(|| TwinError::not_found("Worktree", name))?
+            .clone()],
+        None => worktrees.clone(),
+    };
+    let statuses = collect_statuses(&mut git, &config, &worktrees, &targets, base.as_deref())?;
+
+    let formatter = OutputFormatter::new(&args.format);
+    formatter.format_statuses(&statuses)?;
+
+    Ok(())
+}
+
+pub async fn handle_check(args: CheckArgs) -> TwinResult<()> {
+    use crate::check::find_issues;
+    use crate::git::GitManager;
+
+    let config = Config::load(args.config.as_deref()).await?;
+    let mut git = GitManager::new(std::path::Path::new("."))?;
+    let worktrees = git.list_worktrees()?;
+    let statuses = collect_statuses(&mut git, &config, &worktrees, &worktrees, None)?;
+
+    let mut issue_count = 0;
+    for status in statuses.iter().filter(|status| !status.is_main) {
+        let issues = find_issues(status);
+        let name = worktree_display_name(status);
+        if issues.is_empty() {
+            println!("✓ {name}");
+            continue;
+        }
+
+        issue_count += issues.len();
+        println!("✗ {name} ({})", status.path.display());
+        for issue in &issues {
+            println!("    - {}", issue.description());
+            println!("      → {}", issue.hint(&name));
+        }
+    }
+
+    if issue_count > 0 {
+        return Err(TwinError::other(format!(
+            "{issue_count} issue(s) found in worktrees"
+        )));
+    }
+    println!("問題は見つかりませんでした");
+
+    Ok(())
+}
+
+pub async fn handle_repair(args: RepairArgs) -> TwinResult<()> {
+    let git = crate::git::GitManager::new(std::path::Path::new("."))?;
+    handle_repair_with(args, git).await
+}
+
+/// 指定した`GitManager`（とそのバックエンド）でworktreeを修復する
+pub async fn handle_repair_with(
+    args: RepairArgs,
+    mut git: crate::git::GitManager,
+) -> TwinResult<()> {
+    use crate::manifest::{AppliedMapping, Manifest};
+    use crate::mapping::{repair_mapping, MappingOutcome};
+    use crate::symlink::create_symlink_manager;
+    use crate::template;
+
+    let config = Config::load(args.config.as_deref()).await?;
+    let _lock = lock_repository(&mut git).await?;
+    let worktrees = git.list_worktrees()?;
+    let Some(main) = worktrees.first().cloned() else {
+        return Err(TwinError::not_found("Worktree", "main"));
+    };
+
+    let targets: Vec<_> = match (&args.worktree, args.all) {
+        (Some(name), _) => vec![find_worktree(&worktrees, name)
+            .ok_or_else(|| TwinError::not_found("Worktree", name))?
+            .clone()],
+        (None, true) => worktrees[1..].to_vec(),
+        (None, false) => {
+            return Err(TwinError::invalid_argument(
+                "Specify a worktree or --all to repair",
+            ))
+        }
+    };
+
+    let symlink_manager = create_symlink_manager();
+    let twin_dir = git.get_twin_dir()?;
+    let project_root = project_root(&git)?;
+    let mut failures = 0;
+    for worktree in &targets {
+        let name = worktree
+            .branch
+            .strip_prefix("refs/heads/")
+            .map(String::from)
+            .unwrap_or_else(|| worktree.path.display().to_string());
+        if worktree.path == main.path {
+            println!("- {name}: メインのワークツリーは修復の対象外です");
+            continue;
+        }
+        if worktree.prunable || !worktree.path.exists() {
+            println!(
+                "- {name}: ディレクトリが存在しません（`twin prune` で管理情報と残った副作用を削除できます）"
+            );
+            continue;
+        }
+
+        // リポジトリの移動などで壊れた.gitファイルのリンクを修復
+        if git.is_worktree_link_broken(&worktree.path) {
+            match git.repair_worktrees(&[&worktree.path]) {
+                Ok(()) => println!("✓ {name}: git worktree repair を実行しました"),
+                Err(e) => {
+                    eprintln!("✗ {name}: git worktree repair に失敗しました: {e}");
+                    failures += 1;
+                    continue;
+                }
+            }
+        }
+
+        let context = worktree_hook_context(&mut git, worktree, &project_root);
+        let variables = template::context_variables(&context);
+        let mut manifest = Manifest::load(&twin_dir, &worktree.path)?;
+        let mut repaired = 0;
+        for mapping in &config.settings.files {
+            let source = project_root.join(&mapping.source);
+            let target = worktree.path.join(mapping.target_path());
+            if !source.exists() {
+                continue;
+            }
+
+            match repair_mapping(
+                mapping,
+                &source,
+                &target,
+                symlink_manager.as_ref(),
+                &variables,
+            ) {
+                Ok(None) => {}
+                Ok(Some(outcome)) => {
+                    repaired += 1;
+                    if let Some(manifest) = manifest.as_mut() {
+                        manifest.record_mapping(AppliedMapping::new(
+                            mapping.mapping_type.clone(),
+                            &source,
+                            mapping.target_path(),
+                            &worktree.path,
+                        ));
                     }
+                    if !args.quiet {
+                        let action = match outcome {
+                            MappingOutcome::Linked => "Created symlink",
+                            MappingOutcome::Copied => "Copied",
+                            MappingOutcome::Rendered => "Rendered template",
+                            MappingOutcome::Skipped => "Skipped",
+                        };
+                        println!("✓ {name}: {action}: {}", target.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("✗ {name}: {}: {e}", target.display());
+                    failures += 1;
+                }
+            }
+        }
+        if repaired == 0 && !args.quiet {
+            println!("✓ {name}: 修復が必要なマッピングはありません");
+        }
+        // 作り直したマッピングを記録し、removeで削除されるようにする
+        if let Some(manifest) = manifest.filter(|_| repaired > 0) {
+            manifest.save(&twin_dir)?;
+        }
+    }
+
+    if failures > 0 {
+        return Err(TwinError::other(format!(
+            "{failures} item(s) could not be repaired"
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn handle_sync(args: SyncArgs) -> TwinResult<()> {
+    let git = crate::git::GitManager::new(std::path::Path::new("."))?;
+    handle_sync_with(args, git).await
+}
+
+/// 指定した`GitManager`（とそのバックエンド）でworktreeのマッピングを設定に同期する
+pub async fn handle_sync_with(args: SyncArgs, mut git: crate::git::GitManager) -> TwinResult<()> {
+    use crate::manifest::Manifest;
+    use crate::plan::MappingAction;
+    use crate::symlink::create_symlink_manager;
+    use crate::sync::{apply_sync, plan_sync, record_sync, SyncAction};
+    use crate::template;
+
+    let config = Config::load(args.config.as_deref()).await?;
+    let _lock = if args.dry_run.is_some() {
+        None
+    } else {
+        Some(lock_repository(&mut git).await?)
+    };
+    let worktrees = git.list_worktrees()?;
+    let Some(main) = worktrees.first().cloned() else {
+        return Err(TwinError::not_found("Worktree", "main"));
+    };
+
+    let targets: Vec<_> = match (&args.worktree, args.all) {
+        (Some(name), _) => vec![find_worktree(&worktrees, name)
+            .ok_or_else(|| TwinError::not_found("Worktree", name))?
+            .clone()],
+        (None, true) => worktrees[1..].to_vec(),
+        (None, false) => {
+            return Err(TwinError::invalid_argument(
+                "Specify a worktree or --all to sync",
+            ))
+        }
+    };
+
+    let symlink_manager = create_symlink_manager();
+    let twin_dir = git.get_twin_dir()?;
+    let project_root = project_root(&git)?;
+    let mut dry_run_plan = Plan::new("sync");
+    // ドライランでは標準出力を実行計画のみにする（JSONとして読めるように）
+    let note = |message: String| {
+        if args.dry_run.is_some() {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    };
+    let mut failures = 0;
+    for worktree in &targets {
+        let name = worktree
+            .branch
+            .strip_prefix("refs/heads/")
+            .map(String::from)
+            .unwrap_or_else(|| worktree.path.display().to_string());
+        if worktree.path == main.path {
+            note(format!("- {name}: メインのワークツリーは同期の対象外です"));
+            continue;
+        }
+        if worktree.prunable || !worktree.path.exists() {
+            note(format!(
+                "- {name}: ディレクトリが存在しません（`twin prune` で管理情報と残った副作用を削除できます）"
+            ));
+            continue;
+        }
+
+        // マニフェストがないワークツリーでは、削除対象のリンクを未追跡のパスから探す
+        let manifest = Manifest::load(&twin_dir, &worktree.path)?;
+        let untracked = if args.prune && manifest.is_none() {
+            git.list_untracked_paths(&worktree.path)?
+        } else {
+            Vec::new()
+        };
+        let plan = plan_sync(
+            &worktree.path,
+            &project_root,
+            &config,
+            manifest.as_ref(),
+            &untracked,
+            args.prune,
+            symlink_manager.as_ref(),
+        )?;
+
+        if args.dry_run.is_some() {
+            for action in &plan.actions {
+                let (action_kind, source) = match action {
+                    SyncAction::Create { mapping, .. } => {
+                        let mapping = &config.settings.files[*mapping];
+                        (
+                            MappingAction::for_type(&mapping.mapping_type),
+                            Some(project_root.join(&mapping.source)),
+                        )
+                    }
+                    SyncAction::Relink { mapping, .. } => (
+                        MappingAction::Relink,
+                        Some(project_root.join(&config.settings.files[*mapping].source)),
+                    ),
+                    SyncAction::Remove { target } => {
+                        (MappingAction::Unlink, std::fs::read_link(target).ok())
+                    }
+                };
+                dry_run_plan.mapping(action_kind, source.as_deref(), action.target(), None);
+            }
+            for (mapping, source) in &plan.missing_sources {
+                let mapping = &config.settings.files[*mapping];
+                dry_run_plan.mapping(
+                    MappingAction::for_type(&mapping.mapping_type),
+                    Some(source),
+                    &worktree.path.join(mapping.target_path()),
+                    Some("source not found"),
+                );
+            }
+            continue;
+        }
+
+        for (_, source) in &plan.missing_sources {
+            eprintln!(
+                "⚠️  {name}: ソースが存在しないためスキップします: {}",
+                source.display()
+            );
+        }
+        if plan.is_empty() {
+            if !args.quiet {
+                println!("✓ {name}: 同期済みです");
+            }
+        } else if !args.quiet {
+            for action in &plan.actions {
+                let (mark, label) = match action {
+                    SyncAction::Create { .. } => ("+", "作成"),
+                    SyncAction::Relink { .. } => ("~", "再作成"),
+                    SyncAction::Remove { .. } => ("-", "削除"),
+                };
+                let target = action.target();
+                let target = target.strip_prefix(&worktree.path).unwrap_or(target);
+                println!("{mark} {name}: {label}: {}", target.display());
+            }
+        }
+
+        if !plan.is_empty() {
+            let context = worktree_hook_context(&mut git, worktree, &project_root);
+            let variables = template::context_variables(&context);
+            for (action, error) in apply_sync(
+                &plan,
+                &project_root,
+                &config,
+                symlink_manager.as_ref(),
+                &variables,
+            ) {
+                eprintln!("✗ {name}: {}: {error}", action.target().display());
+                failures += 1;
+            }
+        }
+
+        // 同期後の状態を記録し、remove・statusが現在の設定ではなく適用済みの状態を参照できるようにする
+        let branch = worktree.branch.trim_start_matches("refs/heads/");
+        record_sync(
+            manifest,
+            &worktree.path,
+            branch,
+            &project_root,
+            &config,
+            symlink_manager.as_ref(),
+        )?
+        .save(&twin_dir)?;
+    }
+
+    if let Some(format) = args.dry_run {
+        return dry_run_plan.print(format);
+    }
+    if failures > 0 {
+        return Err(TwinError::other(format!(
+            "{failures} item(s) could not be synced"
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn handle_prune(args: PruneArgs) -> TwinResult<()> {
+    let git = crate::git::GitManager::new(std::path::Path::new("."))?;
+    handle_prune_with(args, git).await
+}
+
+/// 指定した`GitManager`（とそのバックエンド）で存在しないworktreeを整理する
+pub async fn handle_prune_with(args: PruneArgs, mut git: crate::git::GitManager) -> TwinResult<()> {
+    use crate::git::PrunedWorktree;
+    use crate::hooks::{HookContext, HookExecutor, HookType};
+    use crate::manifest::same_worktree;
+    use crate::plan::MappingAction;
+    use crate::prune::{clean_orphan, find_orphans};
+    use crate::symlink::create_symlink_manager;
+
+    let config = Config::load(args.config.as_deref()).await?;
+    let _lock = if args.dry_run.is_some() {
+        None
+    } else {
+        Some(lock_repository(&mut git).await?)
+    };
+    let worktrees = git.list_worktrees()?;
+    let Some(main) = worktrees.first().cloned() else {
+        return Err(TwinError::not_found("Worktree", "main"));
+    };
+
+    // git worktree pruneで削除される管理情報（ドライランではgitのドライランで削除対象のみ取得する）
+    // 絞り込み条件を指定した場合は、一致するprunableなワークツリーだけをgit worktree removeで削除する
+    let mut plan = Plan::new("prune");
+    let filtered = args.filter.has_criteria();
+    let pruned = if filtered {
+        let pruned: Vec<_> = select_worktrees(&mut git, &config, &args.filter, &[])?
+            .into_iter()
+            .map(|(_, entry)| entry.worktree)
+            .filter(|worktree| worktree.prunable)
+            .map(|worktree| PrunedWorktree {
+                id: worktree
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                branch: worktree
+                    .branch
+                    .strip_prefix("refs/heads/")
+                    .map(String::from),
+                path: Some(worktree.path),
+                reason: "directory is missing".to_string(),
+            })
+            .collect();
+        git.set_dry_run(args.dry_run.is_some());
+        for entry in &pruned {
+            if let Some(path) = &entry.path {
+                git.remove_worktree(path, false)?;
+            }
+        }
+        pruned
+    } else {
+        git.prune_worktrees(args.dry_run.is_some())?
+    };
+    if args.dry_run.is_some() {
+        if !filtered {
+            git.set_dry_run(true);
+            git.prune_worktrees(false)?;
+        }
+        plan.git_commands(&mut git);
+    }
+    for entry in &pruned {
+        let path = entry
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| entry.id.clone());
+        let branch = entry
+            .branch
+            .as_ref()
+            .map(|branch| format!(" [{branch}]"))
+            .unwrap_or_default();
+        if args.dry_run.is_some() {
+            let path = entry
+                .path
+                .clone()
+                .unwrap_or_else(|| entry.id.clone().into());
+            plan.remove(&path, format!("worktree metadata: {}", entry.reason));
+        } else if !args.quiet {
+            println!("✓ 管理情報: {path}{branch} ({})", entry.reason);
+        }
+    }
+
+    // pruneの後もgitが管理しているワークツリー以外の副作用は孤立している
+    let known: Vec<_> = worktrees
+        .iter()
+        .map(|w| w.path.clone())
+        .filter(|path| {
+            !pruned
+                .iter()
+                .any(|entry| entry.path.as_deref() == Some(path.as_path()))
+        })
+        .collect();
+    let twin_dir = git.get_twin_dir()?;
+    let mut orphans = find_orphans(&twin_dir, &known)?;
+    if filtered {
+        // 絞り込み条件を指定した場合は、今回削除したワークツリーの副作用だけを片付ける
+        orphans.retain(|orphan| {
+            pruned.iter().any(|entry| {
+                entry
+                    .path
+                    .as_deref()
+                    .is_some_and(|path| same_worktree(path, &orphan.worktree))
+            })
+        });
+    }
+
+    let mut hook_executor = HookExecutor::new();
+    hook_executor.set_quiet(args.quiet);
+    hook_executor.set_dry_run(args.dry_run.is_some());
+    let symlink_manager = create_symlink_manager();
+    let mut failures = 0;
+    for orphan in &orphans {
+        // 削除されたワークツリーと同様にpost_removeフックを実行（失敗しても警告のみ）
+        let branch = orphan.branch.clone().unwrap_or_else(|| orphan.name());
+        let mut context = HookContext::new(
+            branch.clone(),
+            orphan.worktree.clone(),
+            branch,
+            main.path.clone(),
+        );
+        if let Ok(base_branch) = git.get_current_branch() {
+            context.set_base_branch(base_branch);
+        }
+        if args.dry_run.is_some() {
+            plan.hooks(
+                &hook_executor,
+                HookType::PostRemove,
+                &config.settings.hooks.post_remove,
+                &context,
+            )?;
+            for link in &orphan.links {
+                let source = std::fs::read_link(link).ok();
+                plan.mapping(MappingAction::Unlink, source.as_deref(), link, None);
+            }
+            if let Some(manifest) = &orphan.manifest {
+                plan.remove(manifest, "manifest");
+            }
+            if let Some(log) = &orphan.log {
+                plan.remove(log, "hook log");
+            }
+            continue;
+        }
+        for hook in &config.settings.hooks.post_remove {
+            match hook_executor.execute(HookType::PostRemove, hook, &context) {
+                Ok(result) if result.timed_out => eprintln!(
+                    "Warning: Post-remove hook timed out after {}s: {}",
+                    hook.timeout, hook.command
+                ),
+                Ok(result) if !result.success => {
+                    eprintln!("Warning: Post-remove hook failed: {}", hook.command)
                 }
+                Ok(_) => {}
                 Err(e) => eprintln!("Warning: Post-remove hook failed: {e}"),
             }
         }
+
+        match clean_orphan(orphan, symlink_manager.as_ref()) {
+            Ok(()) => {
+                if !args.quiet {
+                    println!(
+                        "✓ 孤立した副作用: {} ({})",
+                        orphan.worktree.display(),
+                        orphan.description()
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {e}", orphan.worktree.display());
+                failures += 1;
+            }
+        }
     }
 
-    println!("✓ Worktree '{}' を削除しました", path.display());
+    if let Some(format) = args.dry_run {
+        return plan.print(format);
+    }
+    if pruned.is_empty() && orphans.is_empty() && !args.quiet {
+        println!("削除対象はありません");
+    }
+    if failures > 0 {
+        return Err(TwinError::other(format!(
+            "{failures} orphan(s) could not be cleaned up"
+        )));
+    }
 
     Ok(())
 }
 
-pub async fn handle_config(args: ConfigArgs) -> TwinResult<()> {
+/// 対象のワークツリーの状態を収集する（マッピングのソースはメインのワークツリーから解決する）
+fn collect_statuses(
+    git: &mut crate::git::GitManager,
+    config: &Config,
+    worktrees: &[crate::git::WorktreeInfo],
+    targets: &[crate::git::WorktreeInfo],
+    base: Option<&str>,
+) -> TwinResult<Vec<crate::status::WorktreeStatus>> {
+    use crate::manifest::Manifest;
+    use crate::status::collect_status;
+    use crate::symlink::create_symlink_manager;
+
+    // git worktree listの最初のエントリーがメインのワークツリー
+    let main = worktrees.first();
+    let project_root = match main {
+        Some(main) => main.path.clone(),
+        None => std::env::current_dir()?,
+    };
+
+    let symlink_manager = create_symlink_manager();
+    let twin_dir = git.get_twin_dir()?;
+    let mut statuses = Vec::with_capacity(targets.len());
+    for worktree in targets {
+        let is_main = main.is_some_and(|main| main.path == worktree.path);
+        let manifest = Manifest::load(&twin_dir, &worktree.path)?;
+        statuses.push(collect_status(
+            git,
+            worktree,
+            is_main,
+            base,
+            config,
+            &project_root,
+            manifest.as_ref(),
+            symlink_manager.as_ref(),
+        )?);
+    }
+
+    Ok(statuses)
+}
+
+/// 表示用のワークツリー名（ブランチ名、detached HEADの場合はディレクトリ名）
+fn worktree_display_name(status: &crate::status::WorktreeStatus) -> String {
+    status.branch.clone().unwrap_or_else(|| {
+        status
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| status.path.display().to_string())
+    })
+}
+
+/// 既存のワークツリーのテンプレート変数・フック用のコンテキストを作成
+fn worktree_hook_context(
+    git: &mut crate::git::GitManager,
+    worktree: &crate::git::WorktreeInfo,
+    project_root: &std::path::Path,
+) -> crate::hooks::HookContext {
+    let branch = worktree
+        .branch
+        .trim_start_matches("refs/heads/")
+        .to_string();
+    let mut context = crate::hooks::HookContext::new(
+        branch.clone(),
+        worktree.path.clone(),
+        branch,
+        project_root.to_path_buf(),
+    );
+    context.set_commit(worktree.commit.clone());
+    if let Ok(base_branch) = git.get_current_branch() {
+        context.set_base_branch(base_branch);
+    }
+    context
+}
+
+/// ブランチ名・ディレクトリ名・パスのいずれかでワークツリーを探す
+fn find_worktree<'a>(
+    worktrees: &'a [crate::git::WorktreeInfo],
+    name: &str,
+) -> Option<&'a crate::git::WorktreeInfo> {
+    worktrees.iter().find(|w| {
+        w.branch.trim_start_matches("refs/heads/") == name
+            || w.path.file_name().map(|n| n.to_string_lossy()) == Some(name.into())
+            || w.path.to_string_lossy() == name
+    })
+}
+
+pub async fn handle_logs(args: LogsArgs) -> TwinResult<()> {
+    use crate::git::GitManager;
+    use crate::hook_log::HookLog;
     use std::path::PathBuf;
 
-    // 設定ファイルのパスを決定
-    let config_path = PathBuf::from(".twin.toml");
+    let mut git = GitManager::new(std::path::Path::new("."))?;
+
+    // 既存のworktreeはブランチ名でも指定できる。削除済みの場合は名前をそのまま使う
+    let worktrees = git.list_worktrees()?;
+    let found = find_worktree(&worktrees, &args.worktree).map(|w| w.path.clone());
+    let path = found
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(&args.worktree));
+
+    // 同名のworktreeのログ（以前の形式のログを含む）から、パスが分かる場合はそのworktreeのものだけを表示する
+    let twin_dir = git.get_twin_dir()?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| args.worktree.clone());
+    let exact = found.is_some() || path.components().count() > 1;
+    let mut entries = Vec::new();
+    for log in HookLog::find_by_name(&twin_dir, &name)? {
+        entries.extend(log.read()?);
+    }
+    entries.sort_by_key(|entry| entry.timestamp);
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| !exact || crate::manifest::same_worktree(&entry.worktree, &path))
+        .filter(|entry| {
+            args.hook_type
+                .is_none_or(|hook_type| entry.hook_type == hook_type.as_str())
+        })
+        .filter(|entry| !args.failed || !entry.success)
+        .collect();
 
+    if entries.is_empty() {
+        println!(
+            "フックの実行ログはありません: {}",
+            HookLog::for_worktree(&twin_dir, &path).path().display()
+        );
+        return Ok(());
+    }
+
+    for entry in entries {
+        let mark = if entry.success { "✓" } else { "✗" };
+        let status = if entry.timed_out {
+            "timed out".to_string()
+        } else {
+            match entry.exit_code {
+                Some(code) => format!("exit {code}"),
+                None => "killed".to_string(),
+            }
+        };
+        println!(
+            "{mark} {} [{}] {} ({status}, {}ms)",
+            entry
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            entry.hook_type,
+            entry.command,
+            entry.duration_ms
+        );
+        for (label, output) in [("stdout", &entry.stdout), ("stderr", &entry.stderr)] {
+            if output.trim().is_empty() {
+                continue;
+            }
+            println!("    {label}:");
+            for line in output.lines() {
+                println!("      {line}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn handle_recover(args: RecoverArgs) -> TwinResult<()> {
+    use crate::git::GitManager;
+    use crate::journal::Journal;
+
+    let mut git = GitManager::new(std::path::Path::new("."))?;
+    let journal = Journal::new(&git.get_twin_dir()?);
+    let _lock = if args.finish || args.rollback {
+        Some(lock_repository(&mut git).await?)
+    } else {
+        None
+    };
+
+    // 対象の操作を決定（--allでは実行中の操作を除外する）
+    let entries = match &args.id {
+        Some(id) => {
+            let entry = journal.find(id)?;
+            if entry.is_running() && (args.finish || args.rollback) {
+                return Err(TwinError::config(
+                    format!(
+                        "Operation {} is still running (pid {})",
+                        entry.id, entry.pid
+                    ),
+                    None,
+                ));
+            }
+            vec![entry]
+        }
+        None if args.finish || args.rollback => {
+            if !args.all {
+                return Err(TwinError::invalid_argument(
+                    "Specify an operation id or --all to recover",
+                ));
+            }
+            journal
+                .list()?
+                .into_iter()
+                .filter(|entry| !entry.is_running())
+                .collect()
+        }
+        None => journal.list()?,
+    };
+
+    // アクションが指定されていない場合は一覧を表示
+    if !args.finish && !args.rollback {
+        if entries.is_empty() {
+            println!("中断された操作はありません");
+            return Ok(());
+        }
+        for entry in &entries {
+            print_journal_entry(entry, args.id.is_some());
+        }
+        if args.id.is_none() {
+            println!();
+            println!("`twin recover <ID> --finish` で再開、`--rollback` で元に戻せます");
+        }
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("中断された操作はありません");
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for entry in entries {
+        let id = entry.id.clone();
+        if args.rollback {
+            let report = crate::rollback::rollback(&entry.state, &mut git);
+            print_rollback_report(&entry.state, &report);
+            if report.is_clean() {
+                journal.discard(&id)?;
+                println!("✓ 操作 {id} をロールバックしました");
+            } else {
+                eprintln!("✗ 操作 {id} の一部を元に戻せませんでした");
+                failures += 1;
+            }
+            continue;
+        }
+
+        match finish_operation(&journal, entry, &mut git, args.quiet).await {
+            Ok(()) => println!("✓ 操作 {id} を完了しました"),
+            Err(e) => {
+                eprintln!("✗ 操作 {id} を完了できませんでした: {e}");
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(TwinError::other(format!(
+            "{failures} operation(s) could not be recovered"
+        )));
+    }
+
+    Ok(())
+}
+
+/// 中断された操作の残りのステップを実行する（失敗した場合はジャーナルを残す）
+async fn finish_operation(
+    journal: &crate::journal::Journal,
+    entry: crate::journal::JournalEntry,
+    git: &mut crate::git::GitManager,
+    quiet: bool,
+) -> TwinResult<()> {
+    use crate::core::OperationType;
+    use crate::hook_log::HookLog;
+    use crate::hooks::{HookContext, HookExecutor};
+
+    let config = Config::load(entry.config_path.as_deref()).await?;
+
+    let mut hook_executor = HookExecutor::new();
+    hook_executor.set_quiet(quiet);
+    hook_executor.set_log(HookLog::for_worktree(&git.get_twin_dir()?, &entry.worktree));
+    let mut hook_context = HookContext::new(
+        entry.branch.clone(),
+        entry.worktree.clone(),
+        entry.branch.clone(),
+        entry.project_root.clone(),
+    );
+    if let Some(commit) = &entry.commit {
+        hook_context.set_commit(commit.clone());
+    }
+    if let Some(base_branch) = &entry.base_branch {
+        hook_context.set_base_branch(base_branch.clone());
+    }
+
+    let mut operation = journal.resume(entry)?;
+    let result = match operation.state().operation {
+        OperationType::CreateEnvironment => run_add_steps(
+            &mut operation,
+            &config,
+            git,
+            &hook_executor,
+            &hook_context,
+            quiet,
+        )
+        .map(|_| ()),
+        OperationType::RemoveEnvironment => run_remove_steps(
+            &mut operation,
+            &config,
+            git,
+            &hook_executor,
+            &hook_context,
+            quiet,
+        ),
+        _ => Err(TwinError::config(
+            format!(
+                "Operation '{}' cannot be resumed",
+                operation.entry.operation_name()
+            ),
+            None,
+        )),
+    };
+    result?;
+    operation.complete()
+}
+
+/// ジャーナルの記録を表示
+fn print_journal_entry(entry: &crate::journal::JournalEntry, show_steps: bool) {
+    let status = if entry.is_running() {
+        format!("実行中 (pid {})", entry.pid)
+    } else {
+        "中断".to_string()
+    };
+    println!(
+        "🔧 {} [{}] {} ({status})",
+        entry.id,
+        entry.operation_name(),
+        entry.branch
+    );
+    println!("    worktree: {}", entry.worktree.display());
+    println!(
+        "    開始: {}  完了済みステップ: {}",
+        entry
+            .started_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        entry.state.succeeded_steps.len()
+    );
+    if show_steps {
+        for step in &entry.state.succeeded_steps {
+            println!("      ✓ {}", crate::rollback::describe(step));
+        }
+    }
+    if let Some(step) = &entry.state.failed_step {
+        println!(
+            "    失敗: {}: {}",
+            crate::rollback::describe(step),
+            entry.state.error.as_deref().unwrap_or("")
+        );
+    }
+}
+
+pub async fn handle_config(args: ConfigArgs) -> TwinResult<()> {
     // サブコマンドの処理
     if let Some(subcommand) = &args.subcommand {
         match subcommand.as_str() {
@@ -449,22 +2246,27 @@ pub async fn handle_config(args: ConfigArgs) -> TwinResult<()> {
                 println!("# Twin設定ファイル (.twin.toml)");
                 println!("# このファイルをプロジェクトルートに配置してください");
                 println!();
-                println!("# Worktreeのベースディレクトリ（省略時: ../ブランチ名）");
+                println!("# Worktreeのベースディレクトリ（省略時: ./worktrees/ブランチ名）");
                 println!("# worktree_base = \"../workspaces\"");
                 println!();
                 println!("# ファイルマッピング設定");
                 println!("# Worktree作成時に自動的にシンボリックリンクやコピーを作成します");
                 println!("# [[files]]");
-                println!("# path = \".env.template\"          # ソースファイルのパス");
-                println!("# mapping_type = \"copy\"           # \"symlink\" または \"copy\"");
+                println!("# source = \".env.template\"        # ソースファイルのパス");
+                println!("# target = \".env\"                 # 配置先（省略時: sourceと同じ）");
+                println!("# mapping_type = \"copy\"           # \"symlink\"、\"copy\" または \"template\"");
                 println!("# description = \"環境変数設定\"     # 説明（省略可）");
                 println!("# skip_if_exists = true           # 既存ファイルをスキップ（省略可）");
                 println!();
                 println!("# [[files]]");
-                println!("# path = \".claude/config.json\"");
+                println!("# source = \".claude/config.json\"");
                 println!("# mapping_type = \"symlink\"");
                 println!();
                 println!("# フック設定（環境作成・削除時に実行するコマンド）");
+                println!("# {{name}} {{path}} {{branch}} {{worktree_name}} {{repo_name}} {{commit}} {{base_branch}} {{hook_type}}");
+                println!(
+                    "# または ${{AGENT_NAME}} ${{WORKTREE_PATH}} ${{BRANCH}} などが使用できます"
+                );
                 println!("[hooks]");
                 println!("# pre_create = [");
                 println!("#   {{ command = \"echo\", args = [\"Creating: {{branch}}\"] }}");
@@ -487,13 +2289,20 @@ pub async fn handle_config(args: ConfigArgs) -> TwinResult<()> {
     }
 
     if args.show {
-        // 現在の設定を表示
-        if config_path.exists() {
-            let config = Config::from_path(&config_path)?;
-            println!("{config:#?}");
-        } else {
-            println!("設定ファイルが見つかりません: {}", config_path.display());
+        // 現在の設定を表示（グローバル設定とプロジェクト設定のマージ結果）
+        let config = Config::load(None).await?;
+        match (&config.global_path, &config.path) {
+            (None, None) => println!("設定ファイルが見つかりません（デフォルト設定を使用）"),
+            (global, project) => {
+                if let Some(global) = global {
+                    println!("# グローバル設定: {}", global.display());
+                }
+                if let Some(project) = project {
+                    println!("# プロジェクト設定: {}", project.display());
+                }
+            }
         }
+        println!("{config:#?}");
     } else if let Some(set_value) = args.set {
         // 設定値をセット (key=value形式)
         let parts: Vec<&str> = set_value.splitn(2, '=').collect();
@@ -509,13 +2318,9 @@ pub async fn handle_config(args: ConfigArgs) -> TwinResult<()> {
         println!("注: この機能は現在実装中です");
     } else if let Some(key) = args.get {
         // 設定値を取得
-        if config_path.exists() {
-            let _config = Config::from_path(&config_path)?;
-            println!("キー '{key}' の値を取得します");
-            println!("注: この機能は現在実装中です");
-        } else {
-            println!("設定ファイルが見つかりません: {}", config_path.display());
-        }
+        let _config = Config::load(None).await?;
+        println!("キー '{key}' の値を取得します");
+        println!("注: この機能は現在実装中です");
     } else {
         println!("使用方法:");
         println!("  twin config default         : デフォルト設定をTOML形式で出力");
@@ -532,11 +2337,11 @@ pub async fn handle_init(args: InitArgs) -> TwinResult<()> {
     // TODO(human): Add interactive mode support here
     // When args.interactive is true, prompt user for:
     // - worktree_base (default: "./worktrees")
-    // - branch_prefix (default: "agent/")
+    // - branch_prefix (default: "agent")
     // Then pass these values to Config::init_with_options() or similar
 
-    // config::Config::init()を呼び出して設定ファイルを作成
-    let config_path = crate::config::Config::init(args.path, args.force).await?;
+    // Config::init()を呼び出して設定ファイルを作成
+    let config_path = Config::init(args.path, args.force).await?;
 
     println!("✅ 設定ファイルを作成しました: {}", config_path.display());
     println!();
@@ -549,3 +2354,253 @@ pub async fn handle_init(args: InitArgs) -> TwinResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::GitManager;
+    use crate::git_backend::FakeBackend;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    /// メインのworktree・設定ファイル・`.env`を持つテスト用のディレクトリ
+    fn setup(config: &str) -> (TempDir, PathBuf, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("repo");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(".env"), "A=1").unwrap();
+        let config_path = temp.path().join("twin.toml");
+        fs::write(&config_path, config).unwrap();
+        (temp, root, config_path)
+    }
+
+    fn git(root: &Path, fake: &FakeBackend) -> GitManager {
+        GitManager::with_backend(root, Box::new(fake.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_sync_creates_mappings_from_project_root() {
+        let (temp, root, config_path) = setup(
+            r#"
+[[files]]
+path = ".env"
+mapping_type = "copy"
+"#,
+        );
+        let fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("feature");
+        git(&root, &fake)
+            .add_worktree_with_options(&["-b", "feature", &worktree.to_string_lossy()])
+            .unwrap();
+
+        let args = SyncArgs {
+            worktree: Some("feature".to_string()),
+            all: false,
+            prune: false,
+            config: Some(config_path),
+            quiet: true,
+            dry_run: None,
+        };
+        handle_sync_with(args, git(&root, &fake)).await.unwrap();
+
+        assert_eq!(fs::read_to_string(worktree.join(".env")).unwrap(), "A=1");
+    }
+
+    #[tokio::test]
+    async fn test_repair_restores_mappings_from_project_root() {
+        let (temp, root, config_path) = setup(
+            r#"
+[[files]]
+path = ".env"
+mapping_type = "copy"
+"#,
+        );
+        let fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("feature");
+        git(&root, &fake)
+            .add_worktree_with_options(&["-b", "feature", &worktree.to_string_lossy()])
+            .unwrap();
+
+        let args = RepairArgs {
+            worktree: Some("feature".to_string()),
+            all: false,
+            config: Some(config_path),
+            quiet: true,
+        };
+        handle_repair_with(args, git(&root, &fake)).await.unwrap();
+
+        assert_eq!(fs::read_to_string(worktree.join(".env")).unwrap(), "A=1");
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_with_fake_backend() {
+        let (temp, root, config_path) = setup(
+            r#"
+branch_prefix = "agent"
+
+[[files]]
+path = ".env"
+mapping_type = "copy"
+"#,
+        );
+        let fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("claude");
+
+        let args = AddArgs {
+            branch: "claude".to_string(),
+            path: Some(worktree.clone()),
+            config: Some(config_path.clone()),
+            quiet: true,
+            ..Default::default()
+        };
+        handle_add_with(args, git(&root, &fake)).await.unwrap();
+
+        assert!(fake.state().branches.contains_key("agent/claude"));
+        assert_eq!(
+            fake.state().calls,
+            vec![format!(
+                "worktree add -b agent/claude --quiet {}",
+                worktree.display()
+            )]
+        );
+        assert_eq!(fs::read_to_string(worktree.join(".env")).unwrap(), "A=1");
+
+        let args = RemoveArgs {
+            worktree: Some(worktree.to_string_lossy().to_string()),
+            force: true,
+            config: Some(config_path),
+            quiet: true,
+            ..Default::default()
+        };
+        handle_remove_with(args, git(&root, &fake)).await.unwrap();
+
+        assert_eq!(fake.state().worktrees.len(), 1);
+        assert!(!worktree.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_rollback_keeps_file_that_replaced_symlink() {
+        let (temp, root, config_path) = setup(
+            r#"
+[[files]]
+path = ".env"
+
+[[files]]
+path = ".other"
+"#,
+        );
+        fs::write(root.join(".other"), "B=2").unwrap();
+        let fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("feature");
+
+        let args = AddArgs {
+            branch: "feature".to_string(),
+            path: Some(worktree.clone()),
+            config: Some(config_path.clone()),
+            quiet: true,
+            ..Default::default()
+        };
+        handle_add_with(args, git(&root, &fake)).await.unwrap();
+        assert!(worktree.join(".env").is_symlink());
+
+        // ユーザーがリンクを通常のファイルに置き換え、worktreeの削除はロックで失敗する
+        fs::remove_file(worktree.join(".env")).unwrap();
+        fs::write(worktree.join(".env"), "user edits").unwrap();
+        fake.state().worktrees[1].locked = true;
+
+        let args = RemoveArgs {
+            worktree: Some(worktree.to_string_lossy().to_string()),
+            yes: true,
+            config: Some(config_path),
+            quiet: true,
+            ..Default::default()
+        };
+        assert!(handle_remove_with(args, git(&root, &fake)).await.is_err());
+
+        // 削除したリンクだけが作成し直され、置き換えられたファイルは残る
+        assert!(worktree.join(".other").is_symlink());
+        assert!(!worktree.join(".env").is_symlink());
+        assert_eq!(
+            fs::read_to_string(worktree.join(".env")).unwrap(),
+            "user edits"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_rolls_back_when_post_create_hook_fails() {
+        let (temp, root, config_path) = setup(
+            r#"
+[hooks]
+post_create = [{ command = "false" }]
+"#,
+        );
+        let fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("feature");
+
+        let args = AddArgs {
+            branch: "feature".to_string(),
+            path: Some(worktree.clone()),
+            config: Some(config_path),
+            quiet: true,
+            ..Default::default()
+        };
+        assert!(handle_add_with(args, git(&root, &fake)).await.is_err());
+
+        // 作成したworktreeとブランチは削除される
+        let state = fake.state();
+        assert_eq!(state.worktrees.len(), 1);
+        assert!(!state.branches.contains_key("feature"));
+        assert!(!worktree.exists());
+    }
+
+    #[tokio::test]
+    async fn test_add_rolls_back_when_worktree_lookup_fails() {
+        let (temp, root, config_path) = setup("");
+        let fake = FakeBackend::new(&root);
+        fake.state().fail_list_worktrees = true;
+        let worktree = temp.path().join("feature");
+
+        let args = AddArgs {
+            branch: "feature".to_string(),
+            path: Some(worktree.clone()),
+            config: Some(config_path),
+            quiet: true,
+            ..Default::default()
+        };
+        assert!(handle_add_with(args, git(&root, &fake)).await.is_err());
+
+        // 作成直後の確認に失敗しても、worktreeを削除してからブランチを削除する
+        let state = fake.state();
+        assert_eq!(state.worktrees.len(), 1);
+        assert!(!state.branches.contains_key("feature"));
+        assert!(!worktree.exists());
+        assert!(state.calls.ends_with(&[
+            format!("worktree remove --force {}", worktree.display()),
+            "branch -D feature".to_string()
+        ]));
+    }
+
+    #[tokio::test]
+    async fn test_prune_with_fake_backend() {
+        let (temp, root, config_path) = setup("");
+        let fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("gone");
+        git(&root, &fake)
+            .add_worktree_with_options(&["-b", "gone", &worktree.to_string_lossy()])
+            .unwrap();
+        fs::remove_dir_all(&worktree).unwrap();
+
+        let args = PruneArgs {
+            config: Some(config_path),
+            quiet: true,
+            filter: Default::default(),
+            dry_run: None,
+        };
+        handle_prune_with(args, git(&root, &fake)).await.unwrap();
+
+        assert_eq!(fake.state().worktrees.len(), 1);
+        assert!(fake.state().branches.contains_key("gone"));
+    }
+}
@@ -1,8 +1,11 @@
 //! CLIの出力フォーマット機能
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
 use std::path::Path;
 
 use crate::git::WorktreeInfo;
+use crate::list::{format_age, format_size, ListColumn, ListEntry};
+use crate::status::WorktreeStatus;
 
 /// 出力フォーマッタークラス
 pub struct OutputFormatter {
@@ -16,8 +19,12 @@ impl OutputFormatter {
         Self { format }
     }
 
-    pub fn format_worktrees(&self, worktrees: &[WorktreeInfo]) -> Result<()> {
-        format_worktrees(worktrees, &self.format)
+    pub fn format_worktrees(&self, entries: &[ListEntry], columns: &[ListColumn]) -> Result<()> {
+        format_worktrees(entries, columns, &self.format)
+    }
+
+    pub fn format_statuses(&self, statuses: &[WorktreeStatus]) -> Result<()> {
+        format_statuses(statuses, &self.format)
     }
 }
 
@@ -62,152 +69,339 @@ pub fn format_path_output(path: &Path, show_cd_command: bool) -> Result<()> {
 }
 
 /// Worktree一覧を指定されたフォーマットで出力
-pub fn format_worktrees(worktrees: &[WorktreeInfo], format: &OutputFormat) -> Result<()> {
+pub fn format_worktrees(
+    entries: &[ListEntry],
+    columns: &[ListColumn],
+    format: &OutputFormat,
+) -> Result<()> {
     match format {
-        OutputFormat::Table => format_worktrees_table(worktrees),
-        OutputFormat::Json => format_worktrees_json(worktrees),
-        OutputFormat::Simple => format_worktrees_simple(worktrees),
+        OutputFormat::Table => format_worktrees_table(entries, columns),
+        OutputFormat::Json => format_worktrees_json(entries),
+        OutputFormat::Simple => format_worktrees_simple(entries),
     }
 }
 
 /// Worktreeをテーブル形式で出力
-fn format_worktrees_table(worktrees: &[WorktreeInfo]) -> Result<()> {
-    if worktrees.is_empty() {
+fn format_worktrees_table(entries: &[ListEntry], columns: &[ListColumn]) -> Result<()> {
+    if entries.is_empty() {
         println!("No worktrees found.");
         return Ok(());
     }
 
     // メインリポジトリと作業ツリーを分けて表示
-    // 通常、最初のエントリーがメインリポジトリ（bare でない限り）
-    let (main_repo, work_trees) = if !worktrees.is_empty() {
-        // 最初のエントリーか、現在のディレクトリと同じパスのものをメインリポジトリとする
-        let current_dir = std::env::current_dir().ok();
-        let is_main = |w: &WorktreeInfo| -> bool {
-            if let Some(ref cwd) = current_dir {
-                // 現在のディレクトリと一致するか確認
-                w.path.canonicalize().ok() == cwd.canonicalize().ok()
-            } else {
-                false
-            }
-        };
+    // 現在のディレクトリと同じパスのものをメインリポジトリとし、一致するものがなければ最初のエントリーとする
+    let current_dir = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| cwd.canonicalize().ok());
+    let main_idx = entries
+        .iter()
+        .position(|e| current_dir.is_some() && e.worktree.path.canonicalize().ok() == current_dir)
+        .unwrap_or(0);
+    let main = &entries[main_idx].worktree;
+    let work_trees: Vec<&ListEntry> = entries
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != main_idx)
+        .map(|(_, entry)| entry)
+        .collect();
 
-        if let Some(main_idx) = worktrees.iter().position(is_main) {
-            let mut all: Vec<WorktreeInfo> = worktrees.to_vec();
-            let main = all.remove(main_idx);
-            (Some(main), all)
+    // メインリポジトリの表示
+    println!("📁 Main Repository");
+    println!(
+        "  Branch: {}",
+        if main.branch.is_empty() {
+            "(no branch)"
         } else {
-            // 現在のディレクトリと一致するものがない場合、最初のエントリーをメインとする
-            let mut all: Vec<WorktreeInfo> = worktrees.to_vec();
-            if !all.is_empty() {
-                let main = all.remove(0);
-                (Some(main), all)
-            } else {
-                (None, vec![])
-            }
+            &main.branch
         }
-    } else {
-        (None, vec![])
-    };
-
-    // メインリポジトリの表示
-    if let Some(main) = main_repo {
-        println!("📁 Main Repository");
-        println!(
-            "  Branch: {}",
-            if main.branch.is_empty() {
-                "(no branch)"
-            } else {
-                &main.branch
-            }
-        );
-        println!("  Path:   {}", main.path.to_string_lossy());
-        println!("  Commit: {}", &main.commit[..8.min(main.commit.len())]);
-        println!();
+    );
+    println!("  Path:   {}", main.path.to_string_lossy());
+    println!("  Commit: {}", &main.commit[..8.min(main.commit.len())]);
+    if main.upstream.is_some() {
+        println!("  Upstream: {}", upstream_display(main));
     }
+    println!();
 
     // ワークツリーの表示
     if !work_trees.is_empty() {
+        let width = columns
+            .iter()
+            .map(|c| c.width() + 1)
+            .sum::<usize>()
+            .max(100);
         println!("🌲 Work Trees");
-        println!("{}", "-".repeat(80));
+        println!("{}", "-".repeat(width));
 
         // ヘッダー
-        println!(
-            "{:<30} {:<10} {:<12} {:<30}",
-            "Branch", "Status", "Commit", "Path"
-        );
-        println!("{}", "-".repeat(80));
+        let header: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{:<width$}", c.header(), width = c.width()))
+            .collect();
+        println!("{}", header.join(" ").trim_end());
+        println!("{}", "-".repeat(width));
 
         // Worktree一覧
-        for wt in work_trees.iter() {
-            let status = if wt.locked {
-                "🔒 locked"
-            } else if wt.prunable {
-                "⚠️  prunable"
-            } else {
-                "✓ active"
-            };
-
-            let branch_display = if wt.branch.is_empty() {
-                "(no branch)".to_string()
-            } else if wt.branch.len() > 28 {
-                format!("{}...", &wt.branch[..25])
-            } else {
-                wt.branch.clone()
-            };
-
-            let path_display = {
-                let path_str = wt.path.to_string_lossy();
-                if path_str.len() > 28 {
-                    // パスが長い場合は最後の部分を表示
-                    if let Some(file_name) = wt.path.file_name() {
-                        format!(".../{}", file_name.to_string_lossy())
-                    } else {
-                        format!("...{}", &path_str[path_str.len() - 25..])
-                    }
-                } else {
-                    path_str.to_string()
-                }
-            };
-
-            println!(
-                "{:<30} {:<10} {:<12} {:<30}",
-                branch_display,
-                status,
-                &wt.commit[..8.min(wt.commit.len())],
-                path_display
-            );
+        let now = Local::now();
+        for entry in work_trees.iter() {
+            let row: Vec<String> = columns
+                .iter()
+                .map(|c| format!("{:<width$}", cell(entry, *c, now), width = c.width()))
+                .collect();
+            println!("{}", row.join(" ").trim_end());
         }
 
-        println!("{}", "-".repeat(80));
+        println!("{}", "-".repeat(width));
         println!("Total: {} worktree(s)", work_trees.len());
     }
 
     Ok(())
 }
 
+/// テーブルの1セルの表示
+fn cell(entry: &ListEntry, column: ListColumn, now: DateTime<Local>) -> String {
+    let wt = &entry.worktree;
+    let limit = column.width() - 2;
+    match column {
+        ListColumn::Branch if wt.branch.is_empty() => "(no branch)".to_string(),
+        ListColumn::Branch => truncate(&wt.branch, limit),
+        ListColumn::Agent => truncate(wt.agent_name.as_deref().unwrap_or("-"), limit),
+        ListColumn::Status if wt.locked => "🔒 locked".to_string(),
+        ListColumn::Status if wt.prunable => "⚠️  prunable".to_string(),
+        ListColumn::Status => "✓ active".to_string(),
+        ListColumn::Commit => wt.commit[..8.min(wt.commit.len())].to_string(),
+        ListColumn::Upstream => upstream_display(wt),
+        ListColumn::Path => {
+            let path_str = wt.path.to_string_lossy();
+            match wt.path.file_name() {
+                // パスが長い場合は最後の部分を表示
+                Some(file_name) if path_str.chars().count() > limit => {
+                    format!(".../{}", file_name.to_string_lossy())
+                }
+                _ => truncate(&path_str, limit),
+            }
+        }
+        ListColumn::Dirty => optional(entry.dirty.map(|dirty| dirty.to_string())),
+        ListColumn::Base => optional(
+            entry
+                .base
+                .as_ref()
+                .map(|base| format!("↑{} ↓{}", base.ahead, base.behind)),
+        ),
+        ListColumn::Subject => truncate(entry.last_commit_subject.as_deref().unwrap_or("-"), limit),
+        ListColumn::Updated => optional(wt.last_updated.map(|time| format_age(time, now))),
+        ListColumn::Created => optional(wt.created_at.map(|time| format_age(time, now))),
+        ListColumn::Lock => match &wt.lock_reason {
+            Some(reason) => truncate(reason, limit),
+            None if wt.locked => "locked".to_string(),
+            None => "-".to_string(),
+        },
+        ListColumn::Size => optional(entry.disk_size.map(format_size)),
+    }
+}
+
+/// 値がない場合は`-`を表示
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "-".to_string())
+}
+
+/// 指定した文字数を超える場合は末尾を`...`にして切り詰める
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(limit.saturating_sub(3)).collect();
+    format!("{kept}...")
+}
+
+/// 上流ブランチと差分の表示（例: origin/main ↑1 ↓2）
+fn upstream_display(wt: &WorktreeInfo) -> String {
+    let Some(upstream) = &wt.upstream else {
+        return "-".to_string();
+    };
+    if wt.upstream_gone {
+        return format!("{upstream} (gone)");
+    }
+
+    let mut display = upstream.clone();
+    if wt.ahead > 0 {
+        display.push_str(&format!(" ↑{}", wt.ahead));
+    }
+    if wt.behind > 0 {
+        display.push_str(&format!(" ↓{}", wt.behind));
+    }
+    display
+}
+
 /// WorktreeをJSON形式で出力
-fn format_worktrees_json(worktrees: &[WorktreeInfo]) -> Result<()> {
-    let json = serde_json::to_string_pretty(worktrees)?;
+fn format_worktrees_json(entries: &[ListEntry]) -> Result<()> {
+    let json = serde_json::to_string_pretty(entries)?;
     println!("{json}");
     Ok(())
 }
 
 /// Worktreeをシンプル形式で出力
-fn format_worktrees_simple(worktrees: &[WorktreeInfo]) -> Result<()> {
-    for wt in worktrees {
-        if wt.branch.is_empty() {
+fn format_worktrees_simple(entries: &[ListEntry]) -> Result<()> {
+    for entry in entries {
+        if entry.worktree.branch.is_empty() {
             println!("(no branch)");
         } else {
-            println!("{}", wt.branch);
+            println!("{}", entry.worktree.branch);
         }
     }
     Ok(())
 }
 
+/// Worktreeの状態を指定されたフォーマットで出力
+pub fn format_statuses(statuses: &[WorktreeStatus], format: &OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Table => format_statuses_table(statuses),
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(statuses)?);
+            Ok(())
+        }
+        OutputFormat::Simple => {
+            for status in statuses {
+                println!("{}", status_summary_line(status));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Worktreeの状態をテーブル形式で出力
+fn format_statuses_table(statuses: &[WorktreeStatus]) -> Result<()> {
+    if statuses.is_empty() {
+        println!("No worktrees found.");
+        return Ok(());
+    }
+
+    for (index, status) in statuses.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        let icon = if status.is_main { "📁" } else { "🌲" };
+        println!(
+            "{icon} {}",
+            status.branch.as_deref().unwrap_or("(detached HEAD)")
+        );
+        println!("  Path:     {}", status.path.display());
+        println!("  HEAD:     {}", &status.head[..8.min(status.head.len())]);
+
+        if status.prunable {
+            println!("  State:    ⚠️  prunable (directory is missing)");
+            continue;
+        }
+        if status.git_link_broken {
+            println!("  State:    ⚠️  .git link is broken (run `twin repair`)");
+        }
+        if status.config_outdated {
+            println!("  Config:   ⚠️  changed since mappings were applied (run `twin sync`)");
+        }
+        if status.is_dirty() {
+            println!(
+                "  Changes:  {} changed, {} untracked",
+                status.changed, status.untracked
+            );
+        } else {
+            println!("  Changes:  clean");
+        }
+        if let Some(upstream) = &status.upstream {
+            println!(
+                "  Upstream: {} (↑{} ↓{})",
+                upstream.branch, upstream.ahead, upstream.behind
+            );
+        }
+        if let Some(base) = &status.base {
+            println!(
+                "  Base:     {} (↑{} ↓{})",
+                base.branch, base.ahead, base.behind
+            );
+        }
+        if status.locked {
+            println!("  Lock:     🔒 locked");
+        }
+
+        if !status.mappings.is_empty() {
+            println!("  Mappings:");
+            for mapping in &status.mappings {
+                let mark = if mapping.health.is_valid() {
+                    "✓"
+                } else {
+                    "✗"
+                };
+                let relative = mapping
+                    .target
+                    .strip_prefix(&status.path)
+                    .unwrap_or(&mapping.target);
+                println!(
+                    "    {mark} {:<8} {:<30} {}",
+                    mapping.mapping_type,
+                    relative.display(),
+                    mapping.health.as_str()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Worktreeの状態を1行にまとめる（simple形式）
+fn status_summary_line(status: &WorktreeStatus) -> String {
+    let state = if status.prunable {
+        "prunable"
+    } else if status.is_dirty() {
+        "dirty"
+    } else {
+        "clean"
+    };
+    let healthy = status.mappings.len() - status.unhealthy_mappings();
+    format!(
+        "{}\t{state}\t{healthy}/{} mappings ok",
+        status.branch.as_deref().unwrap_or("(no branch)"),
+        status.mappings.len()
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_upstream_display() {
+        let mut wt = WorktreeInfo::default();
+        assert_eq!(upstream_display(&wt), "-");
+
+        wt.upstream = Some("origin/feature".to_string());
+        wt.ahead = 2;
+        wt.behind = 1;
+        assert_eq!(upstream_display(&wt), "origin/feature ↑2 ↓1");
+
+        wt.upstream_gone = true;
+        assert_eq!(upstream_display(&wt), "origin/feature (gone)");
+    }
+
+    #[test]
+    fn test_cell_truncates_by_characters() {
+        let now = Local::now();
+        let entry = ListEntry {
+            worktree: WorktreeInfo {
+                branch: "機能/".repeat(12),
+                locked: true,
+                lock_reason: Some("USBドライブ上".to_string()),
+                ..Default::default()
+            },
+            dirty: Some(3),
+            ..Default::default()
+        };
+        let branch = cell(&entry, ListColumn::Branch, now);
+        assert_eq!(branch.chars().count(), ListColumn::Branch.width() - 2);
+        assert!(branch.ends_with("..."));
+        assert_eq!(cell(&entry, ListColumn::Lock, now), "USBドライブ上");
+        assert_eq!(cell(&entry, ListColumn::Dirty, now), "3");
+        assert_eq!(cell(&entry, ListColumn::Size, now), "-");
+    }
+
     #[test]
     fn test_output_format_from_str() {
         assert!(matches!(
@@ -1,100 +1,68 @@
 #![allow(clippy::all)]
 #![allow(dead_code)]
 /// 設定管理モジュール
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+///
+/// 設定モデルは`crate::core::Config`に一本化されており、
+/// このモジュールでは設定ファイルの探索・読み込み・保存を担当する。
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use crate::core::{FileMapping, HookCommand, HookConfig, MappingType};
-
-/// アプリケーション全体の設定
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Config {
-    /// Git管理外ファイルのマッピング設定
-    #[serde(default)]
-    pub files: Vec<FileMapping>,
-
-    /// フック設定（環境作成・削除時に実行するコマンド）
-    #[serde(default)]
-    pub hooks: HookConfig,
-
-    /// Worktreeのベースディレクトリ
-    pub worktree_base: Option<PathBuf>,
-
-    /// デフォルトのブランチプレフィックス
-    #[serde(default = "default_branch_prefix")]
-    pub branch_prefix: String,
-}
-
-fn default_branch_prefix() -> String {
-    "agent/".to_string()
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            files: Vec::new(),
-            hooks: HookConfig::default(),
-            worktree_base: Some(PathBuf::from("worktrees")),
-            branch_prefix: default_branch_prefix(),
-        }
-    }
-}
+pub use crate::core::Config;
+use crate::core::{
+    ConfigSettings, FileMapping, HookCommand, HookConfig, MappingType, TwinError, TwinResult,
+};
 
 impl Config {
-    /// 設定ファイルを読み込む（ファイルが存在しない場合はデフォルト値を返す）
-    pub async fn load(path: Option<&Path>) -> Result<Self> {
-        // パスが指定されていて、ファイルが存在する場合は読み込む
-        if let Some(p) = path {
-            if p.exists() {
-                let content = fs::read_to_string(p)
-                    .await
-                    .with_context(|| format!("Failed to read config file: {}", p.display()))?;
-                return toml::from_str(&content)
-                    .with_context(|| format!("Failed to parse config file: {}", p.display()));
-            }
-        }
-
-        // プロジェクト設定を探す
-        if let Some(config_path) = Self::find_config_path(Path::new(".")).await {
-            let content = fs::read_to_string(&config_path).await.with_context(|| {
-                format!("Failed to read config file: {}", config_path.display())
-            })?;
-            return toml::from_str(&content).with_context(|| {
-                format!("Failed to parse config file: {}", config_path.display())
-            });
-        }
+    /// 階層的に設定を読み込む（グローバル → プロジェクト → 明示的に指定された設定）
+    ///
+    /// すべてのコマンドはこの関数を経由して設定を取得する。
+    /// 後から読み込んだ層の値が優先され、未設定の項目は下位の層から引き継ぐ。
+    pub async fn load(explicit: Option<&Path>) -> TwinResult<Self> {
+        let global = Self::global_config_path().ok().filter(|p| p.exists());
+        let start = std::env::current_dir()?;
+        let project = Self::find_config_path(&start).await;
+
+        Self::load_layers(global.as_deref(), project.as_deref(), explicit)
+    }
 
-        // グローバル設定を試す
-        if let Ok(global_path) = Self::global_config_path() {
-            if global_path.exists() {
-                let content = fs::read_to_string(&global_path).await.with_context(|| {
-                    format!("Failed to read config file: {}", global_path.display())
-                })?;
-                return toml::from_str(&content).with_context(|| {
-                    format!("Failed to parse config file: {}", global_path.display())
-                });
-            }
+    /// 指定された各層の設定ファイルを読み込んでマージする
+    ///
+    /// 明示的に指定されたファイルが存在しない場合はエラーとする。
+    pub fn load_layers(
+        global: Option<&Path>,
+        project: Option<&Path>,
+        explicit: Option<&Path>,
+    ) -> TwinResult<Self> {
+        let mut settings = ConfigSettings::default();
+        for path in [global, project, explicit].into_iter().flatten() {
+            settings = settings.merge(Self::from_path(path)?.settings);
         }
 
-        // デフォルト設定を返す
-        Ok(Self::default())
+        Ok(Self {
+            settings,
+            path: explicit.or(project).map(Path::to_path_buf),
+            global_path: global.map(Path::to_path_buf),
+        })
     }
 
     /// 設定ファイルを保存
-    pub async fn save(&self, path: &Path) -> Result<()> {
-        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
+    pub async fn save(&self, path: &Path) -> TwinResult<()> {
+        let content = toml::to_string_pretty(&self.settings)?;
 
         // 親ディレクトリが存在しない場合は作成
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).await?;
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).await?;
+            }
         }
 
-        fs::write(path, content)
-            .await
-            .with_context(|| format!("Failed to write config file: {}", path.display()))
+        fs::write(path, content).await.map_err(|e| {
+            TwinError::config(
+                format!("Failed to write config file: {e}"),
+                Some(path.to_path_buf()),
+            )
+        })
     }
 
     /// デフォルト設定ファイルを作成
@@ -102,25 +70,31 @@ impl Config {
         let mut env_vars = HashMap::new();
         env_vars.insert("NODE_ENV".to_string(), "production".to_string());
 
-        Self {
+        let settings = ConfigSettings {
             files: vec![
                 FileMapping {
-                    path: PathBuf::from(".env"),
+                    source: PathBuf::from(".env"),
+                    target: None,
                     mapping_type: MappingType::Symlink,
                     description: Some("環境変数ファイル（共有）".to_string()),
                     skip_if_exists: false,
+                    variables: HashMap::new(),
                 },
                 FileMapping {
-                    path: PathBuf::from(".env.local"),
+                    source: PathBuf::from(".env.local"),
+                    target: None,
                     mapping_type: MappingType::Copy,
                     description: Some("ローカル環境変数（各環境で独立）".to_string()),
                     skip_if_exists: false,
+                    variables: HashMap::new(),
                 },
                 FileMapping {
-                    path: PathBuf::from(".vscode/settings.local.json"),
+                    source: PathBuf::from(".vscode/settings.local.json"),
+                    target: None,
                     mapping_type: MappingType::Symlink,
                     description: Some("VS Codeローカル設定".to_string()),
                     skip_if_exists: true,
+                    variables: HashMap::new(),
                 },
             ],
             hooks: HookConfig {
@@ -151,7 +125,14 @@ impl Config {
                 post_remove: vec![],
             },
             worktree_base: Some(PathBuf::from("./worktrees")),
-            branch_prefix: "agent/".to_string(),
+            branch_prefix: Some(crate::core::types::DEFAULT_BRANCH_PREFIX.to_string()),
+            branch_template: None,
+        };
+
+        Self {
+            settings,
+            path: None,
+            global_path: None,
         }
     }
 
@@ -159,22 +140,9 @@ impl Config {
     pub fn merge(global: Self, project: Self) -> Self {
         // プロジェクト設定を優先し、未設定の項目はグローバル設定を使用
         Self {
-            files: if !project.files.is_empty() {
-                project.files
-            } else {
-                global.files
-            },
-            hooks: if project.hooks != HookConfig::default() {
-                project.hooks
-            } else {
-                global.hooks
-            },
-            worktree_base: project.worktree_base.or(global.worktree_base),
-            branch_prefix: if project.branch_prefix != default_branch_prefix() {
-                project.branch_prefix
-            } else {
-                global.branch_prefix
-            },
+            settings: global.settings.merge(project.settings),
+            path: project.path,
+            global_path: global.path.or(global.global_path),
         }
     }
 
@@ -202,22 +170,25 @@ impl Config {
     }
 
     /// グローバル設定ファイルのパスを取得
-    pub fn global_config_path() -> Result<PathBuf> {
+    pub fn global_config_path() -> TwinResult<PathBuf> {
         let proj_dirs = directories::ProjectDirs::from("com", "twin", "twin")
-            .context("Failed to get project directories")?;
+            .ok_or_else(|| TwinError::config("Failed to get project directories", None))?;
         Ok(proj_dirs.config_dir().join("config.toml"))
     }
 
     /// 設定ファイルを初期化（twin initコマンド用）
-    pub async fn init(path: Option<PathBuf>, force: bool) -> Result<PathBuf> {
+    pub async fn init(path: Option<PathBuf>, force: bool) -> TwinResult<PathBuf> {
         let config_path = path.unwrap_or_else(|| PathBuf::from("twin.toml"));
 
         // ファイルが既に存在する場合
         if config_path.exists() && !force {
-            anyhow::bail!(
-                "Config file already exists: {}. Use --force to overwrite.",
-                config_path.display()
-            );
+            return Err(TwinError::config(
+                format!(
+                    "Config file already exists: {}. Use --force to overwrite.",
+                    config_path.display()
+                ),
+                Some(config_path),
+            ));
         }
 
         // デフォルト設定を作成
@@ -225,7 +196,7 @@ impl Config {
         // ユーザーが参考にできるようにする
         let config = if cfg!(test) {
             // テスト時はシンプルなデフォルト設定を使用
-            Self::default()
+            Self::new()
         } else {
             // 本番環境では実用的なサンプル設定を使用
             Self::example()
@@ -242,16 +213,16 @@ mod tests {
 
     #[test]
     fn test_default_config() {
-        let config = Config::default();
-        assert_eq!(config.branch_prefix, "agent/");
-        assert!(config.files.is_empty());
+        let config = Config::new();
+        assert_eq!(config.settings.branch_prefix(), "agent");
+        assert!(config.settings.files.is_empty());
     }
 
     #[test]
     fn test_example_config() {
-        let config = Config::example();
+        let config = Config::example().settings;
         assert!(!config.files.is_empty());
-        assert_eq!(config.files[0].path, PathBuf::from(".env"));
+        assert_eq!(config.files[0].source, PathBuf::from(".env"));
         assert_eq!(config.files[0].mapping_type, MappingType::Symlink);
         assert_eq!(config.files[1].mapping_type, MappingType::Copy);
         assert!(!config.hooks.post_create.is_empty());
@@ -260,7 +231,7 @@ mod tests {
     #[test]
     fn test_hook_command_example() {
         let config = Config::example();
-        let first_hook = &config.hooks.post_create[0];
+        let first_hook = &config.settings.hooks.post_create[0];
         assert_eq!(first_hook.command, "echo");
         assert_eq!(first_hook.timeout, 60);
         assert!(!first_hook.continue_on_error);
@@ -285,7 +256,7 @@ mod tests {
 
         // ファイルの内容を読み込んで解析できることを確認
         let content = tokio::fs::read_to_string(&config_path).await.unwrap();
-        let _config: Config = toml::from_str(&content).unwrap();
+        let _config: ConfigSettings = toml::from_str(&content).unwrap();
     }
 
     #[tokio::test]
@@ -331,7 +302,7 @@ mod tests {
         // ファイルが新しい設定で上書きされたことを確認
         let content = tokio::fs::read_to_string(&config_path).await.unwrap();
         assert!(!content.starts_with("# custom content"));
-        let _config: Config = toml::from_str(&content).unwrap();
+        let _config: ConfigSettings = toml::from_str(&content).unwrap();
     }
 
     #[tokio::test]
@@ -354,4 +325,180 @@ mod tests {
         // 元のディレクトリに戻す
         env::set_current_dir(original_dir).unwrap();
     }
+
+    #[test]
+    fn test_load_layers_precedence() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let global = temp_dir.path().join("global.toml");
+        let project = temp_dir.path().join("twin.toml");
+        let explicit = temp_dir.path().join("explicit.toml");
+
+        std::fs::write(
+            &global,
+            r#"
+worktree_base = "../global"
+branch_prefix = "global"
+
+[[files]]
+path = ".env"
+
+[hooks]
+pre_create = [{ command = "echo", args = ["global"] }]
+post_create = [{ command = "echo", args = ["global"] }]
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &project,
+            r#"
+worktree_base = "../project"
+
+[hooks]
+post_create = [{ command = "echo", args = ["project"] }]
+"#,
+        )
+        .unwrap();
+        std::fs::write(&explicit, "branch_prefix = \"explicit\"\n").unwrap();
+
+        let config = Config::load_layers(Some(&global), Some(&project), Some(&explicit)).unwrap();
+        let settings = &config.settings;
+
+        assert_eq!(settings.worktree_base, Some(PathBuf::from("../project")));
+        assert_eq!(settings.branch_prefix.as_deref(), Some("explicit"));
+        // プロジェクト側で未定義の項目はグローバル設定を引き継ぐ
+        assert_eq!(settings.files.len(), 1);
+        assert_eq!(settings.hooks.pre_create[0].args, vec!["global"]);
+        assert_eq!(settings.hooks.post_create[0].args, vec!["project"]);
+        assert_eq!(config.path, Some(explicit));
+        assert_eq!(config.global_path, Some(global));
+    }
+
+    #[test]
+    fn test_load_layers_without_files() {
+        let config = Config::load_layers(None, None, None).unwrap();
+        assert!(config.settings.files.is_empty());
+        assert!(config.path.is_none());
+    }
+
+    #[test]
+    fn test_load_layers_missing_explicit_file() {
+        let result = Config::load_layers(None, None, Some(Path::new("/nonexistent/twin.toml")));
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_example_round_trip() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("twin.toml");
+
+        // 保存した設定を読み込むと同じ内容になる
+        let example = Config::example();
+        example.save(&config_path).await.unwrap();
+        let loaded = Config::from_path(&config_path).unwrap();
+        assert_eq!(loaded.settings, example.settings);
+
+        // 探索経由で読み込んだ場合も同じ内容になる
+        let loaded = Config::load_layers(None, Some(&config_path), None).unwrap();
+        assert_eq!(loaded.settings, example.settings);
+    }
+
+    #[test]
+    fn test_merge_covers_every_field() {
+        let global = Config::example();
+        let mut project = Config::new();
+        project.path = Some(PathBuf::from("twin.toml"));
+
+        // プロジェクト側が未設定ならすべての項目をグローバル設定から引き継ぐ
+        let merged = Config::merge(global.clone(), project.clone());
+        assert_eq!(merged.settings, global.settings);
+        assert_eq!(merged.path, Some(PathBuf::from("twin.toml")));
+
+        // プロジェクト側で設定した項目はすべて優先される
+        project.settings = ConfigSettings {
+            files: vec![FileMapping {
+                source: PathBuf::from("local.json"),
+                target: None,
+                mapping_type: MappingType::Copy,
+                description: None,
+                skip_if_exists: false,
+                variables: HashMap::new(),
+            }],
+            hooks: HookConfig {
+                pre_create: global.settings.hooks.pre_remove.clone(),
+                post_create: vec![],
+                pre_remove: vec![],
+                post_remove: global.settings.hooks.post_create.clone(),
+            },
+            worktree_base: Some(PathBuf::from("../project")),
+            branch_prefix: Some("project".to_string()),
+            branch_template: Some("{prefix}{name}-{seq}".to_string()),
+        };
+        let merged = Config::merge(global.clone(), project.clone());
+        assert_eq!(merged.settings.files, project.settings.files);
+        assert_eq!(
+            merged.settings.hooks.pre_create,
+            project.settings.hooks.pre_create
+        );
+        assert_eq!(
+            merged.settings.hooks.post_create,
+            global.settings.hooks.post_create
+        );
+        assert_eq!(
+            merged.settings.hooks.pre_remove,
+            global.settings.hooks.pre_remove
+        );
+        assert_eq!(
+            merged.settings.hooks.post_remove,
+            project.settings.hooks.post_remove
+        );
+        assert_eq!(
+            merged.settings.worktree_base,
+            Some(PathBuf::from("../project"))
+        );
+        assert_eq!(merged.settings.branch_prefix(), "project");
+        assert_eq!(
+            merged.settings.branch_template.as_deref(),
+            Some("{prefix}{name}-{seq}")
+        );
+    }
+
+    #[test]
+    fn test_shipped_examples_parse() {
+        for (name, content) in [
+            ("twin.toml.example", include_str!("../twin.toml.example")),
+            (
+                "twin.toml.example.en",
+                include_str!("../twin.toml.example.en"),
+            ),
+            (
+                "twin.toml.example.ja",
+                include_str!("../twin.toml.example.ja"),
+            ),
+        ] {
+            let settings: ConfigSettings =
+                toml::from_str(content).unwrap_or_else(|e| panic!("{name} should parse: {e}"));
+            assert!(!settings.files.is_empty(), "{name} should define files");
+        }
+
+        let settings: ConfigSettings =
+            toml::from_str(include_str!("../twin.toml.example.en")).unwrap();
+        let env = settings
+            .files
+            .iter()
+            .find(|f| f.source == Path::new(".env.template"))
+            .unwrap();
+        assert_eq!(env.target_path(), Path::new(".env"));
+        assert_eq!(env.mapping_type, MappingType::Copy);
+
+        let template = settings
+            .files
+            .iter()
+            .find(|f| f.mapping_type == MappingType::Template)
+            .unwrap();
+        assert_eq!(template.variables["environment"], "{{agent_name}}");
+    }
 }
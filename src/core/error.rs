@@ -66,6 +66,18 @@ pub enum TwinError {
         exit_code: Option<i32>,
     },
 
+    /// フックのタイムアウト
+    #[error("Hook timed out after {timeout_secs}s: {command}")]
+    HookTimeout {
+        command: String,
+        hook_type: String,
+        timeout_secs: u64,
+    },
+
+    /// フックの実行中にシグナル（Ctrl-Cなど）で中断された
+    #[error("Interrupted by signal {signal}")]
+    Interrupted { signal: i32 },
+
     /// 既に存在するエラー
     #[error("{resource} already exists: {name}")]
     AlreadyExists { resource: String, name: String },
@@ -227,6 +239,29 @@ impl TwinError {
         }
     }
 
+    /// フックのタイムアウトエラーを作成
+    pub fn hook_timeout(
+        command: impl Into<String>,
+        hook_type: impl Into<String>,
+        timeout_secs: u64,
+    ) -> Self {
+        Self::HookTimeout {
+            command: command.into(),
+            hook_type: hook_type.into(),
+            timeout_secs,
+        }
+    }
+
+    /// シグナルによる中断エラーを作成
+    pub fn interrupted(signal: i32) -> Self {
+        Self::Interrupted { signal }
+    }
+
+    /// シグナルで中断されたかどうか（continue_on_errorのフックでも処理を中断する）
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self, Self::Interrupted { .. })
+    }
+
     /// 無効な引数エラーを作成
     pub fn invalid_argument(message: impl Into<String>) -> Self {
         Self::InvalidArgument {
@@ -246,7 +281,10 @@ impl TwinError {
 
     /// エラーが致命的かどうかを判定
     pub fn is_fatal(&self) -> bool {
-        !matches!(self, Self::Hook { .. } | Self::Lock { .. })
+        !matches!(
+            self,
+            Self::Hook { .. } | Self::HookTimeout { .. } | Self::Lock { .. }
+        )
     }
 }
 
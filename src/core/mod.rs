@@ -2,4 +2,7 @@ pub mod error;
 pub mod types;
 
 pub use error::{TwinError, TwinResult};
-pub use types::{Config, FileMapping, HookCommand, HookConfig, MappingType, SymlinkInfo};
+pub use types::{
+    Config, ConfigSettings, FileMapping, HookCommand, HookConfig, MappingType, OperationStep,
+    OperationType, PartialFailureState, SymlinkInfo, DEFAULT_BRANCH_PREFIX,
+};
@@ -76,8 +76,9 @@ impl Config {
         let settings = ConfigSettings {
             files: vec![],                // 空のファイルリスト
             hooks: HookConfig::default(), // すべて空のフック
-            worktree_base: None,          // デフォルト: ../ブランチ名
+            worktree_base: None,          // デフォルト: ./worktrees/ブランチ名
             branch_prefix: None,          // 削除済み機能
+            branch_template: None,
         };
 
         Self {
@@ -107,7 +108,10 @@ impl Config {
 }
 
 /// 設定の実際の内容
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// 省略された項目はNoneまたは空のままとし、階層的な設定のマージ時に
+/// 下位の設定を引き継げるようにする。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct ConfigSettings {
     /// Git管理外ファイルの定義
     #[serde(default)]
@@ -118,36 +122,61 @@ pub struct ConfigSettings {
     pub hooks: HookConfig,
 
     /// Worktreeのベースディレクトリ
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub worktree_base: Option<PathBuf>,
 
-    /// デフォルトのブランチプレフィックス
-    #[serde(default = "default_branch_prefix")]
+    /// デフォルトのブランチプレフィックス（省略時: "agent"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub branch_prefix: Option<String>,
-}
 
-fn default_branch_prefix() -> Option<String> {
-    Some("agent".to_string())
+    /// twin addで作成するブランチ名のテンプレート（省略時: "{prefix}{name}"）
+    ///
+    /// `{prefix}`・`{name}`・`{date}`・`{user}`・`{seq}`を使用できる。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_template: Option<String>,
 }
 
-impl Default for ConfigSettings {
-    fn default() -> Self {
-        Self {
-            files: Vec::new(),
-            hooks: HookConfig::default(),
-            worktree_base: Some(PathBuf::from("worktrees")),
-            branch_prefix: Some("agent".to_string()),
+/// branch_prefixが未設定の場合に使用するプレフィックス
+pub const DEFAULT_BRANCH_PREFIX: &str = "agent";
+
+impl ConfigSettings {
+    /// 有効なブランチプレフィックスを取得
+    pub fn branch_prefix(&self) -> &str {
+        self.branch_prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_BRANCH_PREFIX)
+    }
+
+    /// 上位の設定（`overlay`）を優先してマージする
+    ///
+    /// `overlay`で設定されていない項目は`self`の値を引き継ぐ。
+    pub fn merge(self, overlay: ConfigSettings) -> ConfigSettings {
+        ConfigSettings {
+            files: if overlay.files.is_empty() {
+                self.files
+            } else {
+                overlay.files
+            },
+            hooks: self.hooks.merge(overlay.hooks),
+            worktree_base: overlay.worktree_base.or(self.worktree_base),
+            branch_prefix: overlay.branch_prefix.or(self.branch_prefix),
+            branch_template: overlay.branch_template.or(self.branch_template),
         }
     }
 }
 
 /// Git管理外ファイルのマッピング定義
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FileMapping {
-    /// ファイルパス（メインリポジトリとワークツリーの両方で同じパス）
-    pub path: PathBuf,
+    /// メインリポジトリ側のパス（旧形式の`path`キーも受け付ける）
+    #[serde(alias = "path")]
+    pub source: PathBuf,
 
-    /// マッピングタイプ（symlink or copy）
+    /// ワークツリー側のパス（省略時はsourceと同じパス）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<PathBuf>,
+
+    /// マッピングタイプ（symlink, copy or template）
     #[serde(default = "default_mapping_type")]
     pub mapping_type: MappingType,
 
@@ -158,6 +187,17 @@ pub struct FileMapping {
     /// 既に存在する場合はスキップ
     #[serde(default)]
     pub skip_if_exists: bool,
+
+    /// テンプレート展開時に追加する変数（mapping_type = "template" の場合のみ使用）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+}
+
+impl FileMapping {
+    /// ワークツリー側のパスを取得
+    pub fn target_path(&self) -> &Path {
+        self.target.as_deref().unwrap_or(&self.source)
+    }
 }
 
 /// マッピングタイプ
@@ -168,6 +208,19 @@ pub enum MappingType {
     Symlink,
     /// ファイルコピー（各環境で独立）
     Copy,
+    /// テンプレートを展開してファイルを生成（各環境で独立）
+    Template,
+}
+
+impl MappingType {
+    /// マッピングタイプを文字列として取得（設定ファイルでの表記）
+    pub fn as_str(&self) -> &str {
+        match self {
+            MappingType::Symlink => "symlink",
+            MappingType::Copy => "copy",
+            MappingType::Template => "template",
+        }
+    }
 }
 
 fn default_mapping_type() -> MappingType {
@@ -194,6 +247,26 @@ pub struct HookConfig {
     pub post_remove: Vec<HookCommand>,
 }
 
+impl HookConfig {
+    /// フックの種類ごとに、上位の設定（`overlay`）で定義されたものを優先してマージする
+    pub fn merge(self, overlay: HookConfig) -> HookConfig {
+        fn pick(base: Vec<HookCommand>, overlay: Vec<HookCommand>) -> Vec<HookCommand> {
+            if overlay.is_empty() {
+                base
+            } else {
+                overlay
+            }
+        }
+
+        HookConfig {
+            pre_create: pick(self.pre_create, overlay.pre_create),
+            post_create: pick(self.post_create, overlay.post_create),
+            pre_remove: pick(self.pre_remove, overlay.pre_remove),
+            post_remove: pick(self.post_remove, overlay.post_remove),
+        }
+    }
+}
+
 /// フックコマンドの定義
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct HookCommand {
@@ -267,6 +340,67 @@ pub struct OperationStep {
     pub can_rollback: bool,
 }
 
+impl PartialFailureState {
+    /// 新しい操作の状態を作成
+    pub fn new(operation: OperationType) -> Self {
+        Self {
+            operation,
+            succeeded_steps: Vec::new(),
+            failed_step: None,
+            can_rollback: false,
+            error: None,
+        }
+    }
+
+    /// 成功したステップを記録
+    pub fn record(&mut self, step: OperationStep) {
+        self.can_rollback |= step.can_rollback;
+        self.succeeded_steps.push(step);
+    }
+
+    /// 失敗したステップとエラーを記録
+    pub fn fail(&mut self, step: OperationStep, error: impl Into<String>) {
+        self.failed_step = Some(step);
+        self.error = Some(error.into());
+    }
+
+    /// 失敗しているかどうか
+    pub fn is_failed(&self) -> bool {
+        self.failed_step.is_some()
+    }
+
+    /// ロールバックの対象となるステップを実行とは逆の順序で取得
+    pub fn rollback_steps(&self) -> impl Iterator<Item = &OperationStep> {
+        self.succeeded_steps
+            .iter()
+            .rev()
+            .filter(|step| step.can_rollback)
+    }
+}
+
+impl OperationStep {
+    /// 新しいステップを作成
+    pub fn new(name: impl Into<String>, can_rollback: bool) -> Self {
+        Self {
+            name: name.into(),
+            details: HashMap::new(),
+            timestamp: Utc::now(),
+            can_rollback,
+        }
+    }
+
+    /// 詳細情報を追加
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+
+    /// 詳細情報を取得
+    pub fn detail(&self, key: &str) -> Option<&str> {
+        self.details.get(key).map(String::as_str)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,7 +419,7 @@ mod tests {
         assert_eq!(config.settings.hooks.post_create.len(), 0);
         assert_eq!(config.settings.hooks.pre_remove.len(), 0);
         assert_eq!(config.settings.hooks.post_remove.len(), 0);
-        assert_eq!(config.settings.branch_prefix, Some("agent".to_string()));
+        assert_eq!(config.settings.branch_prefix(), DEFAULT_BRANCH_PREFIX);
     }
 
     #[test]
@@ -311,7 +445,7 @@ mod tests {
         let config = Config::from_path(temp_file.path()).unwrap();
         assert_eq!(config.settings.branch_prefix, Some("feature".to_string()));
         assert_eq!(config.settings.files.len(), 1);
-        assert_eq!(config.settings.files[0].path, PathBuf::from(".env"));
+        assert_eq!(config.settings.files[0].source, PathBuf::from(".env"));
         assert_eq!(config.settings.files[0].mapping_type, MappingType::Copy);
         assert_eq!(
             config.settings.files[0].description,
@@ -348,8 +482,9 @@ mod tests {
     fn test_config_settings_default() {
         let settings = ConfigSettings::default();
         assert_eq!(settings.files.len(), 0);
-        assert_eq!(settings.branch_prefix, Some("agent".to_string()));
-        assert_eq!(settings.worktree_base, Some(PathBuf::from("worktrees")));
+        assert_eq!(settings.branch_prefix, None);
+        assert_eq!(settings.branch_prefix(), "agent");
+        assert_eq!(settings.worktree_base, None);
 
         // HookConfigもデフォルトで空
         assert_eq!(settings.hooks.pre_create.len(), 0);
@@ -367,13 +502,34 @@ mod tests {
         assert_eq!(mapping.mapping_type, MappingType::Symlink);
     }
 
+    #[test]
+    fn test_file_mapping_source_and_target() {
+        let toml_str = r#"
+            [[files]]
+            source = ".env.template"
+            target = ".env"
+
+            [[files]]
+            path = "config.json"
+        "#;
+
+        let settings: ConfigSettings = toml::from_str(toml_str).unwrap();
+        assert_eq!(settings.files[0].source, PathBuf::from(".env.template"));
+        assert_eq!(settings.files[0].target_path(), Path::new(".env"));
+        // 旧形式のpathはsourceとtargetの両方として扱う
+        assert_eq!(settings.files[1].source, PathBuf::from("config.json"));
+        assert_eq!(settings.files[1].target_path(), Path::new("config.json"));
+    }
+
     #[test]
     fn test_file_mapping_skip_if_exists_default() {
         let mapping = FileMapping {
-            path: PathBuf::from("test.txt"),
+            source: PathBuf::from("test.txt"),
+            target: None,
             mapping_type: MappingType::Symlink,
             description: None,
             skip_if_exists: false, // デフォルトはfalse
+            variables: HashMap::new(),
         };
 
         assert!(!mapping.skip_if_exists);
@@ -382,17 +538,21 @@ mod tests {
     #[test]
     fn test_file_mapping_with_description() {
         let mapping_with = FileMapping {
-            path: PathBuf::from("test.txt"),
+            source: PathBuf::from("test.txt"),
+            target: None,
             mapping_type: MappingType::Copy,
             description: Some("Test file".to_string()),
             skip_if_exists: true,
+            variables: HashMap::new(),
         };
 
         let mapping_without = FileMapping {
-            path: PathBuf::from("test2.txt"),
+            source: PathBuf::from("test2.txt"),
+            target: None,
             mapping_type: MappingType::Symlink,
             description: None,
             skip_if_exists: false,
+            variables: HashMap::new(),
         };
 
         assert_eq!(mapping_with.description, Some("Test file".to_string()));
@@ -456,11 +616,11 @@ mod tests {
 
         let config = Config::from_path(temp_file.path()).expect("Should parse minimal config");
         assert_eq!(config.settings.files.len(), 1);
-        assert_eq!(config.settings.files[0].path, PathBuf::from("test.txt"));
+        assert_eq!(config.settings.files[0].source, PathBuf::from("test.txt"));
         assert_eq!(config.settings.files[0].mapping_type, MappingType::Symlink); // デフォルト
         assert!(config.settings.hooks.pre_create.is_empty());
         assert!(config.settings.hooks.post_create.is_empty());
-        assert_eq!(config.settings.branch_prefix, Some("agent".to_string())); // デフォルト
+        assert_eq!(config.settings.branch_prefix(), "agent"); // デフォルト
     }
 
     #[test]
@@ -503,7 +663,8 @@ mod tests {
         assert!(config.settings.hooks.post_create.is_empty());
         assert!(config.settings.hooks.pre_remove.is_empty());
         assert!(config.settings.hooks.post_remove.is_empty());
-        assert_eq!(config.settings.branch_prefix, Some("agent".to_string()));
+        // 空の設定はデフォルト設定と同一になる
+        assert_eq!(config.settings, ConfigSettings::default());
     }
 
     #[test]
@@ -565,4 +726,24 @@ mod tests {
         assert_eq!(hook.timeout, 60); // デフォルトタイムアウト
         assert!(!hook.continue_on_error); // デフォルトはfalse
     }
+
+    #[test]
+    fn test_partial_failure_rollback_order() {
+        let mut state = PartialFailureState::new(OperationType::CreateEnvironment);
+        assert!(!state.can_rollback);
+
+        state.record(OperationStep::new("pre_create", false));
+        state.record(OperationStep::new("create_branch", true).with_detail("branch", "feature"));
+        state.record(OperationStep::new("add_worktree", true).with_detail("path", "/wt"));
+        state.fail(OperationStep::new("post_create", false), "hook failed");
+
+        assert!(state.is_failed());
+        assert!(state.can_rollback);
+        assert_eq!(state.error.as_deref(), Some("hook failed"));
+
+        // 元に戻せるステップのみ、逆順で返される
+        let names: Vec<_> = state.rollback_steps().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["add_worktree", "create_branch"]);
+        assert_eq!(state.succeeded_steps[1].detail("branch"), Some("feature"));
+    }
 }
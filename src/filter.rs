@@ -0,0 +1,299 @@
+//! ワークツリーの絞り込みモジュール
+//!
+//! このモジュールの役割：
+//! - `twin list`・`twin remove`・`twin prune`で共通の絞り込み条件の定義
+//! - 条件の判定に必要な列（変更数・最終コミット日時）の指定
+//! - 期間（`7d`など）とブランチ名のパターンの解釈
+
+#![allow(dead_code)]
+use crate::core::TwinResult;
+use crate::git::GitManager;
+use crate::list::{ListColumn, ListEntry};
+use chrono::{Duration, Local};
+
+/// ワークツリーの絞り込み条件（指定した条件をすべて満たすものを選択する）
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct WorktreeFilter {
+    /// ロックされているワークツリーのみ
+    #[arg(long)]
+    pub locked: bool,
+
+    /// ディレクトリがなくなっている（prunable）ワークツリーのみ
+    #[arg(long)]
+    pub prunable: bool,
+
+    /// 未コミットの変更か未追跡ファイルがあるワークツリーのみ
+    #[arg(long)]
+    pub dirty: bool,
+
+    /// 指定したブランチにマージ済みのブランチのワークツリーのみ
+    #[arg(long, value_name = "BASE")]
+    pub merged: Option<String>,
+
+    /// エージェント名（ブランチ名テンプレートの`{name}`の部分）が一致するワークツリーのみ
+    #[arg(long, value_name = "NAME")]
+    pub agent: Option<String>,
+
+    /// ブランチ名がパターンに一致するワークツリーのみ（`*`は`/`を含む任意の文字列、`?`は任意の1文字）
+    #[arg(long, value_name = "PATTERN")]
+    pub branch_glob: Option<String>,
+
+    /// 最終コミットが指定した期間（例: 30m, 12h, 7d, 2w）より古いワークツリーのみ
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// メインのワークツリーも対象にする（条件を指定しない場合は常に対象）
+    #[arg(long, overrides_with = "no_main")]
+    pub main: bool,
+
+    /// メインのワークツリーを対象から外す
+    #[arg(long, overrides_with = "main")]
+    pub no_main: bool,
+}
+
+impl WorktreeFilter {
+    /// 絞り込み条件が指定されているか（`--main`・`--no-main`は含まない）
+    pub fn has_criteria(&self) -> bool {
+        self.locked
+            || self.prunable
+            || self.dirty
+            || self.merged.is_some()
+            || self.agent.is_some()
+            || self.branch_glob.is_some()
+            || self.older_than.is_some()
+    }
+
+    /// 条件の判定に`list::collect_entries`で収集しておく必要がある列
+    pub fn required_columns(&self) -> Vec<ListColumn> {
+        let mut columns = Vec::new();
+        if self.dirty {
+            columns.push(ListColumn::Dirty);
+        }
+        if self.older_than.is_some() {
+            columns.push(ListColumn::Updated);
+        }
+        columns
+    }
+
+    /// メインのワークツリーを対象にするか
+    fn includes_main(&self) -> bool {
+        if self.no_main {
+            false
+        } else {
+            self.main || !self.has_criteria()
+        }
+    }
+
+    /// 条件に一致するワークツリーだけを残す（`entries`は先頭がメインのワークツリーの一覧）
+    pub fn apply(
+        &self,
+        git: &mut GitManager,
+        entries: Vec<ListEntry>,
+    ) -> TwinResult<Vec<ListEntry>> {
+        let merged = match &self.merged {
+            Some(base) => Some(git.merged_branches(base)?),
+            None => None,
+        };
+        let now = Local::now();
+
+        Ok(entries
+            .into_iter()
+            .enumerate()
+            .filter(|(index, entry)| {
+                let wt = &entry.worktree;
+                let branch = wt.branch.trim_start_matches("refs/heads/");
+                (*index > 0 || self.includes_main())
+                    && (!self.locked || wt.locked)
+                    && (!self.prunable || wt.prunable)
+                    && (!self.dirty || entry.dirty.is_some_and(|dirty| dirty > 0))
+                    && merged
+                        .as_ref()
+                        .is_none_or(|merged| !wt.branch.is_empty() && merged.contains(&wt.branch))
+                    && self
+                        .agent
+                        .as_ref()
+                        .is_none_or(|agent| wt.agent_name.as_ref() == Some(agent))
+                    && self
+                        .branch_glob
+                        .as_ref()
+                        .is_none_or(|pattern| !branch.is_empty() && glob_match(pattern, branch))
+                    && self.older_than.is_none_or(|age| {
+                        // 表現できないほど長い期間の場合はどのワークツリーも一致しない
+                        now.checked_sub_signed(age).is_some_and(|threshold| {
+                            wt.last_updated.is_some_and(|updated| updated < threshold)
+                        })
+                    })
+            })
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+}
+
+/// 期間の指定（例: 30m, 12h, 7d, 2w）を解釈する
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}' (expected e.g. 30m, 12h, 7d, 2w)"))?;
+    let duration = match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => {
+            return Err(format!(
+                "invalid duration unit in '{value}' (use m, h, d or w)"
+            ));
+        }
+    };
+    duration.ok_or_else(|| format!("duration '{value}' is too large"))
+}
+
+/// `*`（任意の文字列）と`?`（任意の1文字）だけを解釈するパターンマッチ
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // 最後の`*`の位置と、その`*`で読み飛ばした位置を覚えておき、不一致の場合はそこからやり直す
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::WorktreeInfo;
+    use crate::git_backend::FakeBackend;
+    use std::path::Path;
+
+    fn entry(branch: &str, agent: Option<&str>, locked: bool, dirty: usize) -> ListEntry {
+        ListEntry {
+            worktree: WorktreeInfo {
+                branch: format!("refs/heads/{branch}"),
+                agent_name: agent.map(String::from),
+                locked,
+                last_updated: Some(Local::now() - Duration::days(dirty as i64 * 10)),
+                ..Default::default()
+            },
+            dirty: Some(dirty),
+            ..Default::default()
+        }
+    }
+
+    fn branches(filter: &WorktreeFilter) -> Vec<String> {
+        let entries = vec![
+            entry("main", None, false, 0),
+            entry("agent/claude", Some("claude"), true, 1),
+            entry("agent/codex", Some("codex"), false, 0),
+            entry("fix/login", None, false, 2),
+        ];
+        let mut git = GitManager::with_backend(
+            Path::new("/repo"),
+            Box::new(FakeBackend::new(Path::new("/repo"))),
+        );
+        filter
+            .apply(&mut git, entries)
+            .unwrap()
+            .into_iter()
+            .map(|e| {
+                e.worktree
+                    .branch
+                    .trim_start_matches("refs/heads/")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_combines_criteria() {
+        assert_eq!(branches(&WorktreeFilter::default()).len(), 4);
+        let filter = WorktreeFilter {
+            no_main: true,
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter).len(), 3);
+
+        let filter = WorktreeFilter {
+            branch_glob: Some("agent/*".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter), vec!["agent/claude", "agent/codex"]);
+
+        let filter = WorktreeFilter {
+            dirty: true,
+            locked: true,
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter), vec!["agent/claude"]);
+
+        let filter = WorktreeFilter {
+            agent: Some("codex".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter), vec!["agent/codex"]);
+
+        // 条件を指定するとメインのワークツリーは--mainを指定した場合だけ対象になる
+        let filter = WorktreeFilter {
+            older_than: Some(Duration::days(1)),
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter), vec!["agent/claude", "fix/login"]);
+        let filter = WorktreeFilter {
+            branch_glob: Some("ma?n".to_string()),
+            main: true,
+            ..Default::default()
+        };
+        assert_eq!(branches(&filter), vec!["main"]);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::hours(12)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::days(7)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::weeks(2)));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert!(parse_duration("9999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_older_than_beyond_representable_dates_matches_nothing() {
+        let filter = WorktreeFilter {
+            older_than: Some(parse_duration("100000000d").unwrap()),
+            ..Default::default()
+        };
+        assert!(branches(&filter).is_empty());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("agent/*", "agent/claude"));
+        assert!(glob_match("agent/*", "agent/team/claude"));
+        assert!(!glob_match("agent/*", "fix/agent/claude"));
+        assert!(glob_match("*/login*", "fix/login-2"));
+        assert!(glob_match("fix-?", "fix-1"));
+        assert!(!glob_match("fix-?", "fix-10"));
+        assert!(glob_match("*", ""));
+    }
+}
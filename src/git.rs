@@ -6,7 +6,9 @@
 /// - ブランチの作成と管理
 /// - 自動コミット機能の実装
 /// - Gitリポジトリの状態確認
+use crate::branch_name::BranchTemplate;
 use crate::core::{TwinError, TwinResult};
+use crate::git_backend::{spawn_git, CliBackend, GitBackend, Libgit2Backend};
 use chrono::{DateTime, Local};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
@@ -14,7 +16,7 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
 
 /// Worktreeの情報を表す構造体
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WorktreeInfo {
     /// Worktreeのパス
     pub path: PathBuf,
@@ -24,22 +26,37 @@ pub struct WorktreeInfo {
     pub commit: String,
     /// エージェント名（ブランチ名から抽出）
     pub agent_name: Option<String>,
-    /// 作成日時
+    /// 作成日時（`attach_created_at`で付与）
     pub created_at: Option<DateTime<Local>>,
-    /// 最終更新日時
+    /// 最終更新日時（ブランチの最終コミット日時、`attach_branch_info`で付与）
     pub last_updated: Option<DateTime<Local>>,
     /// ロック状態
     pub locked: bool,
+    /// ロックの理由（`git worktree lock --reason`で指定したもの）
+    #[serde(default)]
+    pub lock_reason: Option<String>,
     /// プルーニング可能かどうか
     pub prunable: bool,
+    /// 上流ブランチ名（`attach_branch_info`で付与）
+    #[serde(default)]
+    pub upstream: Option<String>,
+    /// 上流ブランチが削除されているか
+    #[serde(default)]
+    pub upstream_gone: bool,
+    /// 上流ブランチより進んでいるコミット数
+    #[serde(default)]
+    pub ahead: usize,
+    /// 上流ブランチより遅れているコミット数
+    #[serde(default)]
+    pub behind: usize,
 }
 
-/// ブランチの情報を表す構造体
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// ブランチの情報を表す構造体（`git for-each-ref`の結果）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct BranchInfo {
-    /// ブランチ名
+    /// ブランチ名（リモート追跡ブランチは`origin/main`の形式）
     pub name: String,
-    /// リモートブランチ名
+    /// リモート名（リモート追跡ブランチはそのリモート、ローカルブランチは上流ブランチのリモート）
     pub remote: Option<String>,
     /// 現在のブランチかどうか
     pub current: bool,
@@ -48,106 +65,179 @@ pub struct BranchInfo {
     /// 上流ブランチとの差分
     pub ahead: usize,
     pub behind: usize,
+    /// リモート追跡ブランチかどうか
+    #[serde(default)]
+    pub is_remote: bool,
+    /// 上流ブランチ名（例: origin/main）
+    #[serde(default)]
+    pub upstream: Option<String>,
+    /// 上流ブランチが削除されているか
+    #[serde(default)]
+    pub upstream_gone: bool,
+    /// 最終コミットの日時
+    #[serde(default)]
+    pub last_commit_date: Option<DateTime<Local>>,
+    /// ブランチをチェックアウトしているWorktreeのパス
+    #[serde(default)]
+    pub worktree: Option<PathBuf>,
+}
+
+/// Worktreeの作業状態（`git status --porcelain=v2 --branch`の結果）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorktreeGitStatus {
+    /// HEADのコミットハッシュ（コミットがない場合はNone）
+    pub head: Option<String>,
+    /// チェックアウトされているブランチ名（detached HEADの場合はNone）
+    pub branch: Option<String>,
+    /// 上流ブランチ名
+    pub upstream: Option<String>,
+    /// 上流ブランチより進んでいるコミット数
+    pub ahead: usize,
+    /// 上流ブランチより遅れているコミット数
+    pub behind: usize,
+    /// 変更されたファイル数（ステージ済み・未ステージ・コンフリクトを含む）
+    pub changed: usize,
+    /// 未追跡ファイル数
+    pub untracked: usize,
+}
+
+impl WorktreeGitStatus {
+    /// 未コミットの変更または未追跡ファイルがあるか
+    pub fn is_dirty(&self) -> bool {
+        self.changed > 0 || self.untracked > 0
+    }
+}
+
+/// `git worktree prune`で削除された（`--dry-run`では削除される）管理情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrunedWorktree {
+    /// 管理情報のID（`<git common dir>/worktrees/<id>`）
+    pub id: String,
+    /// Worktreeのパス（管理情報から取得できた場合）
+    pub path: Option<PathBuf>,
+    /// チェックアウトされていたブランチ名
+    pub branch: Option<String>,
+    /// 削除の理由（gitの出力）
+    pub reason: String,
 }
 
 /// Git操作を管理する構造体
 pub struct GitManager {
     /// リポジトリのルートパス
     repo_path: PathBuf,
-    /// git2ライブラリのリポジトリインスタンス（オプション）
-    repository: Option<git2::Repository>,
+    /// Worktree・ブランチの操作を行うバックエンド
+    backend: Box<dyn GitBackend>,
     /// 実行履歴の記録
     command_history: Vec<String>,
     /// ドライラン モード
     dry_run: bool,
+    /// ドライランで実行しなかった変更を伴うコマンド
+    planned_commands: Vec<String>,
+    /// エージェント名の抽出に使うブランチ名テンプレート
+    branch_template: BranchTemplate,
 }
 
 impl GitManager {
     /// 新しいGitManagerインスタンスを作成
+    ///
+    /// 読み取り専用の問い合わせはgit2で行い、git2でリポジトリを開けない場合はgitコマンドを使う。
     pub fn new(repo_path: &Path) -> TwinResult<Self> {
-        let repo_path = repo_path.to_path_buf();
+        // gitコマンドが使用可能か確認
+        Self::verify_git_available()?;
 
-        // git2ライブラリを使用してリポジトリを開く
-        let repository = match git2::Repository::open(&repo_path) {
-            Ok(repo) => {
-                info!("Opened Git repository at: {repo_path:?}");
-                Some(repo)
-            }
-            Err(e) => {
-                warn!("Failed to open repository with git2: {e}");
-                // git2で開けない場合でも、gitコマンドは使える可能性があるので続行
-                None
+        // GIT_DIRが指定されている場合、git2では同じリポジトリを開けるとは限らないのでgitコマンドを使う
+        let backend: Box<dyn GitBackend> = if std::env::var_os("GIT_DIR").is_some() {
+            Box::new(CliBackend::new(repo_path))
+        } else {
+            match Libgit2Backend::open(repo_path) {
+                Ok(backend) => {
+                    info!("Opened Git repository at: {repo_path:?}");
+                    Box::new(backend)
+                }
+                Err(e) => {
+                    warn!("Failed to open repository with git2: {e}");
+                    // git2で開けない場合でも、gitコマンドは使える可能性があるので続行
+                    Box::new(CliBackend::new(repo_path))
+                }
             }
         };
 
-        // gitコマンドが使用可能か確認
-        Self::verify_git_available()?;
+        Ok(Self::with_backend(repo_path, backend))
+    }
 
-        Ok(Self {
-            repo_path,
-            repository,
+    /// バックエンドを指定してGitManagerを作成（テストでは`FakeBackend`を渡す）
+    pub fn with_backend(repo_path: &Path, backend: Box<dyn GitBackend>) -> Self {
+        debug!("Using {} backend", backend.name());
+        Self {
+            repo_path: repo_path.to_path_buf(),
+            backend,
             command_history: Vec::new(),
             dry_run: false,
-        })
+            planned_commands: Vec::new(),
+            branch_template: BranchTemplate::default(),
+        }
+    }
+
+    /// 一覧でエージェント名を抽出するブランチ名テンプレートを設定
+    pub fn set_branch_template(&mut self, template: BranchTemplate) {
+        self.branch_template = template;
     }
 
     /// ドライランモードを設定
+    ///
+    /// ドライランでも状態の取得に必要な読み取り専用のコマンドは実行し、
+    /// 変更を伴うコマンドは実行せずに`take_planned_commands`で取得できるよう記録する。
     pub fn set_dry_run(&mut self, dry_run: bool) {
         self.dry_run = dry_run;
     }
 
-    /// gitコマンドが使用可能か確認
-    fn verify_git_available() -> TwinResult<()> {
-        let output = Command::new("git")
-            .arg("--version")
-            .output()
-            .map_err(|e| TwinError::git(format!("Git command not found: {e}")))?;
+    /// ドライランで実行しなかったコマンドを取り出す
+    pub fn take_planned_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.planned_commands)
+    }
 
-        if !output.status.success() {
-            return Err(TwinError::git("Git command failed to execute"));
+    /// ドライランの場合、変更を伴うコマンドを記録して実行結果の代わりを返す
+    fn dry_run_output(&mut self, args: &[&str], command_str: &str) -> Option<Output> {
+        if !self.dry_run || is_read_only(args) {
+            return None;
         }
 
-        Ok(())
+        info!("[DRY RUN] Would execute: {command_str}");
+        if std::env::var("TWIN_VERBOSE").is_ok() || std::env::var("TWIN_DEBUG").is_ok() {
+            eprintln!("📝 ドライラン: {command_str}");
+        }
+        self.planned_commands.push(command_str.to_string());
+        Some(Output {
+            #[cfg(unix)]
+            status: std::os::unix::process::ExitStatusExt::from_raw(0),
+            #[cfg(windows)]
+            status: std::os::windows::process::ExitStatusExt::from_raw(0),
+            stdout: b"[DRY RUN]".to_vec(),
+            stderr: Vec::new(),
+        })
     }
 
-    /// gitコマンドを実行する共通メソッド
-    fn execute_git_command(&mut self, args: &[&str]) -> TwinResult<Output> {
+    /// バックエンドで行う操作を実行履歴に記録する
+    ///
+    /// ドライランで変更を伴う操作の場合は実行せずに記録し、trueを返す。
+    fn skip_in_dry_run(&mut self, args: &[&str]) -> bool {
         let command_str = format!("git {}", args.join(" "));
-        info!("Executing: {command_str}");
         self.command_history.push(command_str.clone());
+        self.dry_run_output(args, &command_str).is_some()
+    }
 
-        // 透明性のあるコマンド実行ログ
-        if std::env::var("TWIN_VERBOSE").is_ok() || std::env::var("TWIN_DEBUG").is_ok() {
-            eprintln!("🔧 実行中: {command_str}");
-        }
-
-        if self.dry_run {
-            info!("[DRY RUN] Would execute: {command_str}");
-            if std::env::var("TWIN_VERBOSE").is_ok() || std::env::var("TWIN_DEBUG").is_ok() {
-                eprintln!("📝 ドライラン: {command_str}");
-            }
-            return Ok(Output {
-                #[cfg(unix)]
-                status: std::os::unix::process::ExitStatusExt::from_raw(0),
-                #[cfg(windows)]
-                status: std::os::windows::process::ExitStatusExt::from_raw(0),
-                stdout: b"[DRY RUN]".to_vec(),
-                stderr: Vec::new(),
-            });
-        }
-
+    /// gitコマンドが使用可能か確認
+    fn verify_git_available() -> TwinResult<()> {
         let output = Command::new("git")
-            .current_dir(&self.repo_path)
-            .args(args)
+            .arg("--version")
             .output()
-            .map_err(|e| TwinError::git(format!("Failed to execute git command: {e}")))?;
+            .map_err(|e| TwinError::git(format!("Git command not found: {e}")))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(TwinError::git(format!("Git command failed: {stderr}")));
+            return Err(TwinError::git("Git command failed to execute"));
         }
 
-        Ok(output)
+        Ok(())
     }
 
     /// Worktreeを追加
@@ -157,7 +247,7 @@ impl GitManager {
         branch: Option<&str>,
         create_branch: bool,
     ) -> TwinResult<WorktreeInfo> {
-        let mut args = vec!["worktree", "add"];
+        let mut args = Vec::new();
 
         // 新しいブランチを作成する場合
         if create_branch {
@@ -178,22 +268,22 @@ impl GitManager {
             }
         }
 
-        let output = self.execute_git_command(&args)?;
-        debug!(
-            "Worktree added: {:?}",
-            String::from_utf8_lossy(&output.stdout)
-        );
+        let output = self.add_worktree_with_options(&args)?;
+        debug!("Worktree added: {output:?}");
 
         // 作成されたWorktreeの情報を取得
         self.get_worktree_info(path)
     }
 
-    /// Worktreeを追加（オプションを直接渡す）
-    pub fn add_worktree_with_options(&mut self, args: &[&str]) -> TwinResult<Output> {
+    /// Worktreeを追加（オプションを直接渡す）し、gitの標準出力を返す
+    pub fn add_worktree_with_options(&mut self, args: &[&str]) -> TwinResult<String> {
         let mut full_args = vec!["worktree", "add"];
         full_args.extend_from_slice(args);
+        if self.skip_in_dry_run(&full_args) {
+            return Ok(String::new());
+        }
 
-        self.execute_git_command_raw(&full_args)
+        self.backend.add_worktree(args)
     }
 
     /// Gitコマンドを実行し、エラーメッセージをそのまま表示
@@ -201,23 +291,11 @@ impl GitManager {
         let command_str = format!("git {}", args.join(" "));
         info!("Executing: {command_str}");
 
-        if self.dry_run {
-            info!("[DRY RUN] Would execute: {command_str}");
-            return Ok(Output {
-                #[cfg(unix)]
-                status: std::os::unix::process::ExitStatusExt::from_raw(0),
-                #[cfg(windows)]
-                status: std::os::windows::process::ExitStatusExt::from_raw(0),
-                stdout: b"[DRY RUN]".to_vec(),
-                stderr: Vec::new(),
-            });
+        if let Some(output) = self.dry_run_output(args, &command_str) {
+            return Ok(output);
         }
 
-        let output = Command::new("git")
-            .args(args)
-            .current_dir(&self.repo_path)
-            .output()
-            .map_err(|e| TwinError::git(format!("{e}")))?;
+        let output = spawn_git(&self.repo_path, args)?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -239,71 +317,38 @@ impl GitManager {
         let path_str = path.to_string_lossy();
         args.push(&path_str);
 
-        self.execute_git_command(&args)?;
-        info!("Worktree removed: {path:?}");
+        if !self.skip_in_dry_run(&args) {
+            self.backend.remove_worktree(path, force)?;
+            info!("Worktree removed: {path:?}");
+        }
 
         Ok(())
     }
 
     /// Worktreeの一覧を取得
     pub fn list_worktrees(&mut self) -> TwinResult<Vec<WorktreeInfo>> {
-        let output = self.execute_git_command(&["worktree", "list", "--porcelain"])?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        self.parse_worktree_list(&stdout)
+        Ok(self
+            .backend
+            .list_worktrees()?
+            .into_iter()
+            .map(|wt| self.with_agent_name(wt))
+            .collect())
     }
 
-    /// Worktreeリストの出力をパース
-    fn parse_worktree_list(&self, output: &str) -> TwinResult<Vec<WorktreeInfo>> {
-        let mut worktrees = Vec::new();
-        let mut current_worktree: Option<WorktreeInfo> = None;
-
-        for line in output.lines() {
-            if line.starts_with("worktree ") {
-                // 前のworktree情報を保存
-                if let Some(wt) = current_worktree.take() {
-                    worktrees.push(wt);
-                }
-
-                // 新しいworktree情報を開始
-                let path = PathBuf::from(line.strip_prefix("worktree ").unwrap());
-                current_worktree = Some(WorktreeInfo {
-                    path,
-                    branch: String::new(),
-                    commit: String::new(),
-                    agent_name: None,
-                    created_at: None,
-                    last_updated: None,
-                    locked: false,
-                    prunable: false,
-                });
-            } else if let Some(ref mut wt) = current_worktree {
-                if line.starts_with("HEAD ") {
-                    wt.commit = line.strip_prefix("HEAD ").unwrap().to_string();
-                } else if line.starts_with("branch ") {
-                    wt.branch = line.strip_prefix("branch ").unwrap().to_string();
-                    // エージェント名を抽出（例: agent/claude -> claude）
-                    if wt.branch.starts_with("agent/") {
-                        wt.agent_name = Some(wt.branch[6..].to_string());
-                    }
-                } else if line == "locked" {
-                    wt.locked = true;
-                } else if line == "prunable" {
-                    wt.prunable = true;
-                }
-            }
-        }
-
-        // 最後のworktree情報を保存
-        if let Some(wt) = current_worktree {
-            worktrees.push(wt);
-        }
-
-        Ok(worktrees)
+    /// ブランチ名テンプレートからエージェント名を抽出して設定（例: agent/claude -> claude）
+    fn with_agent_name(&self, mut worktree: WorktreeInfo) -> WorktreeInfo {
+        let branch = worktree.branch.trim_start_matches("refs/heads/");
+        worktree.agent_name = self.branch_template.extract_name(branch);
+        worktree
     }
 
     /// 特定のWorktreeの情報を取得
     pub fn get_worktree_info(&mut self, path: &Path) -> TwinResult<WorktreeInfo> {
+        // バックエンドが直接探せる場合は一覧の取得を省く
+        if let Some(worktree) = self.backend.find_worktree(path)? {
+            return Ok(self.with_agent_name(worktree));
+        }
+
         let worktrees = self.list_worktrees()?;
 
         // パスを絶対パスに変換して比較
@@ -335,29 +380,102 @@ impl GitManager {
     }
 
     /// プルーニング可能なWorktreeをクリーンアップ
-    pub fn prune_worktrees(&mut self, dry_run: bool) -> TwinResult<Vec<PathBuf>> {
-        let mut args = vec!["worktree", "prune"];
-
+    pub fn prune_worktrees(&mut self, dry_run: bool) -> TwinResult<Vec<PrunedWorktree>> {
+        let mut args = vec!["worktree", "prune", "--verbose"];
         if dry_run {
             args.push("--dry-run");
         }
+        if self.skip_in_dry_run(&args) {
+            return Ok(Vec::new());
+        }
 
-        let output = self.execute_git_command(&args)?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        self.backend.prune_worktrees(dry_run)
+    }
 
-        // プルーニングされたWorktreeのパスを抽出
-        let pruned: Vec<PathBuf> = stdout
-            .lines()
-            .filter_map(|line| {
-                if line.contains("Removing worktrees") {
-                    Some(PathBuf::from(line.rsplit(":").next()?.trim()))
-                } else {
-                    None
-                }
-            })
+    /// Worktreeの管理情報を修復（`git worktree repair`）
+    ///
+    /// `paths`を省略した場合は、メインのリポジトリから各worktreeへのリンクを修復する。
+    pub fn repair_worktrees(&mut self, paths: &[&Path]) -> TwinResult<()> {
+        let path_strs: Vec<String> = paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
             .collect();
+        let mut args = vec!["worktree", "repair"];
+        args.extend(path_strs.iter().map(String::as_str));
+
+        if !self.skip_in_dry_run(&args) {
+            self.backend.repair_worktrees(paths)?;
+            info!("Worktrees repaired: {paths:?}");
+        }
+
+        Ok(())
+    }
+
+    /// Worktreeの`.git`ファイルが存在しないgitディレクトリを指しているか
+    /// （リポジトリを移動した後などに`git worktree repair`が必要になる）
+    pub fn is_worktree_link_broken(&self, path: &Path) -> bool {
+        let Ok(content) = std::fs::read_to_string(path.join(".git")) else {
+            // .gitがディレクトリ（メインのワークツリー）または存在しない
+            return false;
+        };
+        match content.trim().strip_prefix("gitdir: ") {
+            Some(gitdir) => {
+                let gitdir = Path::new(gitdir);
+                !path.join(gitdir).exists()
+            }
+            None => true,
+        }
+    }
+
+    /// Worktreeをロック
+    pub fn lock_worktree(&mut self, path: &Path, reason: Option<&str>) -> TwinResult<()> {
+        let mut args = vec!["worktree", "lock"];
+
+        if let Some(reason) = reason {
+            args.push("--reason");
+            args.push(reason);
+        }
+
+        let path_str = path.to_string_lossy();
+        args.push(&path_str);
+
+        if !self.skip_in_dry_run(&args) {
+            self.backend.lock_worktree(path, reason)?;
+            info!("Worktree locked: {path:?}");
+        }
+
+        Ok(())
+    }
+
+    /// Worktreeのロックを解除
+    pub fn unlock_worktree(&mut self, path: &Path) -> TwinResult<()> {
+        let path_str = path.to_string_lossy();
+        if !self.skip_in_dry_run(&["worktree", "unlock", &path_str]) {
+            self.backend.unlock_worktree(path)?;
+            info!("Worktree unlocked: {path:?}");
+        }
+
+        Ok(())
+    }
 
-        Ok(pruned)
+    /// Worktree内の未コミット変更（未追跡ファイルを含む）の件数を取得
+    pub fn count_changes(&mut self, path: &Path) -> TwinResult<usize> {
+        self.backend.count_changes(path)
+    }
+
+    /// Worktreeの作業状態を取得
+    pub fn get_worktree_status(&mut self, path: &Path) -> TwinResult<WorktreeGitStatus> {
+        self.backend.worktree_status(path)
+    }
+
+    /// Worktree内の未追跡・無視されたパスを取得（ディレクトリは配下をまとめて1件として返す）
+    pub fn list_untracked_paths(&mut self, path: &Path) -> TwinResult<Vec<PathBuf>> {
+        self.backend.list_untracked_paths(path)
+    }
+
+    /// Worktreeの HEAD が指定したブランチに対して進んでいる・遅れているコミット数を取得
+    pub fn ahead_behind(&mut self, path: &Path, base: &str) -> TwinResult<(usize, usize)> {
+        self.backend.ahead_behind(path, base)
     }
 
     /// ブランチを作成
@@ -372,8 +490,10 @@ impl GitManager {
             args.push(start);
         }
 
-        self.execute_git_command(&args)?;
-        info!("Branch created: {branch_name}");
+        if !self.skip_in_dry_run(&args) {
+            self.backend.create_branch(branch_name, start_point)?;
+            info!("Branch created: {branch_name}");
+        }
 
         Ok(())
     }
@@ -390,66 +510,70 @@ impl GitManager {
 
         args.push(branch_name);
 
-        self.execute_git_command(&args)?;
-        info!("Branch deleted: {branch_name}");
+        if !self.skip_in_dry_run(&args) {
+            self.backend.delete_branch(branch_name, force)?;
+            info!("Branch deleted: {branch_name}");
+        }
 
         Ok(())
     }
 
-    /// ブランチの一覧を取得
+    /// ブランチの一覧を取得（`remote`がtrueの場合はリモート追跡ブランチのみ、falseの場合はすべて）
     pub fn list_branches(&mut self, remote: bool) -> TwinResult<Vec<BranchInfo>> {
-        let mut args = vec!["branch", "-v"];
-
-        if remote {
-            args.push("-r");
-        } else {
-            args.push("-a");
-        }
-
-        let output = self.execute_git_command(&args)?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        self.parse_branch_list(&stdout)
+        self.backend.list_branches(remote)
     }
 
-    /// ブランチリストの出力をパース
-    fn parse_branch_list(&self, output: &str) -> TwinResult<Vec<BranchInfo>> {
-        let mut branches = Vec::new();
+    /// ローカルブランチが存在するか確認
+    pub fn branch_exists(&mut self, branch_name: &str) -> TwinResult<bool> {
+        self.backend.branch_exists(branch_name)
+    }
 
-        for line in output.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
+    /// Worktreeにチェックアウトされているブランチの上流・最終コミット日時を付与する
+    pub fn attach_branch_info(&mut self, worktrees: &mut [WorktreeInfo]) -> TwinResult<()> {
+        let branches = self.list_branches(false)?;
+        for worktree in worktrees.iter_mut() {
+            let name = worktree.branch.trim_start_matches("refs/heads/");
+            if let Some(branch) = branches.iter().find(|b| !b.is_remote && b.name == name) {
+                worktree.upstream = branch.upstream.clone();
+                worktree.upstream_gone = branch.upstream_gone;
+                worktree.ahead = branch.ahead;
+                worktree.behind = branch.behind;
+                worktree.last_updated = branch.last_commit_date;
             }
+        }
+        Ok(())
+    }
 
-            let current = line.starts_with('*');
-            let line = if current { &line[2..] } else { line };
-
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
+    /// Worktreeの作成日時（管理ディレクトリの`commondir`ファイルの更新日時）を付与する
+    pub fn attach_created_at(&mut self, worktrees: &mut [WorktreeInfo]) -> TwinResult<()> {
+        let admin_dir = self.get_git_common_dir()?.join("worktrees");
+        for (entry, path) in read_worktree_admin_dir(&admin_dir) {
+            let Some(worktree) = worktrees
+                .iter_mut()
+                .find(|wt| Some(&wt.path) == path.as_ref())
+            else {
                 continue;
+            };
+            // `commondir`は`git worktree add`で作成された後は書き換えられない
+            if let Ok(modified) = entry
+                .join("commondir")
+                .metadata()
+                .and_then(|m| m.modified())
+            {
+                worktree.created_at = Some(DateTime::<Local>::from(modified));
             }
-
-            let name = parts[0].to_string();
-            let commit = parts[1].to_string();
-
-            branches.push(BranchInfo {
-                name,
-                remote: None,
-                current,
-                commit,
-                ahead: 0,
-                behind: 0,
-            });
         }
+        Ok(())
+    }
 
-        Ok(branches)
+    /// 指定したブランチにマージ済みのローカルブランチ（`refs/heads/`から始まる名前）を取得
+    pub fn merged_branches(&mut self, base: &str) -> TwinResult<Vec<String>> {
+        self.backend.merged_branches(base)
     }
 
-    /// ブランチが存在するか確認
-    pub fn branch_exists(&mut self, branch_name: &str) -> TwinResult<bool> {
-        let branches = self.list_branches(false)?;
-        Ok(branches.iter().any(|b| b.name == branch_name))
+    /// コミットの日時と件名を取得
+    pub fn commit_summary(&mut self, commit: &str) -> TwinResult<(DateTime<Local>, String)> {
+        self.backend.commit_summary(commit)
     }
 
     /// ユニークなブランチ名を生成（既存のブランチと重複しないように）
@@ -499,11 +623,24 @@ impl GitManager {
         &self.repo_path
     }
 
-    /// 現在のブランチ名を取得
+    /// 全Worktreeで共有されるGitディレクトリ（通常はメインの.git）を取得
+    pub fn get_git_common_dir(&mut self) -> TwinResult<PathBuf> {
+        self.backend.git_common_dir()
+    }
+
+    /// twinの状態を保存するディレクトリ（<git common dir>/twin）を取得
+    pub fn get_twin_dir(&mut self) -> TwinResult<PathBuf> {
+        Ok(self.get_git_common_dir()?.join("twin"))
+    }
+
+    /// 現在のブランチ名を取得（detached HEADの場合は`HEAD`）
     pub fn get_current_branch(&mut self) -> TwinResult<String> {
-        let output = self.execute_git_command(&["rev-parse", "--abbrev-ref", "HEAD"])?;
-        let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        Ok(branch)
+        self.backend.current_branch()
+    }
+
+    /// リビジョンをコミットハッシュに解決
+    pub fn resolve_commit(&mut self, rev: &str) -> TwinResult<String> {
+        self.backend.resolve_commit(rev)
     }
 
     /// cdコマンド文字列を生成
@@ -657,6 +794,209 @@ alias twr='twin remove'
     }
 }
 
+/// Worktreeの管理ディレクトリ（`<common dir>/worktrees`）の各エントリーと、対応するWorktreeのパス
+pub(crate) fn read_worktree_admin_dir(admin_dir: &Path) -> Vec<(PathBuf, Option<PathBuf>)> {
+    std::fs::read_dir(admin_dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| {
+                    // gitdirファイルにはWorktreeの.gitファイルのパスが書かれている
+                    let path = std::fs::read_to_string(entry.path().join("gitdir"))
+                        .ok()
+                        .and_then(|gitdir| {
+                            Path::new(gitdir.trim()).parent().map(Path::to_path_buf)
+                        });
+                    (entry.path(), path)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `git worktree list --porcelain`の出力をパース（エージェント名は`GitManager`が設定する）
+pub fn parse_worktree_list(output: &str) -> Vec<WorktreeInfo> {
+    let mut worktrees = Vec::new();
+    let mut current_worktree: Option<WorktreeInfo> = None;
+
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            // 前のworktree情報を保存
+            if let Some(wt) = current_worktree.take() {
+                worktrees.push(wt);
+            }
+
+            // 新しいworktree情報を開始
+            current_worktree = Some(WorktreeInfo {
+                path: PathBuf::from(path),
+                ..Default::default()
+            });
+        } else if let Some(ref mut wt) = current_worktree {
+            if let Some(commit) = line.strip_prefix("HEAD ") {
+                wt.commit = commit.to_string();
+            } else if let Some(branch) = line.strip_prefix("branch ") {
+                wt.branch = branch.to_string();
+            } else if line == "locked" {
+                wt.locked = true;
+            } else if let Some(reason) = line.strip_prefix("locked ") {
+                wt.locked = true;
+                wt.lock_reason = Some(reason.to_string());
+            } else if line == "prunable" || line.starts_with("prunable ") {
+                wt.prunable = true;
+            }
+        }
+    }
+
+    // 最後のworktree情報を保存
+    if let Some(wt) = current_worktree {
+        worktrees.push(wt);
+    }
+
+    worktrees
+}
+
+/// `list_branches`で使う`git for-each-ref`のフォーマット（フィールドはNUL区切り）
+pub const BRANCH_FORMAT: &str = "%(refname)%00%(symref)%00%(HEAD)%00%(objectname)%00\
+%(upstream:short)%00%(upstream:remotename)%00%(upstream:track,nobracket)%00\
+%(committerdate:iso-strict)%00%(worktreepath)";
+
+/// `BRANCH_FORMAT`で出力された`git for-each-ref`の結果をパース
+///
+/// シンボリック参照（`origin/HEAD -> origin/main`）は除外する。
+pub fn parse_branch_list(output: &str) -> Vec<BranchInfo> {
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\0').collect();
+            let [refname, symref, head, commit, upstream, upstream_remote, track, date, worktree] =
+                fields[..]
+            else {
+                return None;
+            };
+            if !symref.is_empty() {
+                return None;
+            }
+
+            let (name, is_remote) = if let Some(name) = refname.strip_prefix("refs/heads/") {
+                (name, false)
+            } else {
+                (refname.strip_prefix("refs/remotes/")?, true)
+            };
+            let remote = if is_remote {
+                name.split('/').next().map(String::from)
+            } else {
+                non_empty(upstream_remote)
+            };
+
+            let mut ahead = 0;
+            let mut behind = 0;
+            for part in track.split(", ") {
+                match part.split_once(' ') {
+                    Some(("ahead", count)) => ahead = count.parse().unwrap_or(0),
+                    Some(("behind", count)) => behind = count.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+
+            Some(BranchInfo {
+                name: name.to_string(),
+                remote,
+                current: head == "*",
+                commit: commit.to_string(),
+                ahead,
+                behind,
+                is_remote,
+                upstream: non_empty(upstream),
+                upstream_gone: track == "gone",
+                last_commit_date: DateTime::parse_from_rfc3339(date)
+                    .ok()
+                    .map(|date| date.with_timezone(&Local)),
+                worktree: non_empty(worktree).map(PathBuf::from),
+            })
+        })
+        .collect()
+}
+
+/// リポジトリやワークツリーを変更しないgitコマンドか（ドライランでも実行してよいか）
+///
+/// 判定できないコマンドは変更を伴うものとして扱う。
+pub fn is_read_only(args: &[&str]) -> bool {
+    let mut args = args;
+    while let ["-C", _, rest @ ..] = args {
+        args = rest;
+    }
+
+    match args {
+        ["worktree", "list", ..] => true,
+        ["worktree", "prune", options @ ..] => options.contains(&"--dry-run"),
+        ["branch", options @ ..] => options.iter().all(|option| {
+            matches!(
+                *option,
+                "-v" | "-vv" | "-a" | "-r" | "--list" | "--all" | "--remotes"
+            ) || option.starts_with("--format")
+        }),
+        [command, ..] => matches!(
+            *command,
+            "status"
+                | "rev-parse"
+                | "rev-list"
+                | "log"
+                | "show"
+                | "diff"
+                | "for-each-ref"
+                | "show-ref"
+                | "ls-files"
+                | "cat-file"
+                | "merge-base"
+        ),
+        [] => true,
+    }
+}
+
+/// `git worktree prune --verbose`の出力（`Removing worktrees/<id>: <reason>`）をパース
+pub fn parse_prune_output(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Removing worktrees/"))
+        .filter_map(|rest| {
+            let (id, reason) = rest.split_once(": ").unwrap_or((rest, ""));
+            let id = id.trim_end_matches(':');
+            (!id.is_empty()).then(|| (id.to_string(), reason.trim().to_string()))
+        })
+        .collect()
+}
+
+/// `git status --porcelain=v2 --branch`の出力をパース
+pub(crate) fn parse_status_porcelain_v2(output: &str) -> WorktreeGitStatus {
+    let mut status = WorktreeGitStatus::default();
+
+    for line in output.lines() {
+        if let Some(oid) = line.strip_prefix("# branch.oid ") {
+            status.head = (oid != "(initial)").then(|| oid.to_string());
+        } else if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = (head != "(detached)").then(|| head.to_string());
+        } else if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream = Some(upstream.to_string());
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            for count in ab.split_whitespace() {
+                if let Some(ahead) = count.strip_prefix('+') {
+                    status.ahead = ahead.parse().unwrap_or(0);
+                } else if let Some(behind) = count.strip_prefix('-') {
+                    status.behind = behind.parse().unwrap_or(0);
+                }
+            }
+        } else if line.starts_with("? ") {
+            status.untracked += 1;
+        } else if line.starts_with("1 ") || line.starts_with("2 ") || line.starts_with("u ") {
+            status.changed += 1;
+        }
+    }
+
+    status
+}
+
 /// サポートされているシェルタイプ
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -702,3 +1042,241 @@ impl ShellType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_backend::FakeBackend;
+
+    #[test]
+    fn test_dry_run_does_not_reach_backend() {
+        let fake = FakeBackend::new(Path::new("/repo"));
+        let mut git = GitManager::with_backend(Path::new("/repo"), Box::new(fake.clone()));
+        git.set_dry_run(true);
+
+        git.add_worktree_with_options(&["-b", "feature", "/work/feature"])
+            .unwrap();
+        git.delete_branch("main", true).unwrap();
+        assert!(git.prune_worktrees(true).unwrap().is_empty());
+
+        assert!(fake.state().calls.is_empty());
+        assert_eq!(git.list_worktrees().unwrap().len(), 1);
+        assert_eq!(
+            git.take_planned_commands(),
+            vec![
+                "git worktree add -b feature /work/feature",
+                "git branch -D main"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_worktree_list_derives_agent_name_from_template() {
+        let output = "\
+worktree /repo
+HEAD 1111111111111111111111111111111111111111
+branch refs/heads/main
+
+worktree /work/claude
+HEAD 2222222222222222222222222222222222222222
+branch refs/heads/agent/claude
+locked on removable drive
+
+worktree /work/fix
+HEAD 3333333333333333333333333333333333333333
+branch refs/heads/team/fix-login-2
+prunable gitdir file points to non-existent location
+";
+        let worktrees = parse_worktree_list(output);
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[1].branch, "refs/heads/agent/claude");
+        assert_eq!(worktrees[2].commit, "3".repeat(40));
+        assert!(worktrees[1].locked && !worktrees[1].prunable);
+        assert_eq!(
+            worktrees[1].lock_reason.as_deref(),
+            Some("on removable drive")
+        );
+        assert!(worktrees[2].prunable && worktrees[2].lock_reason.is_none());
+
+        // エージェント名は一覧の取得時にテンプレートから抽出する
+        let fake = FakeBackend::new(Path::new("/repo"));
+        fake.state().worktrees = worktrees;
+        let mut git = GitManager::with_backend(Path::new("/repo"), Box::new(fake));
+        let names = |git: &mut GitManager| -> Vec<Option<String>> {
+            git.list_worktrees()
+                .unwrap()
+                .into_iter()
+                .map(|wt| wt.agent_name)
+                .collect()
+        };
+        assert_eq!(
+            names(&mut git),
+            vec![None, Some("claude".to_string()), None]
+        );
+
+        git.set_branch_template(BranchTemplate::parse("{prefix}{name}-{seq}", "team").unwrap());
+        assert_eq!(
+            names(&mut git),
+            vec![None, None, Some("fix-login".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_status_porcelain_v2() {
+        let output = "\
+# branch.oid 1234567890abcdef1234567890abcdef12345678
+# branch.head feature/x
+# branch.upstream origin/feature/x
+# branch.ab +2 -1
+1 .M N... 100644 100644 100644 aaaa bbbb src/lib.rs
+2 R. N... 100644 100644 100644 aaaa bbbb R100 new.rs\told.rs
+u UU N... 100644 100644 100644 100644 aaaa bbbb cccc conflict.rs
+? notes.txt
+? .env
+! target
+";
+        let status = parse_status_porcelain_v2(output);
+
+        assert_eq!(
+            status.head.as_deref(),
+            Some("1234567890abcdef1234567890abcdef12345678")
+        );
+        assert_eq!(status.branch.as_deref(), Some("feature/x"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/feature/x"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!(status.changed, 3);
+        assert_eq!(status.untracked, 2);
+        assert!(status.is_dirty());
+    }
+
+    #[test]
+    fn test_parse_prune_output() {
+        let output = "\
+Removing worktrees/feature-a: gitdir file points to non-existent location
+Removing worktrees/b1: gitdir file does not exist
+warning: unrelated message
+";
+        assert_eq!(
+            parse_prune_output(output),
+            vec![
+                (
+                    "feature-a".to_string(),
+                    "gitdir file points to non-existent location".to_string()
+                ),
+                ("b1".to_string(), "gitdir file does not exist".to_string()),
+            ]
+        );
+        assert!(parse_prune_output("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_branch_list() {
+        let line = |fields: [&str; 9]| fields.join("\0");
+        let output = [
+            line([
+                "refs/heads/main",
+                "",
+                "*",
+                "1111",
+                "origin/main",
+                "origin",
+                "ahead 2, behind 1",
+                "2026-03-09T12:00:00+09:00",
+                "/repo",
+            ]),
+            // 別のWorktreeでチェックアウトされ、上流ブランチが削除されている
+            line([
+                "refs/heads/agent/claude",
+                "",
+                " ",
+                "2222",
+                "origin/agent/claude",
+                "origin",
+                "gone",
+                "2026-03-08T09:30:00+09:00",
+                "/work/claude",
+            ]),
+            line([
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/main",
+                " ",
+                "1111",
+                "",
+                "",
+                "",
+                "2026-03-09T12:00:00+09:00",
+                "",
+            ]),
+            line([
+                "refs/remotes/origin/main",
+                "",
+                " ",
+                "1111",
+                "",
+                "",
+                "",
+                "2026-03-09T12:00:00+09:00",
+                "",
+            ]),
+        ]
+        .join("\n");
+
+        let branches = parse_branch_list(&output);
+        assert_eq!(branches.len(), 3);
+
+        let main = &branches[0];
+        assert_eq!(main.name, "main");
+        assert!(main.current);
+        assert_eq!(main.upstream.as_deref(), Some("origin/main"));
+        assert_eq!(main.remote.as_deref(), Some("origin"));
+        assert_eq!((main.ahead, main.behind), (2, 1));
+        assert_eq!(
+            main.last_commit_date.map(|date| date.timestamp()),
+            Some(1773025200)
+        );
+
+        let agent = &branches[1];
+        assert_eq!(agent.name, "agent/claude");
+        assert!(!agent.current);
+        assert!(agent.upstream_gone);
+        assert_eq!(agent.worktree, Some(PathBuf::from("/work/claude")));
+
+        let remote = &branches[2];
+        assert_eq!(remote.name, "origin/main");
+        assert!(remote.is_remote);
+        assert_eq!(remote.remote.as_deref(), Some("origin"));
+        assert_eq!(remote.worktree, None);
+    }
+
+    #[test]
+    fn test_is_read_only() {
+        assert!(is_read_only(&["worktree", "list", "--porcelain"]));
+        assert!(is_read_only(&["-C", "/tmp/wt", "status", "--porcelain"]));
+        assert!(is_read_only(&[
+            "worktree",
+            "prune",
+            "--verbose",
+            "--dry-run"
+        ]));
+        assert!(is_read_only(&["branch", "-v", "-a"]));
+
+        assert!(!is_read_only(&[
+            "worktree", "add", "-b", "feature", "/tmp/wt"
+        ]));
+        assert!(!is_read_only(&["worktree", "prune", "--verbose"]));
+        assert!(!is_read_only(&["branch", "-D", "feature"]));
+        assert!(!is_read_only(&["branch", "feature"]));
+        assert!(!is_read_only(&["-C", "/tmp/wt", "commit", "-m", "x"]));
+    }
+
+    #[test]
+    fn test_parse_status_detached_without_upstream() {
+        let status =
+            parse_status_porcelain_v2("# branch.oid (initial)\n# branch.head (detached)\n");
+
+        assert_eq!(status.head, None);
+        assert_eq!(status.branch, None);
+        assert_eq!(status.upstream, None);
+        assert!(!status.is_dirty());
+    }
+}
@@ -0,0 +1,1283 @@
+//! Gitバックエンドモジュール
+//!
+//! このモジュールの役割：
+//! - Worktree・ブランチの操作を抽象化する`GitBackend`トレイトの定義
+//! - gitコマンドを実行する実装（`CliBackend`）
+//! - 読み取り専用の問い合わせをgit2で行う実装（`Libgit2Backend`）
+//! - コマンドのロジックをテストするためのインメモリ実装（`FakeBackend`）
+
+#![allow(dead_code)]
+use crate::core::{TwinError, TwinResult};
+use crate::git::{
+    parse_branch_list, parse_prune_output, parse_status_porcelain_v2, parse_worktree_list,
+    read_worktree_admin_dir, BranchInfo, PrunedWorktree, WorktreeGitStatus, WorktreeInfo,
+    BRANCH_FORMAT,
+};
+use chrono::{DateTime, Local, TimeZone};
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Worktree・ブランチの操作を行うバックエンド
+///
+/// ドライラン・実行履歴・エージェント名の抽出は`GitManager`が担当し、
+/// バックエンドは実際の問い合わせと変更だけを行う。
+pub trait GitBackend: Send {
+    /// バックエンドの名前（ログ表示用）
+    fn name(&self) -> &'static str;
+
+    /// 全Worktreeで共有されるGitディレクトリ（通常はメインの.git）
+    fn git_common_dir(&mut self) -> TwinResult<PathBuf>;
+
+    /// Worktreeの一覧（メインのWorktreeが先頭）
+    fn list_worktrees(&mut self) -> TwinResult<Vec<WorktreeInfo>>;
+
+    /// 指定したパスのWorktreeを一覧を取得せずに探す（対応していない場合はNone）
+    fn find_worktree(&mut self, _path: &Path) -> TwinResult<Option<WorktreeInfo>> {
+        Ok(None)
+    }
+
+    /// Worktreeを追加し、gitの標準出力を返す（`args`は`git worktree add`に続く引数）
+    fn add_worktree(&mut self, args: &[&str]) -> TwinResult<String>;
+
+    /// Worktreeを削除
+    fn remove_worktree(&mut self, path: &Path, force: bool) -> TwinResult<()>;
+
+    /// 存在しないWorktreeの管理情報を削除し、削除した（`dry_run`では削除する）ものを返す
+    fn prune_worktrees(&mut self, dry_run: bool) -> TwinResult<Vec<PrunedWorktree>>;
+
+    /// Worktreeの管理情報を修復（`paths`が空の場合はメインのリポジトリから各Worktreeへのリンク）
+    fn repair_worktrees(&mut self, paths: &[&Path]) -> TwinResult<()>;
+
+    /// Worktreeをロック
+    fn lock_worktree(&mut self, path: &Path, reason: Option<&str>) -> TwinResult<()>;
+
+    /// Worktreeのロックを解除
+    fn unlock_worktree(&mut self, path: &Path) -> TwinResult<()>;
+
+    /// Worktree内の未コミット変更（未追跡ファイルを含む）の件数
+    fn count_changes(&mut self, path: &Path) -> TwinResult<usize>;
+
+    /// Worktreeの作業状態
+    fn worktree_status(&mut self, path: &Path) -> TwinResult<WorktreeGitStatus>;
+
+    /// Worktree内の未追跡・無視されたパス（ディレクトリは配下をまとめて1件）
+    fn list_untracked_paths(&mut self, path: &Path) -> TwinResult<Vec<PathBuf>>;
+
+    /// WorktreeのHEADが`base`に対して進んでいる・遅れているコミット数
+    fn ahead_behind(&mut self, path: &Path, base: &str) -> TwinResult<(usize, usize)>;
+
+    /// ブランチの一覧（`remote`がtrueの場合はリモート追跡ブランチのみ、falseの場合はすべて）
+    fn list_branches(&mut self, remote: bool) -> TwinResult<Vec<BranchInfo>>;
+
+    /// ローカルブランチが存在するか
+    fn branch_exists(&mut self, name: &str) -> TwinResult<bool> {
+        Ok(self
+            .list_branches(false)?
+            .iter()
+            .any(|b| !b.is_remote && b.name == name))
+    }
+
+    /// 現在のブランチ名（detached HEADの場合は`HEAD`）
+    fn current_branch(&mut self) -> TwinResult<String>;
+
+    /// リビジョンをコミットハッシュに解決
+    fn resolve_commit(&mut self, rev: &str) -> TwinResult<String>;
+
+    /// ブランチを作成
+    fn create_branch(&mut self, name: &str, start_point: Option<&str>) -> TwinResult<()>;
+
+    /// ブランチを削除
+    fn delete_branch(&mut self, name: &str, force: bool) -> TwinResult<()>;
+
+    /// `base`にマージ済みのローカルブランチ（`refs/heads/`から始まる名前）
+    fn merged_branches(&mut self, base: &str) -> TwinResult<Vec<String>>;
+
+    /// コミットの日時と件名
+    fn commit_summary(&mut self, commit: &str) -> TwinResult<(DateTime<Local>, String)>;
+}
+
+/// gitコマンドを起動する（終了ステータスは確認しない）
+pub(crate) fn spawn_git(repo_path: &Path, args: &[&str]) -> TwinResult<Output> {
+    // 透明性のあるコマンド実行ログ
+    if std::env::var("TWIN_VERBOSE").is_ok() || std::env::var("TWIN_DEBUG").is_ok() {
+        eprintln!("🔧 実行中: git {}", args.join(" "));
+    }
+
+    Command::new("git")
+        .current_dir(repo_path)
+        .args(args)
+        .output()
+        .map_err(|e| TwinError::git(format!("Failed to execute git command: {e}")))
+}
+
+/// gitコマンドを実行するバックエンド
+pub struct CliBackend {
+    /// gitコマンドを実行するディレクトリ
+    repo_path: PathBuf,
+}
+
+impl CliBackend {
+    pub fn new(repo_path: &Path) -> Self {
+        Self {
+            repo_path: repo_path.to_path_buf(),
+        }
+    }
+
+    /// gitコマンドを実行し、失敗した場合はエラーにする
+    fn run(&self, args: &[&str]) -> TwinResult<Output> {
+        info!("Executing: git {}", args.join(" "));
+        let output = spawn_git(&self.repo_path, args)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(TwinError::git(format!("Git command failed: {stderr}")));
+        }
+        Ok(output)
+    }
+
+    fn stdout(&self, args: &[&str]) -> TwinResult<String> {
+        let output = self.run(args)?;
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn git_common_dir(&mut self) -> TwinResult<PathBuf> {
+        let dir = PathBuf::from(self.stdout(&["rev-parse", "--git-common-dir"])?.trim());
+
+        // 相対パスで返される場合はリポジトリのパスを基準に解決
+        let dir = if dir.is_absolute() {
+            dir
+        } else {
+            std::env::current_dir()
+                .map_err(|e| TwinError::io(format!("Failed to get current dir: {e}"), None))?
+                .join(&self.repo_path)
+                .join(dir)
+        };
+
+        Ok(dir.canonicalize().unwrap_or(dir))
+    }
+
+    fn list_worktrees(&mut self) -> TwinResult<Vec<WorktreeInfo>> {
+        Ok(parse_worktree_list(&self.stdout(&[
+            "worktree",
+            "list",
+            "--porcelain",
+        ])?))
+    }
+
+    fn add_worktree(&mut self, args: &[&str]) -> TwinResult<String> {
+        let mut full_args = vec!["worktree", "add"];
+        full_args.extend_from_slice(args);
+        info!("Executing: git {}", full_args.join(" "));
+
+        let output = spawn_git(&self.repo_path, &full_args)?;
+        if !output.status.success() {
+            // git worktreeのエラーメッセージをそのまま返す
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(TwinError::git(stderr.trim().to_string()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn remove_worktree(&mut self, path: &Path, force: bool) -> TwinResult<()> {
+        let path_str = path.to_string_lossy();
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(&path_str);
+        self.run(&args)?;
+        Ok(())
+    }
+
+    fn prune_worktrees(&mut self, dry_run: bool) -> TwinResult<Vec<PrunedWorktree>> {
+        // 削除後は管理情報からパスを取得できないため、先にIDとパス・ブランチの対応を取得しておく
+        let admin_dir = self.git_common_dir()?.join("worktrees");
+        let worktrees = self.list_worktrees()?;
+        let paths: Vec<(String, Option<PathBuf>)> = read_worktree_admin_dir(&admin_dir)
+            .into_iter()
+            .map(|(entry, path)| {
+                let id = entry
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                (id, path)
+            })
+            .collect();
+
+        let mut args = vec!["worktree", "prune", "--verbose"];
+        if dry_run {
+            args.push("--dry-run");
+        }
+        let output = self.run(&args)?;
+        // 削除したエントリーは標準エラー出力に出力される
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        Ok(parse_prune_output(&text)
+            .into_iter()
+            .map(|(id, reason)| {
+                let path = paths
+                    .iter()
+                    .find(|(admin_id, _)| *admin_id == id)
+                    .and_then(|(_, path)| path.clone());
+                let branch = path.as_ref().and_then(|path| {
+                    worktrees
+                        .iter()
+                        .find(|w| &w.path == path)
+                        .and_then(|w| w.branch.strip_prefix("refs/heads/"))
+                        .map(String::from)
+                });
+                PrunedWorktree {
+                    id,
+                    path,
+                    branch,
+                    reason,
+                }
+            })
+            .collect())
+    }
+
+    fn repair_worktrees(&mut self, paths: &[&Path]) -> TwinResult<()> {
+        let path_strs: Vec<String> = paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        let mut args = vec!["worktree", "repair"];
+        args.extend(path_strs.iter().map(String::as_str));
+        self.run(&args)?;
+        Ok(())
+    }
+
+    fn lock_worktree(&mut self, path: &Path, reason: Option<&str>) -> TwinResult<()> {
+        let path_str = path.to_string_lossy();
+        let mut args = vec!["worktree", "lock"];
+        if let Some(reason) = reason {
+            args.push("--reason");
+            args.push(reason);
+        }
+        args.push(&path_str);
+        self.run(&args)?;
+        Ok(())
+    }
+
+    fn unlock_worktree(&mut self, path: &Path) -> TwinResult<()> {
+        self.run(&["worktree", "unlock", &path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    fn count_changes(&mut self, path: &Path) -> TwinResult<usize> {
+        let path_str = path.to_string_lossy();
+        let stdout = self.stdout(&["-C", &path_str, "status", "--porcelain"])?;
+        Ok(stdout.lines().filter(|line| !line.is_empty()).count())
+    }
+
+    fn worktree_status(&mut self, path: &Path) -> TwinResult<WorktreeGitStatus> {
+        let path_str = path.to_string_lossy();
+        Ok(parse_status_porcelain_v2(&self.stdout(&[
+            "-C",
+            &path_str,
+            "status",
+            "--porcelain=v2",
+            "--branch",
+        ])?))
+    }
+
+    fn list_untracked_paths(&mut self, path: &Path) -> TwinResult<Vec<PathBuf>> {
+        let path_str = path.to_string_lossy();
+        let stdout = self.stdout(&["-C", &path_str, "status", "--porcelain", "--ignored", "-z"])?;
+        Ok(stdout
+            .split('\0')
+            .filter_map(|entry| {
+                entry
+                    .strip_prefix("?? ")
+                    .or_else(|| entry.strip_prefix("!! "))
+            })
+            .map(|relative| PathBuf::from(relative.trim_end_matches('/')))
+            .collect())
+    }
+
+    fn ahead_behind(&mut self, path: &Path, base: &str) -> TwinResult<(usize, usize)> {
+        let path_str = path.to_string_lossy();
+        let range = format!("HEAD...{base}");
+        let stdout = self.stdout(&[
+            "-C",
+            &path_str,
+            "rev-list",
+            "--left-right",
+            "--count",
+            &range,
+        ])?;
+        let mut counts = stdout.split_whitespace().map(|n| n.parse::<usize>());
+        match (counts.next(), counts.next()) {
+            (Some(Ok(ahead)), Some(Ok(behind))) => Ok((ahead, behind)),
+            _ => Err(TwinError::git(format!(
+                "Unexpected rev-list output: {}",
+                stdout.trim()
+            ))),
+        }
+    }
+
+    fn list_branches(&mut self, remote: bool) -> TwinResult<Vec<BranchInfo>> {
+        let format = format!("--format={BRANCH_FORMAT}");
+        let mut args = vec!["for-each-ref", format.as_str()];
+        if !remote {
+            args.push("refs/heads");
+        }
+        args.push("refs/remotes");
+
+        Ok(parse_branch_list(&self.stdout(&args)?))
+    }
+
+    fn current_branch(&mut self) -> TwinResult<String> {
+        Ok(self
+            .stdout(&["rev-parse", "--abbrev-ref", "HEAD"])?
+            .trim()
+            .to_string())
+    }
+
+    fn resolve_commit(&mut self, rev: &str) -> TwinResult<String> {
+        let spec = format!("{rev}^{{commit}}");
+        Ok(self
+            .stdout(&["rev-parse", "--verify", "--quiet", &spec])?
+            .trim()
+            .to_string())
+    }
+
+    fn create_branch(&mut self, name: &str, start_point: Option<&str>) -> TwinResult<()> {
+        let mut args = vec!["branch", name];
+        if let Some(start) = start_point {
+            args.push(start);
+        }
+        self.run(&args)?;
+        Ok(())
+    }
+
+    fn delete_branch(&mut self, name: &str, force: bool) -> TwinResult<()> {
+        self.run(&["branch", if force { "-D" } else { "-d" }, name])?;
+        Ok(())
+    }
+
+    fn merged_branches(&mut self, base: &str) -> TwinResult<Vec<String>> {
+        Ok(self
+            .stdout(&[
+                "for-each-ref",
+                "--format=%(refname)",
+                "--merged",
+                base,
+                "refs/heads",
+            ])?
+            .lines()
+            .map(String::from)
+            .collect())
+    }
+
+    fn commit_summary(&mut self, commit: &str) -> TwinResult<(DateTime<Local>, String)> {
+        let stdout = self.stdout(&["show", "-s", "--format=%cI%x00%s", commit])?;
+        let (date, subject) = stdout
+            .trim_end_matches('\n')
+            .split_once('\0')
+            .ok_or_else(|| TwinError::git(format!("Unexpected git show output: {stdout}")))?;
+        let date = DateTime::parse_from_rfc3339(date)
+            .map_err(|e| TwinError::git(format!("Invalid commit date {date}: {e}")))?;
+        Ok((date.with_timezone(&Local), subject.to_string()))
+    }
+}
+
+/// 読み取り専用の問い合わせをgit2で行うバックエンド
+///
+/// git2で扱えない問い合わせ（bareリポジトリの一覧など）と、フックや設定を含めて
+/// gitと同じ結果にする必要がある変更はgitコマンドで行う。
+pub struct Libgit2Backend {
+    repository: git2::Repository,
+    cli: CliBackend,
+}
+
+impl Libgit2Backend {
+    /// `repo_path`を含むリポジトリを開く（gitコマンドと同じく親ディレクトリも探索）
+    pub fn open(repo_path: &Path) -> Result<Self, git2::Error> {
+        Ok(Self {
+            repository: git2::Repository::discover(repo_path)?,
+            cli: CliBackend::new(repo_path),
+        })
+    }
+}
+
+impl GitBackend for Libgit2Backend {
+    fn name(&self) -> &'static str {
+        "libgit2"
+    }
+
+    fn git_common_dir(&mut self) -> TwinResult<PathBuf> {
+        let dir = self.repository.commondir();
+        Ok(dir.canonicalize().unwrap_or_else(|_| normalize_path(dir)))
+    }
+
+    fn list_worktrees(&mut self) -> TwinResult<Vec<WorktreeInfo>> {
+        match git2_list_worktrees(&self.repository) {
+            Ok(worktrees) => Ok(worktrees),
+            Err(e) => {
+                debug!("Falling back to git worktree list: {e}");
+                self.cli.list_worktrees()
+            }
+        }
+    }
+
+    fn find_worktree(&mut self, path: &Path) -> TwinResult<Option<WorktreeInfo>> {
+        // Worktreeを直接開いて一覧の取得を省く
+        match git2_worktree_at(&self.repository, path) {
+            Ok(worktree) => Ok(Some(worktree)),
+            Err(e) => {
+                debug!("Falling back to git worktree list for {path:?}: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    fn add_worktree(&mut self, args: &[&str]) -> TwinResult<String> {
+        self.cli.add_worktree(args)
+    }
+
+    fn remove_worktree(&mut self, path: &Path, force: bool) -> TwinResult<()> {
+        self.cli.remove_worktree(path, force)
+    }
+
+    fn prune_worktrees(&mut self, dry_run: bool) -> TwinResult<Vec<PrunedWorktree>> {
+        self.cli.prune_worktrees(dry_run)
+    }
+
+    fn repair_worktrees(&mut self, paths: &[&Path]) -> TwinResult<()> {
+        self.cli.repair_worktrees(paths)
+    }
+
+    fn lock_worktree(&mut self, path: &Path, reason: Option<&str>) -> TwinResult<()> {
+        self.cli.lock_worktree(path, reason)
+    }
+
+    fn unlock_worktree(&mut self, path: &Path) -> TwinResult<()> {
+        self.cli.unlock_worktree(path)
+    }
+
+    // 作業ツリーの状態は.gitignoreやfsmonitorなどの設定を含めてgitと同じ結果にする
+    fn count_changes(&mut self, path: &Path) -> TwinResult<usize> {
+        self.cli.count_changes(path)
+    }
+
+    fn worktree_status(&mut self, path: &Path) -> TwinResult<WorktreeGitStatus> {
+        self.cli.worktree_status(path)
+    }
+
+    fn list_untracked_paths(&mut self, path: &Path) -> TwinResult<Vec<PathBuf>> {
+        self.cli.list_untracked_paths(path)
+    }
+
+    fn ahead_behind(&mut self, path: &Path, base: &str) -> TwinResult<(usize, usize)> {
+        match git2_ahead_behind(&self.repository, path, base) {
+            Ok(counts) => Ok(counts),
+            Err(e) => {
+                debug!("Falling back to git rev-list for {path:?}: {e}");
+                self.cli.ahead_behind(path, base)
+            }
+        }
+    }
+
+    fn list_branches(&mut self, remote: bool) -> TwinResult<Vec<BranchInfo>> {
+        // 上流ブランチとの差分などはfor-each-refでまとめて取得する
+        self.cli.list_branches(remote)
+    }
+
+    fn branch_exists(&mut self, name: &str) -> TwinResult<bool> {
+        match self.repository.find_branch(name, git2::BranchType::Local) {
+            Ok(_) => Ok(true),
+            Err(e)
+                if matches!(
+                    e.code(),
+                    git2::ErrorCode::NotFound | git2::ErrorCode::InvalidSpec
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => {
+                debug!("Falling back to git for-each-ref: {e}");
+                self.cli.branch_exists(name)
+            }
+        }
+    }
+
+    fn current_branch(&mut self) -> TwinResult<String> {
+        // detached HEADのshorthandは`HEAD`（`git rev-parse --abbrev-ref HEAD`と同じ）
+        match self.repository.head() {
+            Ok(head) => {
+                if let Some(name) = head.shorthand() {
+                    return Ok(name.to_string());
+                }
+            }
+            Err(e) => debug!("Falling back to git rev-parse: {e}"),
+        }
+        self.cli.current_branch()
+    }
+
+    fn resolve_commit(&mut self, rev: &str) -> TwinResult<String> {
+        match self
+            .repository
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+        {
+            Ok(commit) => Ok(commit.id().to_string()),
+            Err(e) => {
+                debug!("Falling back to git rev-parse for {rev}: {e}");
+                self.cli.resolve_commit(rev)
+            }
+        }
+    }
+
+    fn create_branch(&mut self, name: &str, start_point: Option<&str>) -> TwinResult<()> {
+        self.cli.create_branch(name, start_point)
+    }
+
+    fn delete_branch(&mut self, name: &str, force: bool) -> TwinResult<()> {
+        self.cli.delete_branch(name, force)
+    }
+
+    fn merged_branches(&mut self, base: &str) -> TwinResult<Vec<String>> {
+        self.cli.merged_branches(base)
+    }
+
+    fn commit_summary(&mut self, commit: &str) -> TwinResult<(DateTime<Local>, String)> {
+        match git2_commit_summary(&self.repository, commit) {
+            Ok(summary) => Ok(summary),
+            Err(e) => {
+                debug!("Falling back to git show for {commit}: {e}");
+                self.cli.commit_summary(commit)
+            }
+        }
+    }
+}
+
+/// git2でWorktreeのHEADが`base`に対して進んでいる・遅れているコミット数を取得
+fn git2_ahead_behind(
+    repo: &git2::Repository,
+    path: &Path,
+    base: &str,
+) -> Result<(usize, usize), git2::Error> {
+    // WorktreeごとにHEADが異なるため、Worktreeを開いてHEADを解決する
+    let worktree = git2::Repository::open(path)?;
+    if worktree.commondir().canonicalize().ok() != repo.commondir().canonicalize().ok() {
+        return Err(git2::Error::from_str("not a worktree of this repository"));
+    }
+    let head = worktree.head()?.peel_to_commit()?.id();
+    let base = worktree.revparse_single(base)?.peel_to_commit()?.id();
+    worktree.graph_ahead_behind(head, base)
+}
+
+/// git2でコミットの日時（コミッターの日時）と件名を取得
+fn git2_commit_summary(
+    repo: &git2::Repository,
+    commit: &str,
+) -> Result<(DateTime<Local>, String), git2::Error> {
+    let commit = repo.revparse_single(commit)?.peel_to_commit()?;
+    let date = Local
+        .timestamp_opt(commit.committer().when().seconds(), 0)
+        .single()
+        .ok_or_else(|| git2::Error::from_str("invalid commit date"))?;
+    let subject = commit
+        .summary()
+        .ok_or_else(|| git2::Error::from_str("commit summary is not valid UTF-8"))?;
+    Ok((date, subject.to_string()))
+}
+
+/// git2でメインのWorktreeのリポジトリを開く（リンクされたWorktreeから開いた場合は共通のGitディレクトリから開き直す）
+fn git2_main_repository(repo: &git2::Repository) -> Result<git2::Repository, git2::Error> {
+    let main = if repo.is_worktree() {
+        git2::Repository::open(repo.commondir())?
+    } else {
+        git2::Repository::open(repo.path())?
+    };
+    if main.is_bare() {
+        // bareリポジトリの表示（`bare`）はgitコマンドに任せる
+        return Err(git2::Error::from_str("bare repository"));
+    }
+    Ok(main)
+}
+
+/// 末尾の`/`などを取り除いたパス（`git worktree list`の表示に合わせる）
+fn normalize_path(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+/// 管理ディレクトリのHEADファイルからブランチとコミットを読み取ったWorktreeの情報
+///
+/// 他のWorktreeのHEADはgit2の参照APIで直接読めないため、HEADファイルを読んで解決する。
+fn git2_worktree_entry(
+    main: &git2::Repository,
+    path: &Path,
+    git_dir: &Path,
+) -> Result<WorktreeInfo, git2::Error> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD"))
+        .map_err(|e| git2::Error::from_str(&format!("Failed to read HEAD: {e}")))?;
+    let head = head.trim();
+
+    let mut worktree = WorktreeInfo {
+        path: normalize_path(path),
+        ..Default::default()
+    };
+    match head.strip_prefix("ref: ") {
+        Some(refname) => {
+            worktree.branch = refname.to_string();
+            // コミットのないブランチは`git worktree list`と同じく0のハッシュ
+            worktree.commit = main
+                .refname_to_id(refname)
+                .unwrap_or_else(|_| git2::Oid::zero())
+                .to_string();
+        }
+        None => worktree.commit = head.to_string(),
+    }
+    Ok(worktree)
+}
+
+/// git2でメインのWorktreeの情報を取得
+fn git2_main_worktree(main: &git2::Repository) -> Result<WorktreeInfo, git2::Error> {
+    let workdir = main
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("repository has no working directory"))?;
+    git2_worktree_entry(main, workdir, main.path())
+}
+
+/// git2でリンクされたWorktreeの情報を取得
+fn git2_linked_worktree(main: &git2::Repository, name: &str) -> Result<WorktreeInfo, git2::Error> {
+    let worktree = main.find_worktree(name)?;
+    let git_dir = main.path().join("worktrees").join(name);
+    let mut info = git2_worktree_entry(main, worktree.path(), &git_dir)?;
+    if let git2::WorktreeLockStatus::Locked(reason) = worktree.is_locked()? {
+        info.locked = true;
+        // lockedファイルの内容をそのまま返すため、末尾の改行を取り除く
+        info.lock_reason = reason
+            .map(|reason| reason.trim_end().to_string())
+            .filter(|reason| !reason.is_empty());
+    }
+    // ロックされていないWorktreeのディレクトリがなくなっている場合は`git worktree prune`の対象
+    info.prunable = !info.locked && worktree.validate().is_err();
+    Ok(info)
+}
+
+/// git2でWorktreeの一覧を取得（`git worktree list`と同じくメインのWorktreeが先頭）
+fn git2_list_worktrees(repo: &git2::Repository) -> Result<Vec<WorktreeInfo>, git2::Error> {
+    let main = git2_main_repository(repo)?;
+    let mut worktrees = vec![git2_main_worktree(&main)?];
+    for name in main.worktrees()?.iter().flatten() {
+        worktrees.push(git2_linked_worktree(&main, name)?);
+    }
+    Ok(worktrees)
+}
+
+/// git2で指定したパスのWorktreeの情報を取得（同じリポジトリのWorktreeでない場合はエラー）
+fn git2_worktree_at(repo: &git2::Repository, path: &Path) -> Result<WorktreeInfo, git2::Error> {
+    let main = git2_main_repository(repo)?;
+    let opened = git2::Repository::open(path)?;
+    let same_repository = opened.commondir().canonicalize().ok() == main.path().canonicalize().ok()
+        && opened
+            .workdir()
+            .is_some_and(|workdir| workdir.canonicalize().ok() == path.canonicalize().ok());
+    if !same_repository {
+        return Err(git2::Error::from_str("not a worktree of this repository"));
+    }
+
+    if !opened.is_worktree() {
+        return git2_main_worktree(&main);
+    }
+    // リンクされたWorktreeのGitディレクトリは`<common dir>/worktrees/<name>`
+    let name = opened
+        .path()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| git2::Error::from_str("invalid worktree git directory"))?;
+    git2_linked_worktree(&main, name)
+}
+
+/// `FakeBackend`が保持するリポジトリの状態
+#[derive(Debug, Clone, Default)]
+pub struct FakeRepository {
+    /// Worktreeの一覧（先頭がメインのWorktree）
+    pub worktrees: Vec<WorktreeInfo>,
+    /// ローカルブランチ名とコミットハッシュ
+    pub branches: BTreeMap<String, String>,
+    /// 実行された変更（`worktree add -b feature /path`などgitの引数の形式）
+    pub calls: Vec<String>,
+    /// trueの場合、Worktreeの一覧の取得を失敗させる（エラー処理のテスト用）
+    pub fail_list_worktrees: bool,
+    /// Worktreeのパスごとの作業状態（含まれないWorktreeは変更なし）
+    pub statuses: BTreeMap<PathBuf, FakeWorktreeStatus>,
+    /// コミットハッシュごとの日時と件名
+    pub commits: BTreeMap<String, (DateTime<Local>, String)>,
+    /// 次に作成するコミットハッシュの番号
+    next_commit: u64,
+}
+
+/// `FakeBackend`のWorktreeの作業状態
+#[derive(Debug, Clone, Default)]
+pub struct FakeWorktreeStatus {
+    /// 変更されたファイル数
+    pub changed: usize,
+    /// 未追跡・無視されたパス
+    pub untracked: Vec<PathBuf>,
+    /// 比較するブランチより進んでいるコミット数
+    pub ahead: usize,
+    /// 比較するブランチより遅れているコミット数
+    pub behind: usize,
+}
+
+/// gitを実行せずにメモリ上でWorktree・ブランチを管理するバックエンド
+///
+/// Worktreeの追加・削除ではディレクトリも作成・削除するため、ファイルマッピングなどの
+/// 副作用を含めたコマンドのロジックを実際のリポジトリなしでテストできる。
+/// クローンは状態を共有するので、`GitManager`に渡した後も状態を確認できる。
+#[derive(Debug, Clone)]
+pub struct FakeBackend {
+    /// メインのWorktreeのパス
+    root: PathBuf,
+    state: Arc<Mutex<FakeRepository>>,
+}
+
+impl FakeBackend {
+    /// `main`ブランチをチェックアウトしたメインのWorktreeだけを持つリポジトリを作成
+    pub fn new(root: &Path) -> Self {
+        let backend = Self {
+            root: root.to_path_buf(),
+            state: Arc::new(Mutex::new(FakeRepository::default())),
+        };
+        {
+            let mut state = backend.state();
+            let commit = state.new_commit();
+            state.branches.insert("main".to_string(), commit.clone());
+            state.worktrees.push(WorktreeInfo {
+                path: root.to_path_buf(),
+                branch: "refs/heads/main".to_string(),
+                commit,
+                ..Default::default()
+            });
+        }
+        backend
+    }
+
+    /// 現在の状態
+    pub fn state(&self) -> MutexGuard<'_, FakeRepository> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 相対パスはメインのWorktreeを基準に解決する
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        }
+    }
+
+    fn head_commit(&self) -> String {
+        self.state().worktrees[0].commit.clone()
+    }
+}
+
+impl FakeRepository {
+    fn new_commit(&mut self) -> String {
+        self.next_commit += 1;
+        let commit = format!("{:040x}", self.next_commit);
+        self.commits.insert(
+            commit.clone(),
+            (Local::now(), format!("commit {}", self.next_commit)),
+        );
+        commit
+    }
+
+    /// 指定したパスのWorktreeの位置（先頭がメインのWorktree）
+    fn worktree_index(&self, path: &Path) -> TwinResult<usize> {
+        self.worktrees
+            .iter()
+            .position(|wt| wt.path == path)
+            .ok_or_else(|| {
+                TwinError::git(format!("fatal: '{}' is not a working tree", path.display()))
+            })
+    }
+
+    /// Worktreeの作業状態（設定されていない場合は変更なし）
+    fn status(&self, path: &Path) -> TwinResult<FakeWorktreeStatus> {
+        self.worktree_index(path)?;
+        Ok(self.statuses.get(path).cloned().unwrap_or_default())
+    }
+
+    /// ブランチをチェックアウトしているWorktree
+    fn checked_out(&self, name: &str) -> Option<&WorktreeInfo> {
+        let refname = format!("refs/heads/{name}");
+        self.worktrees.iter().find(|wt| wt.branch == refname)
+    }
+}
+
+impl GitBackend for FakeBackend {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn git_common_dir(&mut self) -> TwinResult<PathBuf> {
+        Ok(self.root.join(".git"))
+    }
+
+    fn list_worktrees(&mut self) -> TwinResult<Vec<WorktreeInfo>> {
+        let state = self.state();
+        if state.fail_list_worktrees {
+            return Err(TwinError::git("fatal: simulated worktree list failure"));
+        }
+        Ok(state.worktrees.clone())
+    }
+
+    fn add_worktree(&mut self, args: &[&str]) -> TwinResult<String> {
+        // `-b`/`-B`/`--detach`/`--lock`とパス・コミットだけを解釈する（その他のオプションは無視）
+        let mut new_branch = None;
+        let mut reset_branch = false;
+        let mut detach = false;
+        let mut lock = false;
+        let mut positional = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match *arg {
+                "-b" | "-B" => {
+                    reset_branch = *arg == "-B";
+                    new_branch = iter.next().copied();
+                }
+                "--detach" => detach = true,
+                "--lock" => lock = true,
+                arg if arg.starts_with('-') => {}
+                arg => positional.push(arg),
+            }
+        }
+        let path = positional
+            .first()
+            .map(|path| self.resolve_path(Path::new(path)))
+            .ok_or_else(|| TwinError::git("fatal: worktree path is required"))?;
+        let commitish = positional.get(1).copied();
+
+        let start = match commitish {
+            Some(rev) => self.resolve_commit(rev)?,
+            None => self.head_commit(),
+        };
+        let mut state = self.state();
+        if state.worktrees.iter().any(|wt| wt.path == path) || path.exists() {
+            return Err(TwinError::git(format!(
+                "fatal: '{}' already exists",
+                path.display()
+            )));
+        }
+
+        // チェックアウトするブランチと、そのブランチを`start`から作成（リセット）するか
+        let (branch, create) = if detach {
+            (None, false)
+        } else if let Some(name) = new_branch {
+            if state.branches.contains_key(name) && !reset_branch {
+                return Err(TwinError::git(format!(
+                    "fatal: a branch named '{name}' already exists"
+                )));
+            }
+            (Some(name.to_string()), true)
+        } else {
+            match commitish {
+                Some(name) if state.branches.contains_key(name) => (Some(name.to_string()), false),
+                // 既存のブランチを指定しない場合はディレクトリ名のブランチを作成する
+                _ => {
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let create = !state.branches.contains_key(&name);
+                    (Some(name), create)
+                }
+            }
+        };
+        if let Some(name) = &branch {
+            if let Some(other) = state.checked_out(name) {
+                return Err(TwinError::git(format!(
+                    "fatal: '{name}' is already checked out at '{}'",
+                    other.path.display()
+                )));
+            }
+        }
+
+        std::fs::create_dir_all(&path).map_err(|e| {
+            TwinError::io(
+                format!("Failed to create worktree: {e}"),
+                Some(path.clone()),
+            )
+        })?;
+        let commit = match &branch {
+            Some(name) if create => {
+                state.branches.insert(name.clone(), start.clone());
+                start
+            }
+            Some(name) => state.branches[name].clone(),
+            None => start,
+        };
+        state.worktrees.push(WorktreeInfo {
+            path,
+            branch: branch
+                .map(|name| format!("refs/heads/{name}"))
+                .unwrap_or_default(),
+            commit,
+            locked: lock,
+            ..Default::default()
+        });
+        state.calls.push(format!("worktree add {}", args.join(" ")));
+        Ok(String::new())
+    }
+
+    fn remove_worktree(&mut self, path: &Path, force: bool) -> TwinResult<()> {
+        let path = self.resolve_path(path);
+        let mut state = self.state();
+        let index = state.worktree_index(&path)?;
+        if index == 0 {
+            return Err(TwinError::git(format!(
+                "fatal: '{}' is a main working tree",
+                path.display()
+            )));
+        }
+        if state.worktrees[index].locked && !force {
+            return Err(TwinError::git(format!(
+                "fatal: cannot remove a locked working tree: {}",
+                path.display()
+            )));
+        }
+
+        if path.exists() {
+            std::fs::remove_dir_all(&path).map_err(|e| {
+                TwinError::io(
+                    format!("Failed to remove worktree: {e}"),
+                    Some(path.clone()),
+                )
+            })?;
+        }
+        state.worktrees.remove(index);
+        state.calls.push(format!(
+            "worktree remove {}{}",
+            if force { "--force " } else { "" },
+            path.display()
+        ));
+        Ok(())
+    }
+
+    fn prune_worktrees(&mut self, dry_run: bool) -> TwinResult<Vec<PrunedWorktree>> {
+        let mut state = self.state();
+        // メインのWorktreeとロックされたWorktreeは対象外
+        let pruned: Vec<PrunedWorktree> = state
+            .worktrees
+            .iter()
+            .skip(1)
+            .filter(|wt| !wt.locked && !wt.path.exists())
+            .map(|wt| PrunedWorktree {
+                id: wt
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: Some(wt.path.clone()),
+                branch: wt.branch.strip_prefix("refs/heads/").map(String::from),
+                reason: "gitdir file points to non-existent location".to_string(),
+            })
+            .collect();
+
+        if !dry_run {
+            state
+                .worktrees
+                .retain(|wt| !pruned.iter().any(|p| p.path.as_ref() == Some(&wt.path)));
+            state.calls.push("worktree prune --verbose".to_string());
+        }
+        Ok(pruned)
+    }
+
+    fn repair_worktrees(&mut self, paths: &[&Path]) -> TwinResult<()> {
+        let mut state = self.state();
+        let mut call = "worktree repair".to_string();
+        for path in paths {
+            let path = self.resolve_path(path);
+            state.worktree_index(&path)?;
+            call.push_str(&format!(" {}", path.display()));
+        }
+        state.calls.push(call);
+        Ok(())
+    }
+
+    fn lock_worktree(&mut self, path: &Path, reason: Option<&str>) -> TwinResult<()> {
+        let path = self.resolve_path(path);
+        let mut state = self.state();
+        let index = state.worktree_index(&path)?;
+        if index == 0 {
+            return Err(TwinError::git(
+                "fatal: The main working tree cannot be locked or unlocked",
+            ));
+        }
+        let worktree = &mut state.worktrees[index];
+        if worktree.locked {
+            return Err(TwinError::git(format!(
+                "fatal: '{}' is already locked",
+                path.display()
+            )));
+        }
+        worktree.locked = true;
+        worktree.lock_reason = reason.map(String::from);
+        state.calls.push(match reason {
+            Some(reason) => format!("worktree lock --reason {reason} {}", path.display()),
+            None => format!("worktree lock {}", path.display()),
+        });
+        Ok(())
+    }
+
+    fn unlock_worktree(&mut self, path: &Path) -> TwinResult<()> {
+        let path = self.resolve_path(path);
+        let mut state = self.state();
+        let index = state.worktree_index(&path)?;
+        let worktree = &mut state.worktrees[index];
+        if !worktree.locked {
+            return Err(TwinError::git(format!(
+                "fatal: '{}' is not locked",
+                path.display()
+            )));
+        }
+        worktree.locked = false;
+        worktree.lock_reason = None;
+        state
+            .calls
+            .push(format!("worktree unlock {}", path.display()));
+        Ok(())
+    }
+
+    fn count_changes(&mut self, path: &Path) -> TwinResult<usize> {
+        let status = self.state().status(&self.resolve_path(path))?;
+        Ok(status.changed + status.untracked.len())
+    }
+
+    fn worktree_status(&mut self, path: &Path) -> TwinResult<WorktreeGitStatus> {
+        let path = self.resolve_path(path);
+        let state = self.state();
+        let status = state.status(&path)?;
+        let worktree = &state.worktrees[state.worktree_index(&path)?];
+        // 上流ブランチは持たないので、上流との差分は常に0
+        Ok(WorktreeGitStatus {
+            head: Some(worktree.commit.clone()),
+            branch: worktree
+                .branch
+                .strip_prefix("refs/heads/")
+                .map(String::from),
+            changed: status.changed,
+            untracked: status.untracked.len(),
+            ..Default::default()
+        })
+    }
+
+    fn list_untracked_paths(&mut self, path: &Path) -> TwinResult<Vec<PathBuf>> {
+        Ok(self.state().status(&self.resolve_path(path))?.untracked)
+    }
+
+    fn ahead_behind(&mut self, path: &Path, base: &str) -> TwinResult<(usize, usize)> {
+        self.resolve_commit(base)?;
+        let status = self.state().status(&self.resolve_path(path))?;
+        Ok((status.ahead, status.behind))
+    }
+
+    fn list_branches(&mut self, remote: bool) -> TwinResult<Vec<BranchInfo>> {
+        if remote {
+            return Ok(Vec::new());
+        }
+        let state = self.state();
+        let current = state.worktrees[0].branch.strip_prefix("refs/heads/");
+        Ok(state
+            .branches
+            .iter()
+            .map(|(name, commit)| BranchInfo {
+                name: name.clone(),
+                commit: commit.clone(),
+                current: current == Some(name.as_str()),
+                worktree: state.checked_out(name).map(|wt| wt.path.clone()),
+                ..Default::default()
+            })
+            .collect())
+    }
+
+    fn branch_exists(&mut self, name: &str) -> TwinResult<bool> {
+        Ok(self.state().branches.contains_key(name))
+    }
+
+    fn current_branch(&mut self) -> TwinResult<String> {
+        let state = self.state();
+        Ok(state.worktrees[0]
+            .branch
+            .strip_prefix("refs/heads/")
+            .unwrap_or("HEAD")
+            .to_string())
+    }
+
+    fn resolve_commit(&mut self, rev: &str) -> TwinResult<String> {
+        if rev == "HEAD" {
+            return Ok(self.head_commit());
+        }
+        let state = self.state();
+        if let Some(commit) = state.branches.get(rev) {
+            return Ok(commit.clone());
+        }
+        if rev.len() == 40 && rev.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(rev.to_string());
+        }
+        Err(TwinError::git(format!("fatal: invalid reference: {rev}")))
+    }
+
+    fn create_branch(&mut self, name: &str, start_point: Option<&str>) -> TwinResult<()> {
+        let commit = self.resolve_commit(start_point.unwrap_or("HEAD"))?;
+        let mut state = self.state();
+        if state.branches.contains_key(name) {
+            return Err(TwinError::git(format!(
+                "fatal: a branch named '{name}' already exists"
+            )));
+        }
+        state.branches.insert(name.to_string(), commit);
+        state.calls.push(format!("branch {name}"));
+        Ok(())
+    }
+
+    fn delete_branch(&mut self, name: &str, force: bool) -> TwinResult<()> {
+        let mut state = self.state();
+        if let Some(worktree) = state.checked_out(name) {
+            return Err(TwinError::git(format!(
+                "error: Cannot delete branch '{name}' checked out at '{}'",
+                worktree.path.display()
+            )));
+        }
+        if state.branches.remove(name).is_none() {
+            return Err(TwinError::git(format!("error: branch '{name}' not found")));
+        }
+        state
+            .calls
+            .push(format!("branch {} {name}", if force { "-D" } else { "-d" }));
+        Ok(())
+    }
+
+    /// コミットの履歴は持たないため、`base`と同じコミットを指すブランチをマージ済みとする
+    fn merged_branches(&mut self, base: &str) -> TwinResult<Vec<String>> {
+        let base = self.resolve_commit(base)?;
+        Ok(self
+            .state()
+            .branches
+            .iter()
+            .filter(|(_, commit)| **commit == base)
+            .map(|(name, _)| format!("refs/heads/{name}"))
+            .collect())
+    }
+
+    fn commit_summary(&mut self, commit: &str) -> TwinResult<(DateTime<Local>, String)> {
+        let commit = self.resolve_commit(commit)?;
+        self.state()
+            .commits
+            .get(&commit)
+            .cloned()
+            .ok_or_else(|| TwinError::git(format!("fatal: bad object {commit}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_fake_backend_tracks_worktrees_and_branches() {
+        let temp = TempDir::new().unwrap();
+        let mut fake = FakeBackend::new(&temp.path().join("repo"));
+        let worktree = temp.path().join("feature");
+
+        fake.add_worktree(&["-b", "feature", &worktree.to_string_lossy()])
+            .unwrap();
+        assert!(worktree.is_dir());
+        assert!(fake.branch_exists("feature").unwrap());
+        assert!(fake
+            .add_worktree(&["-b", "feature", "other"])
+            .unwrap_err()
+            .to_string()
+            .contains("already exists"));
+        assert!(fake.delete_branch("feature", true).is_err());
+
+        // ディレクトリがなくなったWorktreeはpruneの対象
+        std::fs::remove_dir_all(&worktree).unwrap();
+        let pruned = fake.prune_worktrees(true).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].branch.as_deref(), Some("feature"));
+        assert_eq!(fake.list_worktrees().unwrap().len(), 2);
+        fake.prune_worktrees(false).unwrap();
+        assert_eq!(fake.list_worktrees().unwrap().len(), 1);
+
+        fake.delete_branch("feature", false).unwrap();
+        assert_eq!(
+            fake.state().calls,
+            vec![
+                format!("worktree add -b feature {}", worktree.display()),
+                "worktree prune --verbose".to_string(),
+                "branch -d feature".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fake_backend_locks_and_reports_status() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("repo");
+        let mut fake = FakeBackend::new(&root);
+        let worktree = temp.path().join("feature");
+        fake.add_worktree(&["-b", "feature", &worktree.to_string_lossy()])
+            .unwrap();
+
+        fake.lock_worktree(&worktree, Some("busy")).unwrap();
+        assert!(fake.lock_worktree(&worktree, None).is_err());
+        assert!(fake.lock_worktree(&root, None).is_err());
+        assert_eq!(
+            fake.list_worktrees().unwrap()[1].lock_reason.as_deref(),
+            Some("busy")
+        );
+        fake.unlock_worktree(&worktree).unwrap();
+        assert!(fake.unlock_worktree(&worktree).is_err());
+
+        fake.state().statuses.insert(
+            worktree.clone(),
+            FakeWorktreeStatus {
+                changed: 2,
+                untracked: vec![PathBuf::from("target")],
+                ahead: 1,
+                behind: 3,
+            },
+        );
+        assert_eq!(fake.count_changes(&worktree).unwrap(), 3);
+        assert_eq!(fake.count_changes(&root).unwrap(), 0);
+        let status = fake.worktree_status(&worktree).unwrap();
+        assert_eq!(status.branch.as_deref(), Some("feature"));
+        assert!(status.is_dirty());
+        assert_eq!(fake.ahead_behind(&worktree, "main").unwrap(), (1, 3));
+        assert!(fake.ahead_behind(&worktree, "missing").is_err());
+
+        // 同じコミットから作成したブランチはマージ済み
+        assert_eq!(
+            fake.merged_branches("main").unwrap(),
+            vec!["refs/heads/feature", "refs/heads/main"]
+        );
+        let (_, subject) = fake.commit_summary("feature").unwrap();
+        assert_eq!(subject, "commit 1");
+        assert_eq!(
+            fake.state().calls[1..],
+            [
+                format!("worktree lock --reason busy {}", worktree.display()),
+                format!("worktree unlock {}", worktree.display()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! フック実行ログモジュール
+//!
+//! このモジュールの役割：
+//! - フックの実行結果をワークツリーごとのJSON Lines形式で記録
+//! - ログの保存先（`<git common dir>/twin/logs/<worktree>-<hash>.jsonl`）の解決
+//! - `twin logs`コマンド向けの読み込み
+
+#![allow(dead_code)]
+use crate::core::{TwinError, TwinResult};
+use crate::hooks::{HookContext, HookResult};
+use crate::manifest::{worktree_key, worktree_key_name};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// ログ1件分のフック実行結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookLogEntry {
+    /// 実行完了時刻
+    pub timestamp: DateTime<Utc>,
+    /// ワークツリーのパス
+    pub worktree: PathBuf,
+    /// ブランチ名
+    pub branch: String,
+    /// フックタイプ（pre_create など）
+    pub hook_type: String,
+    /// 展開後のコマンド文字列
+    pub command: String,
+    /// 実行成功したか
+    pub success: bool,
+    /// 終了コード
+    pub exit_code: Option<i32>,
+    /// タイムアウトにより強制終了されたか
+    #[serde(default)]
+    pub timed_out: bool,
+    /// 実行時間（ミリ秒）
+    pub duration_ms: u64,
+    /// 標準出力
+    pub stdout: String,
+    /// 標準エラー出力
+    pub stderr: String,
+}
+
+impl HookLogEntry {
+    /// フックの実行結果からログエントリを作成
+    pub fn from_result(result: &HookResult, context: &HookContext) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            worktree: context.worktree_path.clone(),
+            branch: context.branch.clone(),
+            hook_type: result.hook_type.as_str().to_string(),
+            command: result.command.clone(),
+            success: result.success,
+            exit_code: result.exit_code,
+            timed_out: result.timed_out,
+            duration_ms: u64::try_from(result.duration_ms).unwrap_or(u64::MAX),
+            stdout: result.stdout.clone(),
+            stderr: result.stderr.clone(),
+        }
+    }
+}
+
+/// ワークツリー単位のフック実行ログ
+#[derive(Debug, Clone)]
+pub struct HookLog {
+    /// ログファイルのパス
+    path: PathBuf,
+}
+
+impl HookLog {
+    /// ログファイルのパスを指定して作成
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// ワークツリーのログを取得（`<twin_dir>/logs/<ワークツリー名>-<パスのハッシュ>.jsonl`）
+    ///
+    /// 別のディレクトリにある同名のワークツリーと区別するため、マニフェストと同じキーをファイル名に使う。
+    pub fn for_worktree(twin_dir: &Path, worktree: &Path) -> Self {
+        Self::new(
+            twin_dir
+                .join("logs")
+                .join(format!("{}.jsonl", worktree_key(worktree))),
+        )
+    }
+
+    /// ワークツリー名が一致するログをすべて名前順に取得する（以前の形式の`<ワークツリー名>.jsonl`も含む）
+    ///
+    /// 削除済みのワークツリーのようにパスが分からない場合や、以前の形式のログを参照する場合に使う。
+    pub fn find_by_name(twin_dir: &Path, name: &str) -> TwinResult<Vec<Self>> {
+        let dir = twin_dir.join("logs");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let matches = path.extension().and_then(|e| e.to_str()) == Some("jsonl")
+                && path
+                    .file_stem()
+                    .is_some_and(|stem| worktree_key_name(&stem.to_string_lossy()) == name);
+            if matches {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths.into_iter().map(Self::new).collect())
+    }
+
+    /// ログファイルのパスを取得
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// エントリを1行追記する
+    pub fn append(&self, entry: &HookLogEntry) -> TwinResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let line = serde_json::to_string(entry).map_err(|e| {
+            TwinError::io(
+                format!("Failed to serialize hook log entry: {e}"),
+                Some(self.path.clone()),
+            )
+        })?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{line}")?;
+        Ok(())
+    }
+
+    /// すべてのエントリを古い順に読み込む（ログがない場合は空）
+    pub fn read(&self) -> TwinResult<Vec<HookLogEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)?;
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    TwinError::io(
+                        format!("Invalid hook log entry at line {}: {e}", index + 1),
+                        Some(self.path.clone()),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::HookType;
+    use tempfile::TempDir;
+
+    fn result(success: bool) -> HookResult {
+        HookResult {
+            hook_type: HookType::PostCreate,
+            command: "npm install".to_string(),
+            success,
+            exit_code: Some(if success { 0 } else { 1 }),
+            stdout: "installed\n".to_string(),
+            stderr: String::new(),
+            duration_ms: 42,
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn test_log_path_distinguishes_same_named_worktrees() {
+        let twin_dir = Path::new("/repo/.git/twin");
+        let log = HookLog::for_worktree(twin_dir, Path::new("/work/agent-1"));
+        assert!(log.path().starts_with("/repo/.git/twin/logs"));
+        assert!(log
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("agent-1-"));
+        assert_ne!(
+            log.path(),
+            HookLog::for_worktree(twin_dir, Path::new("/other/agent-1")).path()
+        );
+    }
+
+    #[test]
+    fn test_find_by_name_includes_legacy_logs() {
+        let temp = TempDir::new().unwrap();
+        let context = HookContext::new("agent-1", "/work/agent-1", "agent-1", "/repo");
+        for worktree in ["/work/agent-1", "/other/agent-1", "/work/agent-10"] {
+            HookLog::for_worktree(temp.path(), Path::new(worktree))
+                .append(&HookLogEntry::from_result(&result(true), &context))
+                .unwrap();
+        }
+        HookLog::new(temp.path().join("logs/agent-1.jsonl"))
+            .append(&HookLogEntry::from_result(&result(true), &context))
+            .unwrap();
+
+        let logs = HookLog::find_by_name(temp.path(), "agent-1").unwrap();
+        assert_eq!(logs.len(), 3);
+        assert!(logs
+            .iter()
+            .any(|log| log.path() == temp.path().join("logs/agent-1.jsonl")));
+    }
+
+    #[test]
+    fn test_append_and_read_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let log = HookLog::for_worktree(temp.path(), Path::new("agent-1"));
+        let context = HookContext::new("agent-1", "/work/agent-1", "agent-1", "/repo");
+
+        assert!(log.read().unwrap().is_empty());
+
+        log.append(&HookLogEntry::from_result(&result(true), &context))
+            .unwrap();
+        log.append(&HookLogEntry::from_result(&result(false), &context))
+            .unwrap();
+
+        let entries = log.read().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].success);
+        assert!(!entries[1].success);
+        assert_eq!(entries[1].hook_type, "post_create");
+        assert_eq!(entries[1].exit_code, Some(1));
+        assert_eq!(entries[0].stdout, "installed\n");
+    }
+}
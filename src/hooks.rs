@@ -3,15 +3,32 @@
 //! このモジュールの役割：
 //! - pre_create/post_create/pre_remove/post_remove フックの実行
 //! - フック実行時のエラーハンドリングと継続/中断制御
-//! - フック実行ログの表示
-//! - 環境変数の設定と引数の展開
+//! - フック実行ログの表示（出力のリアルタイム表示）
+//! - 環境変数の設定とプレースホルダー（`{name}`/`${NAME}`）の展開
+//! - 展開した値のシェル向けエスケープ
+//! - 実行結果のワークツリーごとのログへの記録
+//! - タイムアウト時のプロセスグループ単位での強制終了
+//! - 実行中のSIGINT/SIGTERMの捕捉と、フックのプロセスグループの終了
 
 #![allow(dead_code)]
 use crate::core::{HookCommand, TwinError, TwinResult};
+use crate::hook_log::{HookLog, HookLogEntry};
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::{Command, Output};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 子プロセスの終了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// フックの終了後（強制終了後を含む）に出力の読み取りを待つ時間
+///
+/// バックグラウンドに残ったプロセスがパイプを開いたままにしていても、この時間で読み取りを打ち切る。
+const OUTPUT_GRACE_PERIOD: Duration = Duration::from_secs(2);
 
 /// フックのタイプ
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,6 +55,22 @@ impl HookType {
     }
 }
 
+impl std::str::FromStr for HookType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace('-', "_").as_str() {
+            "pre_create" => Ok(HookType::PreCreate),
+            "post_create" => Ok(HookType::PostCreate),
+            "pre_remove" => Ok(HookType::PreRemove),
+            "post_remove" => Ok(HookType::PostRemove),
+            _ => Err(format!(
+                "unknown hook type '{s}' (expected pre_create, post_create, pre_remove or post_remove)"
+            )),
+        }
+    }
+}
+
 /// フック実行の結果
 #[derive(Debug)]
 pub struct HookResult {
@@ -55,6 +88,17 @@ pub struct HookResult {
     pub stderr: String,
     /// 実行時間（ミリ秒）
     pub duration_ms: u128,
+    /// タイムアウトにより強制終了されたか
+    pub timed_out: bool,
+}
+
+/// 子プロセスの実行結果
+struct CommandOutput {
+    /// 終了ステータス（タイムアウトで強制終了した場合はNone）
+    status: Option<ExitStatus>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    timed_out: bool,
 }
 
 /// フック実行のコンテキスト情報
@@ -70,6 +114,10 @@ pub struct HookContext {
     pub project_root: PathBuf,
     /// 追加の環境変数
     pub env_vars: HashMap<String, String>,
+    /// ワークツリーのHEADコミット（作成前は作成元のコミット）
+    pub commit: Option<String>,
+    /// 作成元のブランチ
+    pub base_branch: Option<String>,
 }
 
 impl HookContext {
@@ -86,9 +134,47 @@ impl HookContext {
             branch: branch.into(),
             project_root: project_root.into(),
             env_vars: HashMap::new(),
+            commit: None,
+            base_branch: None,
         }
     }
 
+    /// コミットを設定
+    pub fn set_commit(&mut self, commit: impl Into<String>) {
+        self.commit = Some(commit.into());
+    }
+
+    /// 作成元のブランチを設定
+    pub fn set_base_branch(&mut self, base_branch: impl Into<String>) {
+        self.base_branch = Some(base_branch.into());
+    }
+
+    /// ワークツリー名（ワークツリーのディレクトリ名）
+    pub fn worktree_name(&self) -> String {
+        file_name(&self.worktree_path)
+    }
+
+    /// リポジトリ名（プロジェクトルートのディレクトリ名）
+    pub fn repo_name(&self) -> String {
+        file_name(&self.project_root)
+    }
+
+    /// プレースホルダーやテンプレートで参照できる変数を取得
+    ///
+    /// 未設定のコミットや作成元ブランチは空文字列になる。
+    pub fn variables(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("agent_name", self.agent_name.clone()),
+            ("worktree_path", self.worktree_path.display().to_string()),
+            ("worktree_name", self.worktree_name()),
+            ("branch", self.branch.clone()),
+            ("base_branch", self.base_branch.clone().unwrap_or_default()),
+            ("commit", self.commit.clone().unwrap_or_default()),
+            ("project_root", self.project_root.display().to_string()),
+            ("repo_name", self.repo_name()),
+        ]
+    }
+
     /// 環境変数を追加
     pub fn add_env_var(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.env_vars.insert(key.into(), value.into());
@@ -98,30 +184,36 @@ impl HookContext {
     pub fn as_env_vars(&self) -> HashMap<String, String> {
         let mut vars = self.env_vars.clone();
 
-        // 標準のコンテキスト変数を追加
-        vars.insert("TWIN_AGENT_NAME".to_string(), self.agent_name.clone());
-        vars.insert(
-            "TWIN_WORKTREE_PATH".to_string(),
-            self.worktree_path.display().to_string(),
-        );
-        vars.insert("TWIN_BRANCH".to_string(), self.branch.clone());
-        vars.insert(
-            "TWIN_PROJECT_ROOT".to_string(),
-            self.project_root.display().to_string(),
-        );
+        // 標準のコンテキスト変数を追加（TWIN_AGENT_NAME, TWIN_BRANCH など）
+        for (name, value) in self.variables() {
+            vars.insert(format!("TWIN_{}", name.to_uppercase()), value);
+        }
 
         vars
     }
 }
 
+/// パスの最後の要素を文字列として取得
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
 /// フック実行マネージャー
 pub struct HookExecutor {
     /// ドライランモード
     dry_run: bool,
-    /// タイムアウト（秒）
+    /// タイムアウト（秒）。フックのtimeoutが0の場合に使用し、0ならタイムアウトなし
     timeout_seconds: u64,
     /// エラー時に続行するか
     continue_on_error: bool,
+    /// 出力のリアルタイム表示を抑制するか（出力はHookResultに記録される）
+    quiet: bool,
+    /// 実行中に受け取ったシグナル（0は未受信）。一度中断したら、このマネージャーでは以降のフックを実行しない
+    interrupted: AtomicI32,
+    /// 実行結果を記録するログ
+    log: Option<HookLog>,
 }
 
 impl HookExecutor {
@@ -131,9 +223,22 @@ impl HookExecutor {
             dry_run: false,
             timeout_seconds: 30,
             continue_on_error: false,
+            quiet: false,
+            interrupted: AtomicI32::new(0),
+            log: None,
         }
     }
 
+    /// 実行結果を記録するログを設定
+    pub fn set_log(&mut self, log: HookLog) {
+        self.log = Some(log);
+    }
+
+    /// 出力のリアルタイム表示を抑制するかを設定
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
     /// ドライランモードを設定
     pub fn set_dry_run(&mut self, dry_run: bool) {
         self.dry_run = dry_run;
@@ -158,18 +263,7 @@ impl HookExecutor {
     ) -> TwinResult<HookResult> {
         info!("Executing {} hook: {}", hook_type.as_str(), hook.command);
 
-        // コマンドと引数を展開
-        let expanded_command = self.expand_command(&hook.command, context);
-        let expanded_args = if hook.args.is_empty() {
-            None
-        } else {
-            Some(
-                hook.args
-                    .iter()
-                    .map(|arg| self.expand_command(arg, context))
-                    .collect::<Vec<_>>(),
-            )
-        };
+        let (expanded_command, expanded_args) = self.expand_hook(hook_type, hook, context)?;
 
         if self.dry_run {
             info!("[DRY RUN] Would execute: {expanded_command}");
@@ -185,23 +279,77 @@ impl HookExecutor {
                 stdout: "[DRY RUN]".to_string(),
                 stderr: String::new(),
                 duration_ms: 0,
+                timed_out: false,
             });
         }
 
-        // 実際にコマンドを実行
+        // 既に中断されている場合は残りのフックを実行しない
+        match self.interrupted.load(Ordering::SeqCst) {
+            0 => {}
+            signal => return Err(TwinError::interrupted(signal)),
+        }
+
+        // 実際にコマンドを実行（フックごとのタイムアウトを優先）
+        let timeout_seconds = if hook.timeout > 0 {
+            hook.timeout
+        } else {
+            self.timeout_seconds
+        };
+        // 出力は標準エラー出力にフックタイプ付きで表示する（標準出力は--print-path等で使用）
+        let prefix = (!self.quiet).then(|| format!("[{}] ", hook_type.as_str()));
+        if let Some(prefix) = &prefix {
+            match &expanded_args {
+                Some(args) => eprintln!("{prefix}$ {expanded_command} {}", args.join(" ")),
+                None => eprintln!("{prefix}$ {expanded_command}"),
+            }
+        }
+
         let start_time = std::time::Instant::now();
-        let result = self.execute_command(&expanded_command, expanded_args.as_deref(), context)?;
+        let result = self
+            .execute_command(
+                &expanded_command,
+                expanded_args.as_deref(),
+                context,
+                timeout_seconds,
+                prefix.as_deref(),
+            )
+            .inspect_err(|e| {
+                if let TwinError::Interrupted { signal } = e {
+                    self.interrupted.store(*signal, Ordering::SeqCst);
+                }
+            })?;
         let duration_ms = start_time.elapsed().as_millis();
 
         let hook_result = HookResult {
             hook_type,
             command: expanded_command.clone(),
-            success: result.status.success(),
-            exit_code: result.status.code(),
+            success: result.status.is_some_and(|status| status.success()) && !result.timed_out,
+            exit_code: result.status.and_then(|status| status.code()),
             stdout: String::from_utf8_lossy(&result.stdout).to_string(),
             stderr: String::from_utf8_lossy(&result.stderr).to_string(),
             duration_ms,
+            timed_out: result.timed_out,
         };
+        self.record(&hook_result, context);
+
+        if hook_result.timed_out {
+            error!(
+                "{} hook timed out after {}s: {}",
+                hook_type.as_str(),
+                timeout_seconds,
+                expanded_command
+            );
+
+            if !hook.continue_on_error {
+                return Err(TwinError::hook_timeout(
+                    expanded_command,
+                    hook_type.as_str(),
+                    timeout_seconds,
+                ));
+            }
+            warn!("Continuing despite hook timeout (continue_on_error=true)");
+            return Ok(hook_result);
+        }
 
         // ログ出力
         if hook_result.success {
@@ -238,6 +386,15 @@ impl HookExecutor {
         Ok(hook_result)
     }
 
+    /// 実行結果をログに追記（記録の失敗でフックの結果は変えない）
+    fn record(&self, result: &HookResult, context: &HookContext) {
+        if let Some(log) = &self.log {
+            if let Err(e) = log.append(&HookLogEntry::from_result(result, context)) {
+                warn!("Failed to write hook log {}: {e}", log.path().display());
+            }
+        }
+    }
+
     /// 複数のフックを順次実行
     pub fn execute_hooks(
         &self,
@@ -258,7 +415,7 @@ impl HookExecutor {
                     }
                 }
                 Err(e) => {
-                    if !hook.continue_on_error {
+                    if !hook.continue_on_error || e.is_interrupted() {
                         return Err(e);
                     }
                     warn!("Hook execution error (continuing): {e}");
@@ -269,37 +426,66 @@ impl HookExecutor {
         Ok(results)
     }
 
-    /// コマンド内の変数を展開
-    fn expand_command(&self, command: &str, context: &HookContext) -> String {
-        let mut result = command.to_string();
-
-        // 基本的な変数展開
-        result = result.replace("${AGENT_NAME}", &context.agent_name);
-        result = result.replace(
-            "${WORKTREE_PATH}",
-            &context.worktree_path.display().to_string(),
-        );
-        result = result.replace("${BRANCH}", &context.branch);
-        result = result.replace(
-            "${PROJECT_ROOT}",
-            &context.project_root.display().to_string(),
-        );
+    /// フックのコマンドを引数も含めて展開し、実行されるコマンドラインを返す（実行はしない）
+    pub fn expand(
+        &self,
+        hook_type: HookType,
+        hook: &HookCommand,
+        context: &HookContext,
+    ) -> TwinResult<String> {
+        let (command, args) = self.expand_hook(hook_type, hook, context)?;
+        Ok(match args {
+            Some(args) => format!("{command} {}", args.join(" ")),
+            None => command,
+        })
+    }
 
-        // 追加の環境変数を展開
-        for (key, value) in &context.env_vars {
-            result = result.replace(&format!("${{{key}}}"), value);
+    /// コマンドと引数を展開（引数はそれぞれ1つのシェル引数としてクォートする）
+    fn expand_hook(
+        &self,
+        hook_type: HookType,
+        hook: &HookCommand,
+        context: &HookContext,
+    ) -> TwinResult<(String, Option<Vec<String>>)> {
+        let command = self.expand_command(&hook.command, context, hook_type)?;
+        if hook.args.is_empty() {
+            return Ok((command, None));
         }
 
-        result
+        let args = hook
+            .args
+            .iter()
+            .map(|arg| {
+                expand_placeholders(arg, context, hook_type, false).map(|arg| shell_quote(&arg))
+            })
+            .collect::<TwinResult<Vec<_>>>()?;
+        Ok((command, Some(args)))
+    }
+
+    /// コマンド内の変数を展開
+    ///
+    /// 値はクォートの状況に応じてエスケープされるため、空白や引用符を含んでも1つの値として渡る。
+    fn expand_command(
+        &self,
+        command: &str,
+        context: &HookContext,
+        hook_type: HookType,
+    ) -> TwinResult<String> {
+        expand_placeholders(command, context, hook_type, true)
     }
 
     /// 実際にコマンドを実行
+    ///
+    /// `timeout_seconds`が0より大きい場合、期限を過ぎるとプロセスグループごと強制終了する。
+    /// `prefix`が指定されている場合は、出力を行単位でその接頭辞付きで表示する。
     fn execute_command(
         &self,
         command: &str,
         args: Option<&[String]>,
         context: &HookContext,
-    ) -> TwinResult<Output> {
+        timeout_seconds: u64,
+        prefix: Option<&str>,
+    ) -> TwinResult<CommandOutput> {
         let mut cmd = if cfg!(windows) {
             let mut c = Command::new("cmd");
             c.arg("/C");
@@ -337,28 +523,424 @@ impl HookExecutor {
         debug!("Executing command: {full_command}");
         debug!("Working directory: {:?}", context.worktree_path);
 
-        // タイムアウトを考慮した実行
-        let output = if self.timeout_seconds > 0 {
-            // タイムアウト付き実行（簡易実装）
-            // 実際のプロダクションコードではtokio::time::timeoutなどを使用
-            cmd.output().map_err(|e| {
-                TwinError::hook(
-                    format!("Failed to execute hook command: {e}"),
-                    command.to_string(),
-                    None,
-                )
-            })?
-        } else {
-            cmd.output().map_err(|e| {
-                TwinError::hook(
-                    format!("Failed to execute hook command: {e}"),
-                    command.to_string(),
-                    None,
-                )
-            })?
+        // 孫プロセスもまとめて終了できるよう、独立したプロセスグループで起動する
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        configure_process_group(&mut cmd);
+
+        // 端末のCtrl-Cはフォアグラウンドのtwinにだけ届くため、捕捉してフックのプロセスグループを終了する
+        let _signals = SignalGuard::install();
+        let mut child = cmd.spawn().map_err(|e| {
+            TwinError::hook(
+                format!("Failed to execute hook command: {e}"),
+                command.to_string(),
+                None,
+            )
+        })?;
+
+        // 出力はパイプが詰まらないよう別スレッドで読み取る
+        let stdout_reader = spawn_reader(child.stdout.take(), prefix.map(str::to_string));
+        let stderr_reader = spawn_reader(child.stderr.take(), prefix.map(str::to_string));
+
+        let deadline =
+            (timeout_seconds > 0).then(|| Instant::now() + Duration::from_secs(timeout_seconds));
+        let mut timed_out = false;
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break Some(status);
+            }
+            if let Some(signal) = received_signal() {
+                warn!("Received signal {signal}, killing process group: {full_command}");
+                kill_process_group(&mut child);
+                let _ = child.wait();
+                return Err(TwinError::interrupted(signal));
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                warn!("Hook exceeded {timeout_seconds}s, killing process group: {full_command}");
+                kill_process_group(&mut child);
+                let _ = child.wait();
+                timed_out = true;
+                break None;
+            }
+            std::thread::sleep(POLL_INTERVAL);
         };
 
-        Ok(output)
+        // バックグラウンドに残ったプロセスがパイプを開いたままにしている場合も猶予期間で打ち切る
+        // （残ったプロセスはフック自体の結果に影響させず、終了もさせない）
+        let drain_deadline = Instant::now() + OUTPUT_GRACE_PERIOD;
+        let stdout = stdout_reader.collect(drain_deadline);
+        let stderr = stderr_reader.collect(drain_deadline);
+
+        Ok(CommandOutput {
+            status,
+            stdout,
+            stderr,
+            timed_out,
+        })
+    }
+}
+
+/// シェル上のクォートの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    None,
+    Single,
+    Double,
+}
+
+/// `{name}`/`{{name}}`/`${NAME}`形式のプレースホルダーを展開
+///
+/// - `{name}`と`{{name}}`は小文字の変数名を参照し、未定義ならエラーになる（`{env.KEY}`で環境変数）
+/// - `${NAME}`は大文字の変数名を参照し、未定義ならシェル変数としてそのまま残す
+/// - 識別子以外を含む波括弧（`{}`、`{a,b}`、`{ print }`など）は展開しない
+///
+/// `escape`が真の場合、値はその位置のクォートの状態に合わせてエスケープする。
+fn expand_placeholders(
+    input: &str,
+    context: &HookContext,
+    hook_type: HookType,
+    escape: bool,
+) -> TwinResult<String> {
+    let mut variables: HashMap<String, String> = context
+        .variables()
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    variables.insert("hook_type".to_string(), hook_type.as_str().to_string());
+
+    // ${NAME}形式は標準の変数と追加の環境変数を参照する
+    let mut shell_variables: HashMap<String, String> = variables
+        .iter()
+        .map(|(name, value)| (name.to_uppercase(), value.clone()))
+        .collect();
+    shell_variables.extend(context.env_vars.clone());
+
+    // {name}形式ではドキュメントで使われている短い別名も使える
+    variables.insert("name".to_string(), context.agent_name.clone());
+    variables.insert(
+        "path".to_string(),
+        context.worktree_path.display().to_string(),
+    );
+
+    let lookup = |name: &str| {
+        variables.get(name).cloned().or_else(|| {
+            let key = name.strip_prefix("env.")?;
+            context
+                .env_vars
+                .get(key)
+                .cloned()
+                .or_else(|| std::env::var(key).ok())
+        })
+    };
+
+    let bytes = input.as_bytes();
+    let mut output = String::with_capacity(input.len());
+    let mut unknown: Vec<String> = Vec::new();
+    let mut quote = Quote::None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            // バックスラッシュの直後の文字は特別な意味を持たない
+            b'\\' if quote != Quote::Single && !cfg!(windows) => {
+                let next = input[i + 1..].chars().next().map_or(0, char::len_utf8);
+                output.push_str(&input[i..i + 1 + next]);
+                i += 1 + next;
+                continue;
+            }
+            b'\'' if quote != Quote::Double && !cfg!(windows) => {
+                quote = if quote == Quote::Single {
+                    Quote::None
+                } else {
+                    Quote::Single
+                };
+            }
+            b'"' if quote != Quote::Single => {
+                quote = if quote == Quote::Double {
+                    Quote::None
+                } else {
+                    Quote::Double
+                };
+            }
+            b'$' => {
+                if let Some((name, len)) = braced_identifier(&input[i + 1..]) {
+                    match shell_variables.get(name) {
+                        Some(value) => output.push_str(&escape_value(value, quote, escape)),
+                        None => output.push_str(&input[i..i + 1 + len]),
+                    }
+                    i += 1 + len;
+                    continue;
+                }
+            }
+            b'{' => {
+                if let Some((name, len)) = placeholder(&input[i..]) {
+                    match lookup(name) {
+                        Some(value) => output.push_str(&escape_value(&value, quote, escape)),
+                        // シングルクォート内の未知の`{...}`はawkやjqのプログラムとしてそのまま渡す
+                        None if quote == Quote::Single => output.push_str(&input[i..i + len]),
+                        None if !unknown.iter().any(|n| n == name) => {
+                            unknown.push(name.to_string())
+                        }
+                        None => {}
+                    }
+                    i += len;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        let len = input[i..].chars().next().map_or(1, char::len_utf8);
+        output.push_str(&input[i..i + len]);
+        i += len;
+    }
+
+    if !unknown.is_empty() {
+        let mut available: Vec<_> = variables.keys().map(|name| format!("{{{name}}}")).collect();
+        available.sort();
+        return Err(TwinError::hook(
+            format!(
+                "Unknown placeholder(s) in hook command: {} (available: {}, {{env.NAME}})",
+                unknown
+                    .iter()
+                    .map(|name| format!("{{{name}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                available.join(", ")
+            ),
+            hook_type.as_str(),
+            None,
+        ));
+    }
+
+    Ok(output)
+}
+
+/// `{name}`または`{{name}}`を解析し、変数名と消費したバイト数を返す
+fn placeholder(input: &str) -> Option<(&str, usize)> {
+    if let Some(inner) = input.strip_prefix("{{") {
+        let end = inner.find("}}")?;
+        let name = inner[..end].trim();
+        return is_identifier(name).then_some((name, end + 4));
+    }
+    braced_identifier(input)
+}
+
+/// `{NAME}`を解析し、変数名と消費したバイト数を返す
+fn braced_identifier(input: &str) -> Option<(&str, usize)> {
+    let inner = input.strip_prefix('{')?;
+    let end = inner.find('}')?;
+    let name = &inner[..end];
+    is_identifier(name).then_some((name, end + 2))
+}
+
+/// 変数名として有効か（英字または`_`で始まり、英数字・`_`・`.`のみ）
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// クォートの状態に合わせて値をエスケープ
+fn escape_value(value: &str, quote: Quote, escape: bool) -> String {
+    if !escape {
+        return value.to_string();
+    }
+
+    match quote {
+        Quote::None => shell_quote(value),
+        Quote::Single => value.replace('\'', r"'\''"),
+        Quote::Double if cfg!(windows) => value.replace('"', "\"\""),
+        Quote::Double => {
+            let mut escaped = String::with_capacity(value.len());
+            for c in value.chars() {
+                if matches!(c, '\\' | '"' | '$' | '`') {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            escaped
+        }
+    }
+}
+
+/// 値を1つのシェル引数として扱えるようにクォート（安全な文字のみの場合はそのまま）
+fn shell_quote(value: &str) -> String {
+    let is_safe = |c: char| {
+        c.is_ascii_alphanumeric()
+            || matches!(c, '_' | '-' | '.' | '/' | ':' | '@' | '%' | '+' | '=' | ',')
+            || (cfg!(windows) && c == '\\')
+    };
+    if !value.is_empty() && value.chars().all(is_safe) {
+        return value.to_string();
+    }
+
+    if cfg!(windows) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// 別スレッドで読み取っているパイプの出力
+struct OutputReader {
+    /// これまでに読み取った出力
+    buffer: Arc<Mutex<Vec<u8>>>,
+    /// パイプが閉じたことの通知
+    done: mpsc::Receiver<()>,
+}
+
+impl OutputReader {
+    /// パイプが閉じるまで`deadline`まで待ち、それまでに読み取った出力を返す
+    fn collect(&self, deadline: Instant) -> Vec<u8> {
+        let _ = self
+            .done
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        std::mem::take(&mut *self.buffer.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// パイプの内容を別スレッドで最後まで読み取る
+///
+/// `prefix`が指定されている場合は、1行読むごとに接頭辞を付けて標準エラー出力に表示する。
+fn spawn_reader<R: Read + Send + 'static>(pipe: Option<R>, prefix: Option<String>) -> OutputReader {
+    let (tx, done) = mpsc::channel();
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let shared = Arc::clone(&buffer);
+    std::thread::spawn(move || {
+        if let Some(pipe) = pipe {
+            let mut reader = BufReader::new(pipe);
+            let mut line = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                if let Some(prefix) = &prefix {
+                    let text = String::from_utf8_lossy(&line);
+                    eprintln!("{prefix}{}", text.trim_end_matches(['\r', '\n']));
+                }
+                shared
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .append(&mut line);
+            }
+        }
+        let _ = tx.send(());
+    });
+    OutputReader { buffer, done }
+}
+
+/// フックの実行中に受け取ったシグナル（0は未受信）
+///
+/// 最初のフックの実行開始時（ハンドラーの設置時）にクリアする。以降のフックを実行しないかは
+/// `HookExecutor`ごとに判定するため、`twin tui`のように長く動作するプロセスでも次の操作には影響しない。
+#[cfg(unix)]
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// 実行中のフックの数と、捕捉する前のシグナルハンドラー（並行実行されるフックで共有する）
+#[cfg(unix)]
+static SIGNAL_HANDLERS: Mutex<(usize, Vec<(libc::c_int, libc::sighandler_t)>)> =
+    Mutex::new((0, Vec::new()));
+
+#[cfg(unix)]
+extern "C" fn record_signal(signal: libc::c_int) {
+    RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+/// 受け取ったシグナルを取得
+#[cfg(unix)]
+fn received_signal() -> Option<i32> {
+    match RECEIVED_SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+#[cfg(windows)]
+fn received_signal() -> Option<i32> {
+    None
+}
+
+/// フックの実行中だけSIGINT/SIGTERMを捕捉する（最後のフックが終わると元のハンドラーに戻す）
+struct SignalGuard;
+
+impl SignalGuard {
+    fn install() -> Self {
+        #[cfg(unix)]
+        {
+            let mut handlers = SIGNAL_HANDLERS.lock().unwrap_or_else(|e| e.into_inner());
+            if handlers.0 == 0 {
+                RECEIVED_SIGNAL.store(0, Ordering::SeqCst);
+                for signal in [libc::SIGINT, libc::SIGTERM] {
+                    // SAFETY: ハンドラーはアトミック変数に書き込むだけで、async-signal-safe
+                    let previous = unsafe {
+                        libc::signal(
+                            signal,
+                            record_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+                        )
+                    };
+                    handlers.1.push((signal, previous));
+                }
+            }
+            handlers.0 += 1;
+        }
+        SignalGuard
+    }
+}
+
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let mut handlers = SIGNAL_HANDLERS.lock().unwrap_or_else(|e| e.into_inner());
+            handlers.0 -= 1;
+            if handlers.0 == 0 {
+                for (signal, previous) in handlers.1.drain(..) {
+                    // SAFETY: installで保存したハンドラーに戻すだけ
+                    unsafe { libc::signal(signal, previous) };
+                }
+            }
+        }
+    }
+}
+
+/// 子プロセスを新しいプロセスグループで起動するよう設定
+#[cfg(unix)]
+fn configure_process_group(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    cmd.process_group(0);
+}
+
+#[cfg(windows)]
+fn configure_process_group(cmd: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+/// プロセスグループ全体を強制終了
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    // process_group(0)で起動しているため、プロセスグループIDは子プロセスのPIDと同じ
+    let pgid = child.id() as libc::pid_t;
+    // SAFETY: killはシグナルを送るだけで、メモリ安全性に影響しない
+    let result = unsafe { libc::kill(-pgid, libc::SIGKILL) };
+    if result != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(windows)]
+fn kill_process_group(child: &mut Child) {
+    // /Tで子孫プロセスも含めて終了する
+    let status = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    if !status.is_ok_and(|s| s.success()) {
+        let _ = child.kill();
     }
 }
 
@@ -372,6 +954,7 @@ impl Default for HookExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
 
     #[test]
     fn test_hook_type_string() {
@@ -407,10 +990,13 @@ mod tests {
         );
 
         let executor = HookExecutor::new();
-        let expanded = executor.expand_command(
-            "echo 'Working on ${AGENT_NAME} in ${WORKTREE_PATH}'",
-            &context,
-        );
+        let expanded = executor
+            .expand_command(
+                "echo 'Working on ${AGENT_NAME} in ${WORKTREE_PATH}'",
+                &context,
+                HookType::PreCreate,
+            )
+            .unwrap();
 
         assert_eq!(
             expanded,
@@ -439,4 +1025,311 @@ mod tests {
         assert!(result.success);
         assert_eq!(result.stdout, "[DRY RUN]");
     }
+
+    #[test]
+    fn test_expand_includes_quoted_args() {
+        let executor = HookExecutor::new();
+        let context = HookContext::new("agent", "/work/my tree", "feature", "/repo");
+        let hook = HookCommand {
+            command: "echo {{branch}}".to_string(),
+            args: vec!["{{worktree_path}}".to_string()],
+            env: HashMap::new(),
+            timeout: 60,
+            continue_on_error: false,
+        };
+
+        let command = executor
+            .expand(HookType::PostCreate, &hook, &context)
+            .unwrap();
+        assert_eq!(command, "echo feature '/work/my tree'");
+    }
+
+    fn hook(command: &str, timeout: u64, continue_on_error: bool) -> HookCommand {
+        HookCommand {
+            command: command.to_string(),
+            args: vec![],
+            env: HashMap::new(),
+            timeout,
+            continue_on_error,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_timeout_returns_error() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+        let executor = HookExecutor::new();
+
+        let start = Instant::now();
+        let result = executor.execute(HookType::PostCreate, &hook("sleep 30", 1, false), &context);
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        match result {
+            Err(TwinError::HookTimeout {
+                hook_type,
+                timeout_secs,
+                ..
+            }) => {
+                assert_eq!(hook_type, "post_create");
+                assert_eq!(timeout_secs, 1);
+            }
+            other => panic!("Expected HookTimeout error, got {other:?}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_hook_timeout_kills_process_group() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+        let executor = HookExecutor::new();
+        let pid_file = temp.path().join("child.pid");
+
+        // バックグラウンドの孫プロセスも含めて終了されること
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let result = executor
+            .execute(HookType::PreCreate, &hook(&command, 1, true), &context)
+            .unwrap();
+
+        assert!(result.timed_out);
+        assert!(!result.success);
+        assert_eq!(result.exit_code, None);
+
+        // シグナルの処理を待つ（回収されていないゾンビプロセスは停止しているとみなす）
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let is_alive = || {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+                .is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while is_alive() && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!is_alive(), "grandchild process should have been killed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_from_previous_run_does_not_interrupt_later_hooks() {
+        // 前の操作で受け取ったシグナルが残っている状態（他のフックの実行中は書き換えない）
+        {
+            let handlers = SIGNAL_HANDLERS.lock().unwrap();
+            if handlers.0 == 0 {
+                RECEIVED_SIGNAL.store(libc::SIGINT, Ordering::SeqCst);
+            }
+        }
+
+        let mut executor = HookExecutor::new();
+        executor.set_quiet(true);
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+        let result = executor
+            .execute(HookType::PreCreate, &hook("echo ok", 5, false), &context)
+            .unwrap();
+        assert!(result.success);
+        assert!(executor
+            .execute(HookType::PreCreate, &hook("echo ok", 5, false), &context)
+            .is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_background_process_does_not_block_or_time_out_hook() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+        let pid_file = temp.path().join("daemon.pid");
+        // バックグラウンドのプロセスが標準出力を開いたままにする
+        let command = format!("echo started; sleep 30 & echo $! > {}", pid_file.display());
+
+        for timeout in [0, 5] {
+            let mut executor = HookExecutor::new();
+            executor.set_quiet(true);
+            executor.set_timeout(timeout);
+            let start = Instant::now();
+            let result = executor
+                .execute(HookType::PostCreate, &hook(&command, 0, false), &context)
+                .unwrap();
+
+            assert!(start.elapsed() < Duration::from_secs(10));
+            assert!(result.success, "{result:?}");
+            assert!(!result.timed_out);
+            assert_eq!(result.stdout, "started\n");
+
+            let pid = std::fs::read_to_string(&pid_file).unwrap();
+            // SAFETY: テストで起動したプロセスにシグナルを送るだけ
+            unsafe { libc::kill(pid.trim().parse().unwrap(), libc::SIGKILL) };
+        }
+    }
+
+    #[test]
+    fn test_hook_within_timeout_succeeds() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+        let executor = HookExecutor::new();
+
+        let result = executor
+            .execute(HookType::PreRemove, &hook("echo done", 5, false), &context)
+            .unwrap();
+
+        assert!(result.success);
+        assert!(!result.timed_out);
+        assert_eq!(result.stdout.trim(), "done");
+    }
+
+    #[test]
+    fn test_streamed_output_is_still_captured() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+
+        for quiet in [false, true] {
+            let mut executor = HookExecutor::new();
+            executor.set_quiet(quiet);
+
+            let result = executor
+                .execute(
+                    HookType::PostCreate,
+                    &hook("echo line1; echo err >&2; printf line2", 5, false),
+                    &context,
+                )
+                .unwrap();
+
+            assert_eq!(result.stdout, "line1\nline2");
+            assert_eq!(result.stderr, "err\n");
+        }
+    }
+
+    #[test]
+    fn test_hook_type_from_str() {
+        assert_eq!("pre_create".parse::<HookType>(), Ok(HookType::PreCreate));
+        assert_eq!("post-remove".parse::<HookType>(), Ok(HookType::PostRemove));
+        assert!("after_create".parse::<HookType>().is_err());
+    }
+
+    #[test]
+    fn test_failed_hook_is_logged() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("test", temp.path(), "test", temp.path());
+        let log = HookLog::for_worktree(temp.path(), Path::new("test"));
+        let mut executor = HookExecutor::new();
+        executor.set_quiet(true);
+        executor.set_log(log.clone());
+
+        let result = executor.execute(
+            HookType::PostCreate,
+            &hook("echo broken >&2; exit 3", 5, false),
+            &context,
+        );
+        assert!(result.is_err());
+
+        // エラーで中断した場合も実行結果は記録される
+        let entries = log.read().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].hook_type, "post_create");
+        assert_eq!(entries[0].exit_code, Some(3));
+        assert_eq!(entries[0].stderr, "broken\n");
+        assert!(!entries[0].success);
+    }
+
+    #[test]
+    fn test_both_placeholder_syntaxes() {
+        let mut context = HookContext::new("agent-1", "/work/agent-1", "feature/x", "/repo/app");
+        context.set_commit("abc123");
+        context.set_base_branch("main");
+
+        let expanded = expand_placeholders(
+            "echo {name} {path} {{branch}} ${BRANCH} {worktree_name} {repo_name} {commit} {base_branch} {hook_type} ${HOOK_TYPE}",
+            &context,
+            HookType::PostCreate,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            expanded,
+            "echo agent-1 /work/agent-1 feature/x feature/x agent-1 app abc123 main post_create post_create"
+        );
+    }
+
+    #[test]
+    fn test_shell_syntax_is_left_untouched() {
+        let context = HookContext::new("a", "/w", "b", "/r");
+        let command = r"find . -name x -exec rm {} \; && echo {a,b} ${HOME} $PATH '{ print $1 }'";
+
+        let expanded = expand_placeholders(command, &context, HookType::PreCreate, true).unwrap();
+        assert_eq!(expanded, command);
+    }
+
+    #[test]
+    fn test_unknown_placeholder_is_error() {
+        let context = HookContext::new("a", "/w", "b", "/r");
+
+        let err = expand_placeholders(
+            "echo {branch} {nmae} {env.TWIN_UNDEFINED_HOOK_VAR}",
+            &context,
+            HookType::PreCreate,
+            true,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("{nmae}"));
+        assert!(err.contains("{env.TWIN_UNDEFINED_HOOK_VAR}"));
+        assert!(err.contains("{worktree_name}"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unknown_placeholders_in_single_quotes_are_kept() {
+        let context = HookContext::new("a", "/w", "b", "/r");
+
+        let expanded = expand_placeholders(
+            "git log | awk '{print} END {exit}' | jq '{id}' > {branch}.txt",
+            &context,
+            HookType::PostCreate,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            expanded,
+            "git log | awk '{print} END {exit}' | jq '{id}' > b.txt"
+        );
+
+        // 既知のプレースホルダーはシングルクォート内でも展開し、クォートの外の未知のものはエラーにする
+        let expanded =
+            expand_placeholders("echo '{branch}'", &context, HookType::PostCreate, true).unwrap();
+        assert_eq!(expanded, "echo 'b'");
+        assert!(expand_placeholders("echo {print}", &context, HookType::PostCreate, true).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_substituted_values_are_shell_escaped() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let branch = "it's \"odd\" $(touch pwned) `touch pwned` \\ end";
+        let context = HookContext::new("test", temp.path(), branch, temp.path());
+        let mut executor = HookExecutor::new();
+        executor.set_quiet(true);
+
+        // クォートの外・シングルクォート内・ダブルクォート内のいずれでも値がそのまま渡る
+        for command in [
+            "printf %s {branch}",
+            "printf %s '{branch}'",
+            "printf %s \"{branch}\"",
+            "printf %s ${BRANCH}",
+        ] {
+            let result = executor
+                .execute(HookType::PostCreate, &hook(command, 5, false), &context)
+                .unwrap();
+            assert_eq!(result.stdout, branch, "command: {command}");
+        }
+
+        // 引数はそれぞれ1つのシェル引数として渡る
+        let mut args_hook = hook("printf [%s]", 5, false);
+        args_hook.args = vec!["{branch}".to_string(), "a b".to_string()];
+        let result = executor
+            .execute(HookType::PostCreate, &args_hook, &context)
+            .unwrap();
+        assert_eq!(result.stdout, format!("[{branch}][a b]"));
+
+        assert!(!temp.path().join("pwned").exists());
+    }
 }
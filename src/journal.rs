@@ -0,0 +1,333 @@
+//! 操作ジャーナルモジュール
+//!
+//! このモジュールの役割：
+//! - add/removeの進行状況（`PartialFailureState`）を共通gitディレクトリに保存
+//! - 中断された操作（プロセスが終了したまま残ったジャーナル）の検出
+//! - `twin recover`で再開・ロールバックするための読み込み
+
+#![allow(dead_code)]
+use crate::core::{OperationStep, OperationType, PartialFailureState, TwinError, TwinResult};
+use crate::utils::is_process_alive;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// 同一プロセス内でIDが重複しないための連番
+static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// ジャーナルに保存される操作の記録
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// 操作ID
+    pub id: String,
+    /// 操作を実行しているプロセスのID
+    pub pid: u32,
+    /// 開始時刻
+    pub started_at: DateTime<Utc>,
+    /// 最終更新時刻
+    pub updated_at: DateTime<Utc>,
+    /// 対象のワークツリーのパス（絶対パス）
+    pub worktree: PathBuf,
+    /// ブランチ名
+    pub branch: String,
+    /// プロジェクトルート（絶対パス）
+    pub project_root: PathBuf,
+    /// 明示的に指定された設定ファイル
+    #[serde(default)]
+    pub config_path: Option<PathBuf>,
+    /// git worktree addの引数（addのみ）
+    #[serde(default)]
+    pub git_args: Vec<String>,
+    /// git worktree addで作成されるブランチ（addのみ）
+    #[serde(default)]
+    pub created_branch: Option<String>,
+    /// 強制実行するか（removeのみ）
+    #[serde(default)]
+    pub force: bool,
+    /// コミット（フックのプレースホルダー用）
+    #[serde(default)]
+    pub commit: Option<String>,
+    /// 作成元のブランチ（フックのプレースホルダー用）
+    #[serde(default)]
+    pub base_branch: Option<String>,
+    /// 各ステップの進行状況
+    pub state: PartialFailureState,
+}
+
+impl JournalEntry {
+    /// 新しい記録を作成
+    pub fn new(
+        operation: OperationType,
+        worktree: impl Into<PathBuf>,
+        branch: impl Into<String>,
+        project_root: impl Into<PathBuf>,
+    ) -> Self {
+        let now = Utc::now();
+        let pid = std::process::id();
+        let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id: format!("{}-{pid}-{sequence}", now.format("%Y%m%d-%H%M%S")),
+            pid,
+            started_at: now,
+            updated_at: now,
+            worktree: worktree.into(),
+            branch: branch.into(),
+            project_root: project_root.into(),
+            config_path: None,
+            git_args: Vec::new(),
+            created_branch: None,
+            force: false,
+            commit: None,
+            base_branch: None,
+            state: PartialFailureState::new(operation),
+        }
+    }
+
+    /// 操作の種類の表示名
+    pub fn operation_name(&self) -> &'static str {
+        match self.state.operation {
+            OperationType::CreateEnvironment => "add",
+            OperationType::RemoveEnvironment => "remove",
+            OperationType::SwitchEnvironment => "switch",
+            OperationType::CreateSymlinks => "link",
+            OperationType::RemoveSymlinks => "unlink",
+        }
+    }
+
+    /// 別のプロセスで実行中か（実行中のものは中断とみなさない）
+    pub fn is_running(&self) -> bool {
+        self.pid != std::process::id() && is_process_alive(self.pid)
+    }
+
+    /// 指定した名前のステップが何回成功したか
+    pub fn completed_count(&self, name: &str) -> usize {
+        self.state
+            .succeeded_steps
+            .iter()
+            .filter(|step| step.name == name)
+            .count()
+    }
+
+    /// 指定した名前・詳細情報のステップが成功済みか
+    pub fn is_completed(&self, name: &str, key: &str, value: &str) -> bool {
+        self.state
+            .succeeded_steps
+            .iter()
+            .any(|step| step.name == name && step.detail(key) == Some(value))
+    }
+}
+
+/// 共通gitディレクトリ配下のジャーナル（`<twin_dir>/journal/<id>.json`）
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    /// twinディレクトリからジャーナルを作成
+    pub fn new(twin_dir: &Path) -> Self {
+        Self {
+            dir: twin_dir.join("journal"),
+        }
+    }
+
+    /// ジャーナルのディレクトリを取得
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 操作を開始し、最初の状態を保存する
+    pub fn begin(&self, entry: JournalEntry) -> TwinResult<JournaledOperation> {
+        let operation = JournaledOperation {
+            path: self.dir.join(format!("{}.json", entry.id)),
+            entry,
+        };
+        operation.save()?;
+        Ok(operation)
+    }
+
+    /// 保存済みの操作を再開する
+    pub fn resume(&self, mut entry: JournalEntry) -> TwinResult<JournaledOperation> {
+        entry.pid = std::process::id();
+        entry.state.failed_step = None;
+        entry.state.error = None;
+        self.begin(entry)
+    }
+
+    /// 記録を削除する
+    pub fn discard(&self, id: &str) -> TwinResult<()> {
+        let path = self.dir.join(format!("{id}.json"));
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// すべての記録を開始時刻順に取得
+    pub fn list(&self) -> TwinResult<Vec<JournalEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for file in fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match read_entry(&path) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping unreadable journal {}: {e}", path.display()),
+            }
+        }
+        entries.sort_by_key(|entry| entry.started_at);
+        Ok(entries)
+    }
+
+    /// IDで記録を取得（前方一致で一意に決まる場合も可）
+    pub fn find(&self, id: &str) -> TwinResult<JournalEntry> {
+        let entries = self.list()?;
+        if let Some(entry) = entries.iter().find(|entry| entry.id == id) {
+            return Ok(entry.clone());
+        }
+
+        let mut matches = entries.into_iter().filter(|entry| entry.id.starts_with(id));
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Ok(entry),
+            (Some(_), Some(_)) => Err(TwinError::config(
+                format!("Operation id '{id}' is ambiguous"),
+                None,
+            )),
+            (None, _) => Err(TwinError::not_found("Operation", id)),
+        }
+    }
+}
+
+/// ステップごとにジャーナルへ保存される実行中の操作
+#[derive(Debug)]
+pub struct JournaledOperation {
+    path: PathBuf,
+    pub entry: JournalEntry,
+}
+
+impl JournaledOperation {
+    /// 現在の状態を取得
+    pub fn state(&self) -> &PartialFailureState {
+        &self.entry.state
+    }
+
+    /// 成功したステップを記録して保存
+    pub fn record(&mut self, step: OperationStep) {
+        self.entry.state.record(step);
+        self.save_or_warn();
+    }
+
+    /// 失敗したステップを記録して保存
+    pub fn fail(&mut self, step: OperationStep, error: impl Into<String>) {
+        self.entry.state.fail(step, error);
+        self.save_or_warn();
+    }
+
+    /// 状態をファイルに保存（書き込み途中の中断で壊れないよう一時ファイルから置き換える）
+    pub fn save(&self) -> TwinResult<()> {
+        fs::create_dir_all(self.path.parent().unwrap_or(Path::new(".")))?;
+        let content = serde_json::to_string_pretty(&self.entry).map_err(|e| {
+            TwinError::io(
+                format!("Failed to serialize journal: {e}"),
+                Some(self.path.clone()),
+            )
+        })?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// 操作が完了した（または完全にロールバックされた）ので記録を削除
+    pub fn complete(self) -> TwinResult<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// ジャーナルのパスを取得
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save_or_warn(&mut self) {
+        self.entry.updated_at = Utc::now();
+        if let Err(e) = self.save() {
+            warn!("Failed to write journal {}: {e}", self.path.display());
+        }
+    }
+}
+
+fn read_entry(path: &Path) -> TwinResult<JournalEntry> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| {
+        TwinError::io(
+            format!("Invalid journal {}: {e}", path.display()),
+            Some(path.to_path_buf()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry() -> JournalEntry {
+        JournalEntry::new(
+            OperationType::CreateEnvironment,
+            "/work/agent-1",
+            "agent-1",
+            "/repo",
+        )
+    }
+
+    #[test]
+    fn test_steps_are_persisted_until_complete() {
+        let temp = TempDir::new().unwrap();
+        let journal = Journal::new(temp.path());
+
+        let mut operation = journal.begin(entry()).unwrap();
+        operation.record(OperationStep::new("add_worktree", true).with_detail("path", "/w"));
+        assert!(operation.path().starts_with(temp.path().join("journal")));
+
+        let saved = journal.list().unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].id, operation.entry.id);
+        assert_eq!(saved[0].completed_count("add_worktree"), 1);
+        assert!(saved[0].is_completed("add_worktree", "path", "/w"));
+        // 自分自身のプロセスの記録は実行中とみなさない
+        assert!(!saved[0].is_running());
+
+        operation.complete().unwrap();
+        assert!(journal.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_by_prefix() {
+        let temp = TempDir::new().unwrap();
+        let journal = Journal::new(temp.path());
+        let operation = journal.begin(entry()).unwrap();
+
+        let id = operation.entry.id.clone();
+        assert_eq!(journal.find(&id).unwrap().id, id);
+        assert_eq!(journal.find(&id[..id.len() - 2]).unwrap().id, id);
+        assert!(journal.find("no-such-id").is_err());
+    }
+
+    #[test]
+    fn test_dead_process_is_not_running() {
+        let mut entry = entry();
+        entry.pid = u32::MAX;
+        assert!(!entry.is_running());
+    }
+}
@@ -1,11 +1,26 @@
 //! Twin - Git Worktree Manager
 
+pub mod branch_name;
+pub mod check;
 pub mod cli;
 pub mod config;
 pub mod core;
+pub mod filter;
 pub mod git;
+pub mod git_backend;
+pub mod hook_log;
 pub mod hooks;
+pub mod journal;
+pub mod list;
+pub mod manifest;
+pub mod mapping;
+pub mod plan;
+pub mod prune;
+pub mod rollback;
+pub mod status;
 pub mod symlink;
+pub mod sync;
+pub mod template;
 pub mod tui;
 pub mod utils;
 
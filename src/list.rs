@@ -0,0 +1,366 @@
+//! ワークツリー一覧モジュール
+//!
+//! このモジュールの役割：
+//! - `twin list --columns`で表示する列と`--sort`の並び順の定義
+//! - 指定された列に必要な情報（変更数・ベースブランチとの差分・最終コミット・ディスク使用量）の収集
+//! - 一覧の並び替えと、経過時間・サイズの表示形式
+
+#![allow(dead_code)]
+use crate::git::{GitManager, WorktreeInfo};
+use crate::status::Divergence;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// `twin list`の列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListColumn {
+    /// ブランチ名
+    Branch,
+    /// エージェント名（ブランチ名テンプレートの`{name}`の部分）
+    Agent,
+    /// 状態（active・locked・prunable）
+    Status,
+    /// HEADのコミットハッシュ
+    Commit,
+    /// 上流ブランチとの差分
+    Upstream,
+    /// Worktreeのパス
+    Path,
+    /// 未コミットの変更・未追跡ファイルの数
+    Dirty,
+    /// ベースブランチとの差分
+    Base,
+    /// 最終コミットの件名
+    Subject,
+    /// 最終コミットの日時
+    Updated,
+    /// Worktreeの作成日時
+    Created,
+    /// ロックの理由
+    Lock,
+    /// ディスク使用量
+    Size,
+}
+
+/// `--columns`を省略した場合の列
+pub const DEFAULT_COLUMNS: &[ListColumn] = &[
+    ListColumn::Branch,
+    ListColumn::Status,
+    ListColumn::Commit,
+    ListColumn::Upstream,
+    ListColumn::Path,
+];
+
+impl ListColumn {
+    /// テーブルの見出し
+    pub fn header(&self) -> &'static str {
+        match self {
+            ListColumn::Branch => "Branch",
+            ListColumn::Agent => "Agent",
+            ListColumn::Status => "Status",
+            ListColumn::Commit => "Commit",
+            ListColumn::Upstream => "Upstream",
+            ListColumn::Path => "Path",
+            ListColumn::Dirty => "Dirty",
+            ListColumn::Base => "Base",
+            ListColumn::Subject => "Subject",
+            ListColumn::Updated => "Updated",
+            ListColumn::Created => "Created",
+            ListColumn::Lock => "Lock",
+            ListColumn::Size => "Size",
+        }
+    }
+
+    /// テーブルの列幅
+    pub fn width(&self) -> usize {
+        match self {
+            ListColumn::Branch | ListColumn::Path => 30,
+            ListColumn::Agent => 16,
+            ListColumn::Status => 10,
+            ListColumn::Commit | ListColumn::Base => 12,
+            ListColumn::Upstream | ListColumn::Lock => 20,
+            ListColumn::Dirty => 6,
+            ListColumn::Subject => 40,
+            ListColumn::Updated | ListColumn::Created | ListColumn::Size => 10,
+        }
+    }
+}
+
+/// `twin list`の並び順（昇順、`--reverse`で降順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListSort {
+    /// ブランチ名
+    Branch,
+    /// Worktreeのパス
+    Path,
+    /// 最終コミットの日時（古い順）
+    Updated,
+    /// Worktreeの作成日時（古い順）
+    Created,
+    /// 未コミットの変更・未追跡ファイルの数
+    Dirty,
+    /// ベースブランチより進んでいるコミット数
+    Ahead,
+    /// ベースブランチより遅れているコミット数
+    Behind,
+    /// ディスク使用量
+    Size,
+}
+
+impl ListSort {
+    /// 並び替えに必要な情報を収集する列
+    pub fn column(&self) -> ListColumn {
+        match self {
+            ListSort::Branch => ListColumn::Branch,
+            ListSort::Path => ListColumn::Path,
+            ListSort::Updated => ListColumn::Updated,
+            ListSort::Created => ListColumn::Created,
+            ListSort::Dirty => ListColumn::Dirty,
+            ListSort::Ahead | ListSort::Behind => ListColumn::Base,
+            ListSort::Size => ListColumn::Size,
+        }
+    }
+}
+
+/// 一覧の1行（Worktreeの情報と、指定された列のために収集した情報）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListEntry {
+    #[serde(flatten)]
+    pub worktree: WorktreeInfo,
+    /// 未コミットの変更・未追跡ファイルの数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dirty: Option<usize>,
+    /// ベースブランチとの差分
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<Divergence>,
+    /// 最終コミットの件名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_commit_subject: Option<String>,
+    /// Worktreeのディスク使用量（バイト）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_size: Option<u64>,
+}
+
+/// 指定された列に必要な情報を収集する
+///
+/// ディレクトリが存在しないWorktreeや、取得に失敗した情報はNoneのままにする。
+pub fn collect_entries(
+    git: &mut GitManager,
+    worktrees: Vec<WorktreeInfo>,
+    columns: &[ListColumn],
+    base: Option<&str>,
+) -> Vec<ListEntry> {
+    let wants = |column: ListColumn| columns.contains(&column);
+
+    worktrees
+        .into_iter()
+        .map(|worktree| {
+            let mut entry = ListEntry::default();
+            let exists = !worktree.prunable && worktree.path.is_dir();
+            if exists && wants(ListColumn::Dirty) {
+                entry.dirty = git
+                    .get_worktree_status(&worktree.path)
+                    .ok()
+                    .map(|status| status.changed + status.untracked);
+            }
+            if let Some(base) = base.filter(|_| exists && wants(ListColumn::Base)) {
+                entry.base = git
+                    .ahead_behind(&worktree.path, base)
+                    .ok()
+                    .map(|(ahead, behind)| Divergence {
+                        branch: base.to_string(),
+                        ahead,
+                        behind,
+                    });
+            }
+            // detached HEADのWorktreeはブランチの情報がないため、最終コミットの日時もコミットから取得する
+            let needs_date = wants(ListColumn::Updated) && worktree.last_updated.is_none();
+            let mut worktree = worktree;
+            if (wants(ListColumn::Subject) || needs_date) && !worktree.commit.is_empty() {
+                if let Ok((date, subject)) = git.commit_summary(&worktree.commit) {
+                    worktree.last_updated.get_or_insert(date);
+                    entry.last_commit_subject = Some(subject);
+                }
+            }
+            if exists && wants(ListColumn::Size) {
+                entry.disk_size = Some(dir_size(&worktree.path));
+            }
+            entry.worktree = worktree;
+            entry
+        })
+        .collect()
+}
+
+/// 一覧を並び替える（`main`のパスのWorktreeは常に先頭、値がないものは昇順で末尾）
+///
+/// 絞り込み後の一覧ではメインのWorktreeが含まれないことがあるため、先頭の要素ではなくパスで判定する。
+pub fn sort_entries(entries: &mut [ListEntry], main: Option<&Path>, sort: ListSort, reverse: bool) {
+    let is_main = |entry: &ListEntry| Some(entry.worktree.path.as_path()) == main;
+
+    entries.sort_by(|a, b| {
+        let pinned = is_main(b).cmp(&is_main(a));
+        if pinned != Ordering::Equal {
+            return pinned;
+        }
+        let ordering = match sort {
+            ListSort::Branch => a.worktree.branch.cmp(&b.worktree.branch),
+            ListSort::Path => a.worktree.path.cmp(&b.worktree.path),
+            ListSort::Updated => cmp_optional(a.worktree.last_updated, b.worktree.last_updated),
+            ListSort::Created => cmp_optional(a.worktree.created_at, b.worktree.created_at),
+            ListSort::Dirty => cmp_optional(a.dirty, b.dirty),
+            ListSort::Ahead => cmp_optional(
+                a.base.as_ref().map(|base| base.ahead),
+                b.base.as_ref().map(|base| base.ahead),
+            ),
+            ListSort::Behind => cmp_optional(
+                a.base.as_ref().map(|base| base.behind),
+                b.base.as_ref().map(|base| base.behind),
+            ),
+            ListSort::Size => cmp_optional(a.disk_size, b.disk_size),
+        };
+        if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// 値がないものを後ろにして比較
+fn cmp_optional<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// ディレクトリ配下のファイルサイズの合計（シンボリックリンクはたどらない）
+fn dir_size(path: &Path) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(metadata) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    total
+}
+
+/// 経過時間の表示（例: 3h ago、2d ago）
+pub fn format_age(time: DateTime<Local>, now: DateTime<Local>) -> String {
+    let elapsed = now.signed_duration_since(time);
+    let minutes = elapsed.num_minutes();
+    let hours = elapsed.num_hours();
+    let days = elapsed.num_days();
+    if minutes < 1 {
+        "just now".to_string()
+    } else if hours < 1 {
+        format!("{minutes}m ago")
+    } else if days < 1 {
+        format!("{hours}h ago")
+    } else if days < 60 {
+        format!("{days}d ago")
+    } else if days < 730 {
+        format!("{}mo ago", days / 30)
+    } else {
+        format!("{}y ago", days / 365)
+    }
+}
+
+/// ディスク使用量の表示（例: 512 B、1.5 MiB）
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn entry(branch: &str, dirty: Option<usize>) -> ListEntry {
+        ListEntry {
+            worktree: WorktreeInfo {
+                branch: branch.to_string(),
+                path: Path::new("/repo").join(branch),
+                ..Default::default()
+            },
+            dirty,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_entries_keeps_main_first() {
+        let mut entries = vec![
+            entry("main", Some(9)),
+            entry("b", Some(3)),
+            entry("c", None),
+            entry("a", Some(1)),
+        ];
+        let branches = |entries: &[ListEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.worktree.branch.clone()).collect()
+        };
+
+        let main = Path::new("/repo/main");
+
+        sort_entries(&mut entries, Some(main), ListSort::Dirty, false);
+        assert_eq!(branches(&entries), vec!["main", "a", "b", "c"]);
+
+        sort_entries(&mut entries, Some(main), ListSort::Branch, true);
+        assert_eq!(branches(&entries), vec!["main", "c", "b", "a"]);
+
+        // 絞り込みでメインのWorktreeが除かれた場合は先頭の要素も並び替える
+        let mut entries = vec![entry("c", Some(2)), entry("b", Some(3)), entry("a", None)];
+        sort_entries(&mut entries, Some(main), ListSort::Branch, false);
+        assert_eq!(branches(&entries), vec!["a", "b", "c"]);
+        sort_entries(&mut entries, Some(main), ListSort::Dirty, false);
+        assert_eq!(branches(&entries), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_format_age_and_size() {
+        let now = Local::now();
+        assert_eq!(format_age(now - Duration::seconds(10), now), "just now");
+        assert_eq!(format_age(now - Duration::minutes(5), now), "5m ago");
+        assert_eq!(format_age(now - Duration::hours(3), now), "3h ago");
+        assert_eq!(format_age(now - Duration::days(12), now), "12d ago");
+        assert_eq!(format_age(now - Duration::days(90), now), "3mo ago");
+
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn test_dir_size_counts_nested_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("a/b")).unwrap();
+        std::fs::write(temp.path().join("a/one"), [0u8; 10]).unwrap();
+        std::fs::write(temp.path().join("a/b/two"), [0u8; 20]).unwrap();
+        assert_eq!(dir_size(temp.path()), 30);
+    }
+}
@@ -1,9 +1,24 @@
+mod branch_name;
+mod check;
 mod cli;
 mod config;
 mod core;
+mod filter;
 mod git;
+mod git_backend;
+mod hook_log;
 mod hooks;
+mod journal;
+mod list;
+mod manifest;
+mod mapping;
+mod plan;
+mod prune;
+mod rollback;
+mod status;
 mod symlink;
+mod sync;
+mod template;
 mod tui;
 mod utils;
 
@@ -39,7 +54,12 @@ async fn main() -> Result<()> {
     }
 
     // Parse CLI arguments
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    // --dry-run はすべてのサブコマンドで指定できるため、対応するコマンドの引数に渡す
+    if let Some(format) = cli.dry_run {
+        cli.command.set_dry_run(format)?;
+    }
 
     // Handle commands
     match cli.command {
@@ -55,11 +75,32 @@ async fn main() -> Result<()> {
         Commands::Remove(args) => {
             handle_remove(args).await?;
         }
+        Commands::Status(args) => {
+            handle_status(args).await?;
+        }
+        Commands::Check(args) => {
+            handle_check(args).await?;
+        }
+        Commands::Repair(args) => {
+            handle_repair(args).await?;
+        }
+        Commands::Sync(args) => {
+            handle_sync(args).await?;
+        }
+        Commands::Prune(args) => {
+            handle_prune(args).await?;
+        }
+        Commands::Logs(args) => {
+            handle_logs(args).await?;
+        }
+        Commands::Recover(args) => {
+            handle_recover(args).await?;
+        }
         Commands::Config(args) => {
             handle_config(args).await?;
         }
         Commands::Tui => {
-            todo!("Implement TUI")
+            tui::run_tui().await?;
         }
         Commands::Init(args) => {
             handle_init(args).await?;
@@ -0,0 +1,518 @@
+//! 適用済み副作用のマニフェストモジュール
+//!
+//! このモジュールの役割：
+//! - ワークツリーに実際に適用したマッピングと実行したフックの記録（`<twin_dir>/manifests/<worktree>-<hash>.json`）
+//! - コピー・テンプレートで生成したファイルのチェックサムの計算
+//! - 作成時の設定のハッシュによる設定変更の検出
+//! - remove/status/syncで現在の設定ではなく適用済みの状態を参照するための読み込み
+
+#![allow(dead_code)]
+use crate::core::{ConfigSettings, FileMapping, MappingType, TwinError, TwinResult};
+use crate::journal::JournalEntry;
+use crate::rollback;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 適用済みのマッピング1件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedMapping {
+    /// マッピングタイプ
+    pub mapping_type: MappingType,
+    /// ソースのパス（絶対パス）
+    pub source: PathBuf,
+    /// ワークツリー内のパス（ワークツリーからの相対パス）
+    pub target: PathBuf,
+    /// 生成したファイルのチェックサム（コピー・テンプレートのみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+impl AppliedMapping {
+    /// 適用したマッピングを記録する（コピー・テンプレートは現在のターゲットのチェックサムを計算する）
+    pub fn new(mapping_type: MappingType, source: &Path, target: &Path, worktree: &Path) -> Self {
+        let absolute = worktree.join(target);
+        let checksum = match mapping_type {
+            MappingType::Symlink => None,
+            MappingType::Copy | MappingType::Template => checksum(&absolute).ok(),
+        };
+        Self {
+            mapping_type,
+            source: source.to_path_buf(),
+            target: target.to_path_buf(),
+            checksum,
+        }
+    }
+
+    /// 状態の確認・再適用に使用するマッピング定義に変換
+    pub fn to_file_mapping(&self) -> FileMapping {
+        FileMapping {
+            source: self.source.clone(),
+            target: Some(self.target.clone()),
+            mapping_type: self.mapping_type.clone(),
+            description: None,
+            skip_if_exists: false,
+            variables: HashMap::new(),
+        }
+    }
+
+    /// 生成したファイルが作成時から変更されているか
+    ///
+    /// チェックサムが記録されていない場合はNoneを返す。
+    pub fn is_modified(&self, worktree: &Path) -> Option<bool> {
+        let recorded = self.checksum.as_ref()?;
+        let target = worktree.join(&self.target);
+        if !target.exists() || target.is_symlink() {
+            return Some(false);
+        }
+        Some(checksum(&target).ok().as_ref() != Some(recorded))
+    }
+}
+
+/// 実行したフック1件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedHook {
+    /// フックタイプ（pre_create など）
+    pub hook_type: String,
+    /// コマンド
+    pub command: String,
+    /// 実行時刻
+    pub ran_at: DateTime<Utc>,
+}
+
+/// ワークツリー1件のマニフェスト
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// ワークツリーのパス（絶対パス）
+    pub worktree: PathBuf,
+    /// ブランチ名
+    pub branch: String,
+    /// 適用時の設定のハッシュ
+    pub config_hash: String,
+    /// 作成時刻
+    pub created_at: DateTime<Utc>,
+    /// 最終更新時刻（syncなど）
+    pub updated_at: DateTime<Utc>,
+    /// 適用済みのマッピング
+    #[serde(default)]
+    pub mappings: Vec<AppliedMapping>,
+    /// 実行したフック
+    #[serde(default)]
+    pub hooks: Vec<AppliedHook>,
+}
+
+impl Manifest {
+    /// 空のマニフェストを作成
+    pub fn new(
+        worktree: impl Into<PathBuf>,
+        branch: impl Into<String>,
+        settings: &ConfigSettings,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            worktree: worktree.into(),
+            branch: branch.into(),
+            config_hash: config_hash(settings),
+            created_at: now,
+            updated_at: now,
+            mappings: Vec::new(),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// 完了したaddのジャーナルから作成する
+    ///
+    /// 成功したマッピングの適用とフックの実行を記録する（skip_if_existsでスキップしたものは含まない）。
+    pub fn from_journal(entry: &JournalEntry, settings: &ConfigSettings) -> Self {
+        let mut manifest = Self::new(&entry.worktree, &entry.branch, settings);
+        manifest.created_at = entry.started_at;
+
+        for step in &entry.state.succeeded_steps {
+            match step.name.as_str() {
+                rollback::STEP_APPLY_MAPPING => {
+                    let (Some(mapping_type), Some(source), Some(target)) = (
+                        step.detail(rollback::DETAIL_MAPPING_TYPE)
+                            .and_then(parse_mapping_type),
+                        step.detail(rollback::DETAIL_SOURCE),
+                        step.detail(rollback::DETAIL_TARGET),
+                    ) else {
+                        continue;
+                    };
+                    let target = Path::new(target);
+                    let relative = target.strip_prefix(&entry.worktree).unwrap_or(target);
+                    manifest.record_mapping(AppliedMapping::new(
+                        mapping_type,
+                        Path::new(source),
+                        relative,
+                        &entry.worktree,
+                    ));
+                }
+                rollback::STEP_PRE_CREATE | rollback::STEP_POST_CREATE => {
+                    manifest.hooks.push(AppliedHook {
+                        hook_type: step.name.clone(),
+                        command: step
+                            .detail(rollback::DETAIL_COMMAND)
+                            .unwrap_or_default()
+                            .to_string(),
+                        ran_at: step.timestamp,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        manifest
+    }
+
+    /// マニフェストのパス（`<twin_dir>/manifests/<ワークツリー名>-<パスのハッシュ>.json`）
+    pub fn path_for(twin_dir: &Path, worktree: &Path) -> PathBuf {
+        twin_dir
+            .join("manifests")
+            .join(format!("{}.json", worktree_key(worktree)))
+    }
+
+    /// ワークツリー名だけをファイル名にしていた以前の形式のマニフェストのパス
+    fn legacy_path_for(twin_dir: &Path, worktree: &Path) -> PathBuf {
+        let name = worktree
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "worktree".to_string());
+        twin_dir.join("manifests").join(format!("{name}.json"))
+    }
+
+    /// ワークツリーのマニフェストのファイルを探す（以前の形式のファイルも参照する）
+    ///
+    /// 同名の別のワークツリーのマニフェストは使用しない。
+    fn locate(twin_dir: &Path, worktree: &Path) -> TwinResult<Option<(PathBuf, Self)>> {
+        for path in [
+            Self::path_for(twin_dir, worktree),
+            Self::legacy_path_for(twin_dir, worktree),
+        ] {
+            if !path.exists() {
+                continue;
+            }
+            let manifest = Self::read(&path)?;
+            if same_worktree(&manifest.worktree, worktree) {
+                return Ok(Some((path, manifest)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// ワークツリーのマニフェストを読み込む（作成されていない場合はNone）
+    ///
+    /// 同名の別のワークツリーのマニフェストは使用しない。
+    pub fn load(twin_dir: &Path, worktree: &Path) -> TwinResult<Option<Self>> {
+        Ok(Self::locate(twin_dir, worktree)?.map(|(_, manifest)| manifest))
+    }
+
+    /// マニフェストのファイルを読み込む
+    pub fn read(path: &Path) -> TwinResult<Self> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            TwinError::io(
+                format!("Invalid manifest {}: {e}", path.display()),
+                Some(path.to_path_buf()),
+            )
+        })
+    }
+
+    /// マニフェストを保存する（書き込み途中の中断で壊れないよう一時ファイルから置き換える）
+    ///
+    /// 以前の形式のファイルに保存されていた場合は、新しい形式で保存してから削除する。
+    pub fn save(&self, twin_dir: &Path) -> TwinResult<()> {
+        let path = Self::path_for(twin_dir, &self.worktree);
+        let previous = Self::locate(twin_dir, &self.worktree)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            TwinError::io(
+                format!("Failed to serialize manifest: {e}"),
+                Some(path.clone()),
+            )
+        })?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &path)?;
+
+        if let Some((previous, _)) = previous.filter(|(previous, _)| *previous != path) {
+            fs::remove_file(previous)?;
+        }
+        Ok(())
+    }
+
+    /// ワークツリーのマニフェストを削除する
+    pub fn delete(twin_dir: &Path, worktree: &Path) -> TwinResult<()> {
+        if let Some((path, _)) = Self::locate(twin_dir, worktree)? {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// ターゲットで適用済みのマッピングを探す
+    pub fn find(&self, target: &Path) -> Option<&AppliedMapping> {
+        self.mappings
+            .iter()
+            .find(|mapping| mapping.target == target)
+    }
+
+    /// マッピングを記録する（同じターゲットの記録は置き換える）
+    pub fn record_mapping(&mut self, mapping: AppliedMapping) {
+        self.forget_mapping(&mapping.target);
+        self.mappings.push(mapping);
+        self.updated_at = Utc::now();
+    }
+
+    /// マッピングの記録を削除する
+    pub fn forget_mapping(&mut self, target: &Path) {
+        self.mappings.retain(|mapping| mapping.target != target);
+        self.updated_at = Utc::now();
+    }
+
+    /// 適用時から設定が変更されているか
+    pub fn is_outdated(&self, settings: &ConfigSettings) -> bool {
+        self.config_hash != config_hash(settings)
+    }
+}
+
+/// 設定のハッシュを計算する（キーの順序に依存しないよう、JSONの値に変換してから計算する）
+pub fn config_hash(settings: &ConfigSettings) -> String {
+    let content = serde_json::to_value(settings)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let mut hasher = Fnv1a::new();
+    hasher.write(content.as_bytes());
+    hasher.finish()
+}
+
+/// ファイルまたはディレクトリのチェックサムを計算する（ディレクトリは配下の名前と内容を含める）
+pub fn checksum(path: &Path) -> TwinResult<String> {
+    let mut hasher = Fnv1a::new();
+    hash_path(path, &mut hasher)?;
+    Ok(hasher.finish())
+}
+
+fn hash_path(path: &Path, hasher: &mut Fnv1a) -> TwinResult<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        hasher.write(b"l");
+        hasher.write(fs::read_link(path)?.to_string_lossy().as_bytes());
+    } else if metadata.is_dir() {
+        let mut names = fs::read_dir(path)?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        names.sort();
+        hasher.write(b"d");
+        for name in names {
+            hasher.write(name.to_string_lossy().as_bytes());
+            hasher.write(&[0]);
+            hash_path(&path.join(name), hasher)?;
+        }
+    } else {
+        hasher.write(b"f");
+        hasher.write(&fs::read(path)?);
+    }
+    hasher.write(&[0]);
+    Ok(())
+}
+
+/// ワークツリーの記録のファイル名に使うキー（`<ワークツリー名>-<パスのハッシュ>`）
+///
+/// 別のディレクトリにある同名のワークツリーと区別するため、パスのハッシュを含める。
+/// シンボリックリンクを含むパスでも同じ値になるよう、`same_worktree`と同様に親ディレクトリを正規化してから計算する。
+pub fn worktree_key(worktree: &Path) -> String {
+    let name = worktree
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "worktree".to_string());
+    let parent = worktree
+        .parent()
+        .map(|parent| fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf()))
+        .unwrap_or_default();
+    let mut hasher = Fnv1a::new();
+    hasher.write(parent.join(&name).to_string_lossy().as_bytes());
+    format!("{name}-{}", hasher.finish())
+}
+
+/// `worktree_key`で作成したキーからワークツリー名を取り出す（キーでない場合はそのまま返す）
+pub fn worktree_key_name(key: &str) -> &str {
+    match key.rsplit_once('-') {
+        Some((name, hash)) if hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            name
+        }
+        _ => key,
+    }
+}
+
+/// 同じワークツリーのパスか（シンボリックリンクを含むパスでも、削除後でも判定できるよう親ディレクトリで比較する）
+pub fn same_worktree(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    if a.file_name() != b.file_name() {
+        return false;
+    }
+    match (
+        a.parent().map(fs::canonicalize),
+        b.parent().map(fs::canonicalize),
+    ) {
+        (Some(Ok(a)), Some(Ok(b))) => a == b,
+        _ => false,
+    }
+}
+
+/// 設定ファイルの表記からマッピングタイプを解釈する
+fn parse_mapping_type(value: &str) -> Option<MappingType> {
+    match value {
+        "symlink" => Some(MappingType::Symlink),
+        "copy" => Some(MappingType::Copy),
+        "template" => Some(MappingType::Template),
+        _ => None,
+    }
+}
+
+/// Rustのバージョンによらず同じ値になるハッシュ（FNV-1a 64bit）
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{OperationStep, OperationType};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_from_journal_records_applied_steps() {
+        let temp = TempDir::new().unwrap();
+        let worktree = temp.path().join("wt");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join(".tool"), "tool").unwrap();
+
+        let mut entry = JournalEntry::new(
+            OperationType::CreateEnvironment,
+            &worktree,
+            "feature",
+            temp.path(),
+        );
+        let mapping_step = |mapping_type: &str, target: &str| {
+            OperationStep::new(rollback::STEP_APPLY_MAPPING, true)
+                .with_detail(rollback::DETAIL_MAPPING_TYPE, mapping_type)
+                .with_detail(rollback::DETAIL_SOURCE, "/repo/src")
+                .with_detail(
+                    rollback::DETAIL_TARGET,
+                    worktree.join(target).to_string_lossy(),
+                )
+        };
+        entry.state.record(mapping_step("symlink", ".env"));
+        entry.state.record(mapping_step("copy", ".tool"));
+        entry.state.record(
+            OperationStep::new(rollback::STEP_POST_CREATE, false)
+                .with_detail(rollback::DETAIL_COMMAND, "npm install"),
+        );
+
+        let settings = ConfigSettings::default();
+        let manifest = Manifest::from_journal(&entry, &settings);
+        assert_eq!(manifest.mappings.len(), 2);
+        assert_eq!(manifest.mappings[0].target, PathBuf::from(".env"));
+        assert_eq!(manifest.mappings[0].checksum, None);
+        assert_eq!(manifest.mappings[1].mapping_type, MappingType::Copy);
+        assert_eq!(manifest.mappings[1].is_modified(&worktree), Some(false));
+        assert_eq!(manifest.hooks[0].command, "npm install");
+        assert!(!manifest.is_outdated(&settings));
+
+        fs::write(worktree.join(".tool"), "edited").unwrap();
+        assert_eq!(manifest.mappings[1].is_modified(&worktree), Some(true));
+
+        let twin_dir = temp.path().join("twin");
+        manifest.save(&twin_dir).unwrap();
+        assert_eq!(
+            Manifest::load(&twin_dir, &worktree).unwrap(),
+            Some(manifest)
+        );
+        // 同名の別のワークツリーのマニフェストは使用しない
+        assert_eq!(
+            Manifest::load(&twin_dir, &temp.path().join("other/wt")).unwrap(),
+            None
+        );
+        Manifest::delete(&twin_dir, &worktree).unwrap();
+        assert_eq!(Manifest::load(&twin_dir, &worktree).unwrap(), None);
+    }
+
+    #[test]
+    fn test_same_named_worktrees_have_separate_manifests() {
+        let temp = TempDir::new().unwrap();
+        let twin_dir = temp.path().join("twin");
+        let first = temp.path().join("a/wt");
+        let second = temp.path().join("b/wt");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        assert_ne!(
+            Manifest::path_for(&twin_dir, &first),
+            Manifest::path_for(&twin_dir, &second)
+        );
+        assert_eq!(worktree_key_name(&worktree_key(&first)), "wt");
+        assert_eq!(worktree_key_name("wt"), "wt");
+
+        let settings = ConfigSettings::default();
+        let first_manifest = Manifest::new(&first, "first", &settings);
+        let second_manifest = Manifest::new(&second, "second", &settings);
+        first_manifest.save(&twin_dir).unwrap();
+        second_manifest.save(&twin_dir).unwrap();
+        assert_eq!(
+            Manifest::load(&twin_dir, &first).unwrap(),
+            Some(first_manifest)
+        );
+        Manifest::delete(&twin_dir, &second).unwrap();
+        assert!(Manifest::load(&twin_dir, &first).unwrap().is_some());
+        assert_eq!(Manifest::load(&twin_dir, &second).unwrap(), None);
+    }
+
+    #[test]
+    fn test_legacy_manifest_is_loaded_and_migrated() {
+        let temp = TempDir::new().unwrap();
+        let twin_dir = temp.path().join("twin");
+        let worktree = temp.path().join("wt");
+        fs::create_dir_all(&worktree).unwrap();
+        let manifest = Manifest::new(&worktree, "feature", &ConfigSettings::default());
+        let legacy = Manifest::legacy_path_for(&twin_dir, &worktree);
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        fs::write(&legacy, serde_json::to_string(&manifest).unwrap()).unwrap();
+
+        assert_eq!(
+            Manifest::load(&twin_dir, &worktree).unwrap(),
+            Some(manifest.clone())
+        );
+        manifest.save(&twin_dir).unwrap();
+        assert!(!legacy.exists());
+        assert!(Manifest::path_for(&twin_dir, &worktree).exists());
+    }
+
+    #[test]
+    fn test_config_hash_detects_changes() {
+        let mut settings = ConfigSettings::default();
+        let before = config_hash(&settings);
+        assert_eq!(before, config_hash(&settings.clone()));
+
+        settings.worktree_base = Some(PathBuf::from("../wt"));
+        assert_ne!(before, config_hash(&settings));
+    }
+}
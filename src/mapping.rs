@@ -0,0 +1,600 @@
+//! ファイルマッピング適用モジュール
+//!
+//! このモジュールの役割：
+//! - マッピングタイプ（symlink/copy/template）に応じた副作用の適用
+//! - ディレクトリの再帰コピーとパーミッションの保持
+//! - skip_if_exists の判定
+//! - 削除時のコピー済みファイルのバックアップ
+//! - 適用済みマッピングの状態（リンク切れ・実ファイルへの置き換えなど）の確認
+//! - 壊れた・不足しているマッピングの再適用
+
+#![allow(dead_code)]
+use crate::core::{FileMapping, MappingType, TwinError, TwinResult};
+use crate::manifest::worktree_key;
+use crate::symlink::SymlinkManager;
+use crate::template;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// マッピング適用の結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum MappingOutcome {
+    /// シンボリックリンクを作成した
+    Linked,
+    /// ファイルまたはディレクトリをコピーした
+    Copied,
+    /// テンプレートを展開した
+    Rendered,
+    /// 既存のファイルがあるためスキップした
+    Skipped,
+}
+
+/// 適用済みマッピングの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingHealth {
+    /// 正常（リンク先が存在する、またはコピー・展開されたファイルが存在する）
+    Valid,
+    /// リンク先が存在しないシンボリックリンク
+    BrokenLink,
+    /// シンボリックリンクが実ファイルに置き換えられている
+    ReplacedByFile,
+    /// シンボリックリンクが設定と異なるソースを指している（リポジトリの移動後など）
+    WrongSource,
+    /// ターゲットが存在しない
+    Missing,
+}
+
+impl MappingHealth {
+    /// 状態の文字列表現
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MappingHealth::Valid => "valid",
+            MappingHealth::BrokenLink => "broken_link",
+            MappingHealth::ReplacedByFile => "replaced_by_file",
+            MappingHealth::WrongSource => "wrong_source",
+            MappingHealth::Missing => "missing",
+        }
+    }
+
+    /// 正常な状態か
+    pub fn is_valid(&self) -> bool {
+        *self == MappingHealth::Valid
+    }
+}
+
+/// マッピングのターゲットの状態を確認する
+///
+/// `source`と`target`は解決済みの絶対パスを受け取る。
+pub fn check_mapping(
+    mapping: &FileMapping,
+    source: &Path,
+    target: &Path,
+    symlink_manager: &dyn SymlinkManager,
+) -> TwinResult<MappingHealth> {
+    if target.is_symlink() {
+        // コピー・テンプレートのターゲットがリンクでも、リンク先があれば利用できる
+        if mapping.mapping_type == MappingType::Symlink && !links_to(target, source) {
+            return Ok(MappingHealth::WrongSource);
+        }
+        return Ok(if symlink_manager.validate_symlink(target)? {
+            MappingHealth::Valid
+        } else {
+            MappingHealth::BrokenLink
+        });
+    }
+
+    Ok(match (&mapping.mapping_type, target.exists()) {
+        (_, false) => MappingHealth::Missing,
+        // skip_if_existsのマッピングは既存のファイルを尊重する
+        (MappingType::Symlink, true) if !mapping.skip_if_exists => MappingHealth::ReplacedByFile,
+        (_, true) => MappingHealth::Valid,
+    })
+}
+
+/// シンボリックリンクが指定したソースを指しているか
+fn links_to(link: &Path, source: &Path) -> bool {
+    let Ok(destination) = fs::read_link(link) else {
+        return false;
+    };
+    let destination = match link.parent() {
+        Some(parent) if destination.is_relative() => parent.join(destination),
+        _ => destination,
+    };
+    if destination == source {
+        return true;
+    }
+
+    match (fs::canonicalize(&destination), fs::canonicalize(source)) {
+        (Ok(destination), Ok(source)) => destination == source,
+        _ => false,
+    }
+}
+
+/// マッピングを1件適用する
+///
+/// `source`と`target`は解決済みの絶対パスを受け取る。
+/// `variables`はテンプレート展開に使用する変数（`template::context_variables`で構築）。
+pub fn apply_mapping(
+    mapping: &FileMapping,
+    source: &Path,
+    target: &Path,
+    symlink_manager: &dyn SymlinkManager,
+    variables: &HashMap<String, String>,
+) -> TwinResult<MappingOutcome> {
+    if mapping.skip_if_exists && (target.exists() || target.is_symlink()) {
+        return Ok(MappingOutcome::Skipped);
+    }
+
+    match mapping.mapping_type {
+        MappingType::Symlink => {
+            symlink_manager.create_symlink(source, target)?;
+            Ok(MappingOutcome::Linked)
+        }
+        MappingType::Copy => {
+            copy_path(source, target)?;
+            Ok(MappingOutcome::Copied)
+        }
+        MappingType::Template => {
+            let variables =
+                template::with_mapping_variables(variables.clone(), &mapping.variables)?;
+            template::render_file(source, target, &variables)?;
+            Ok(MappingOutcome::Rendered)
+        }
+    }
+}
+
+/// 壊れている・不足しているマッピングを適用し直す（冪等）
+///
+/// 正常なマッピングと、実ファイルに置き換えられたリンク（ユーザーのデータの可能性がある）はそのままにし、
+/// Noneを返す。ソースは存在している必要がある。
+pub fn repair_mapping(
+    mapping: &FileMapping,
+    source: &Path,
+    target: &Path,
+    symlink_manager: &dyn SymlinkManager,
+    variables: &HashMap<String, String>,
+) -> TwinResult<Option<MappingOutcome>> {
+    match check_mapping(mapping, source, target, symlink_manager)? {
+        MappingHealth::Valid | MappingHealth::ReplacedByFile => return Ok(None),
+        MappingHealth::BrokenLink | MappingHealth::WrongSource => fs::remove_file(target)?,
+        MappingHealth::Missing => {}
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    apply_mapping(mapping, source, target, symlink_manager, variables).map(Some)
+}
+
+/// ファイルまたはディレクトリをコピーする
+///
+/// ディレクトリは再帰的にコピーし、パーミッションも引き継ぐ。
+/// ターゲットに既存のシンボリックリンクがある場合は、リンク先を上書きしないよう先に削除する。
+pub fn copy_path(source: &Path, target: &Path) -> TwinResult<()> {
+    let metadata = fs::symlink_metadata(source).map_err(|e| {
+        TwinError::io(
+            format!("Failed to read source {}: {e}", source.display()),
+            Some(source.to_path_buf()),
+        )
+    })?;
+
+    if target.is_symlink() {
+        fs::remove_file(target)?;
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if metadata.file_type().is_symlink() {
+        copy_symlink(source, target)
+    } else if metadata.is_dir() {
+        if target.exists() && !target.is_dir() {
+            fs::remove_file(target)?;
+        }
+        fs::create_dir_all(target)?;
+
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &target.join(entry.file_name()))?;
+        }
+
+        fs::set_permissions(target, metadata.permissions())?;
+        Ok(())
+    } else {
+        if target.is_dir() {
+            fs::remove_dir_all(target)?;
+        }
+        // fs::copyはパーミッションも合わせてコピーする
+        fs::copy(source, target).map_err(|e| {
+            TwinError::io(
+                format!(
+                    "Failed to copy {} to {}: {e}",
+                    source.display(),
+                    target.display()
+                ),
+                Some(target.to_path_buf()),
+            )
+        })?;
+        Ok(())
+    }
+}
+
+/// ディレクトリ内のシンボリックリンクはリンクのまま複製する
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> TwinResult<()> {
+    let link = fs::read_link(source)?;
+    if target.exists() || target.is_symlink() {
+        fs::remove_file(target)?;
+    }
+    std::os::unix::fs::symlink(link, target)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn copy_symlink(source: &Path, target: &Path) -> TwinResult<()> {
+    // Windowsではリンクの作成に権限が必要なため、実体をコピーする
+    if source.is_dir() {
+        copy_path(&fs::canonicalize(source)?, target)
+    } else {
+        fs::copy(source, target)?;
+        Ok(())
+    }
+}
+
+/// 2つのパスの内容が同一かどうかを判定（ディレクトリは再帰的に比較）
+pub fn same_content(a: &Path, b: &Path) -> TwinResult<bool> {
+    if a.is_dir() != b.is_dir() {
+        return Ok(false);
+    }
+
+    if a.is_dir() {
+        let mut a_entries = fs::read_dir(a)?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut b_entries = fs::read_dir(b)?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        a_entries.sort();
+        b_entries.sort();
+        if a_entries != b_entries {
+            return Ok(false);
+        }
+        for name in a_entries {
+            if !same_content(&a.join(&name), &b.join(&name))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    } else {
+        Ok(fs::read(a)? == fs::read(b)?)
+    }
+}
+
+/// コピーされたファイルをバックアップする
+///
+/// ソースと内容が同じ場合はバックアップ不要としてNoneを返す。
+/// 変更されている場合は`backup_root`以下に同じ相対パスでコピーし、そのパスを返す。
+pub fn backup_copied(
+    source: &Path,
+    target: &Path,
+    relative: &Path,
+    backup_root: &Path,
+) -> TwinResult<Option<PathBuf>> {
+    if !target.exists() || target.is_symlink() {
+        return Ok(None);
+    }
+
+    if source.exists() && same_content(source, target)? {
+        return Ok(None);
+    }
+
+    backup_to(target, relative, backup_root).map(Some)
+}
+
+/// テンプレートから生成されたファイルをバックアップする
+///
+/// 展開結果（`expected`）から変更されている場合のみバックアップし、そのパスを返す。
+pub fn backup_rendered(
+    expected: &str,
+    target: &Path,
+    relative: &Path,
+    backup_root: &Path,
+) -> TwinResult<Option<PathBuf>> {
+    if !target.is_file() || target.is_symlink() {
+        return Ok(None);
+    }
+
+    if fs::read(target)? == expected.as_bytes() {
+        return Ok(None);
+    }
+
+    backup_to(target, relative, backup_root).map(Some)
+}
+
+/// 削除時のバックアップ先（`<twin_dir>/backups/<ワークツリー名>-<パスのハッシュ>/<操作ID>`）
+///
+/// 同名のワークツリーや同じ名前で作り直したワークツリーのバックアップを上書きしないよう、
+/// ワークツリーのパスと削除操作ごとに別のディレクトリにする。
+pub fn backup_root(twin_dir: &Path, worktree: &Path, operation_id: &str) -> PathBuf {
+    twin_dir
+        .join("backups")
+        .join(worktree_key(worktree))
+        .join(operation_id)
+}
+
+/// `backup_root`以下に同じ相対パスでコピーする
+///
+/// 既に内容の異なるバックアップがある場合は上書きせずにエラーにする（中断した削除の再開では同じ内容になる）。
+pub fn backup_to(target: &Path, relative: &Path, backup_root: &Path) -> TwinResult<PathBuf> {
+    let backup_path = backup_root.join(relative);
+    if backup_path.exists() || backup_path.is_symlink() {
+        if same_content(target, &backup_path)? {
+            return Ok(backup_path);
+        }
+        return Err(TwinError::io(
+            format!("Backup already exists: {}", backup_path.display()),
+            Some(backup_path),
+        ));
+    }
+    copy_path(target, &backup_path)?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symlink::create_symlink_manager;
+    use tempfile::TempDir;
+
+    fn mapping(mapping_type: MappingType, skip_if_exists: bool) -> FileMapping {
+        FileMapping {
+            source: PathBuf::from("ignored"),
+            target: None,
+            mapping_type,
+            description: None,
+            skip_if_exists,
+            variables: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_copy_directory_recursively() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("src");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("nested/b.txt"), "b").unwrap();
+
+        let target = temp.path().join("wt/dst");
+        let outcome = apply_mapping(
+            &mapping(MappingType::Copy, false),
+            &source,
+            &target,
+            create_symlink_manager().as_ref(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(outcome, MappingOutcome::Copied);
+        assert!(!target.is_symlink());
+        assert_eq!(
+            fs::read_to_string(target.join("nested/b.txt")).unwrap(),
+            "b"
+        );
+        assert!(same_content(&source, &target).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_repair_mapping_is_idempotent() {
+        let temp = TempDir::new().unwrap();
+        let manager = create_symlink_manager();
+        let source = temp.path().join("repo/.env");
+        let target = temp.path().join("wt/config/.env");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, "A=1").unwrap();
+        let link = mapping(MappingType::Symlink, false);
+        let repair =
+            || repair_mapping(&link, &source, &target, manager.as_ref(), &HashMap::new()).unwrap();
+
+        // 不足しているリンクを作成し、2回目は何もしない
+        assert_eq!(repair(), Some(MappingOutcome::Linked));
+        assert_eq!(repair(), None);
+
+        // 古いパスを指すリンクを作り直す
+        fs::remove_file(&target).unwrap();
+        std::os::unix::fs::symlink(temp.path().join("old/.env"), &target).unwrap();
+        assert_eq!(repair(), Some(MappingOutcome::Linked));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "A=1");
+
+        // 実ファイルに置き換えられている場合は上書きしない
+        fs::remove_file(&target).unwrap();
+        fs::write(&target, "local").unwrap();
+        assert_eq!(repair(), None);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "local");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_mapping_health() {
+        let temp = TempDir::new().unwrap();
+        let manager = create_symlink_manager();
+        let source = temp.path().join(".env");
+        let target = temp.path().join("wt/.env");
+        fs::write(&source, "A=1").unwrap();
+        let link = mapping(MappingType::Symlink, false);
+
+        let health =
+            |mapping: &FileMapping| check_mapping(mapping, &source, &target, manager.as_ref());
+        assert_eq!(health(&link).unwrap(), MappingHealth::Missing);
+
+        manager.create_symlink(&source, &target).unwrap();
+        assert_eq!(health(&link).unwrap(), MappingHealth::Valid);
+
+        fs::remove_file(&source).unwrap();
+        assert_eq!(health(&link).unwrap(), MappingHealth::BrokenLink);
+
+        // リポジトリの移動などで別のパスを指しているリンク
+        fs::remove_file(&target).unwrap();
+        fs::write(&source, "A=1").unwrap();
+        std::os::unix::fs::symlink(temp.path().join("old/.env"), &target).unwrap();
+        assert_eq!(health(&link).unwrap(), MappingHealth::WrongSource);
+
+        fs::remove_file(&target).unwrap();
+        fs::write(&target, "A=2").unwrap();
+        assert_eq!(health(&link).unwrap(), MappingHealth::ReplacedByFile);
+        assert_eq!(
+            health(&mapping(MappingType::Copy, false)).unwrap(),
+            MappingHealth::Valid
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("run.sh");
+        fs::write(&source, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o750)).unwrap();
+
+        let target = temp.path().join("copy/run.sh");
+        copy_path(&source, &target).unwrap();
+
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+
+    #[test]
+    fn test_skip_if_exists_keeps_target() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join(".env");
+        let target = temp.path().join("wt/.env");
+        fs::write(&source, "A=1").unwrap();
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "A=local").unwrap();
+
+        let outcome = apply_mapping(
+            &mapping(MappingType::Copy, true),
+            &source,
+            &target,
+            create_symlink_manager().as_ref(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(outcome, MappingOutcome::Skipped);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "A=local");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_replaces_symlink_without_touching_source() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join(".env");
+        let target = temp.path().join("wt/.env");
+        fs::write(&source, "A=1").unwrap();
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(&source, &target).unwrap();
+
+        copy_path(&source, &target).unwrap();
+        fs::write(&target, "A=2").unwrap();
+
+        assert!(!target.is_symlink());
+        assert_eq!(fs::read_to_string(&source).unwrap(), "A=1");
+    }
+
+    #[test]
+    fn test_backup_only_modified_copies() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join(".env");
+        let target = temp.path().join("wt/.env");
+        let backup_root = temp.path().join("backups");
+        fs::write(&source, "A=1").unwrap();
+        copy_path(&source, &target).unwrap();
+
+        let backup = backup_copied(&source, &target, Path::new(".env"), &backup_root).unwrap();
+        assert!(backup.is_none());
+
+        fs::write(&target, "A=changed").unwrap();
+        let backup = backup_copied(&source, &target, Path::new(".env"), &backup_root)
+            .unwrap()
+            .unwrap();
+        assert_eq!(backup, backup_root.join(".env"));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "A=changed");
+
+        // 同じ内容のバックアップはそのまま使い、異なる内容のバックアップは上書きしない
+        assert_eq!(
+            backup_to(&target, Path::new(".env"), &backup_root).unwrap(),
+            backup
+        );
+        fs::write(&target, "A=changed again").unwrap();
+        assert!(backup_to(&target, Path::new(".env"), &backup_root).is_err());
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "A=changed");
+    }
+
+    #[test]
+    fn test_backup_root_separates_worktrees_and_operations() {
+        let twin_dir = Path::new("/repo/.git/twin");
+        let first = backup_root(twin_dir, Path::new("/a/wt"), "20260101-000000-1-0");
+        assert!(first.starts_with("/repo/.git/twin/backups"));
+        assert_ne!(
+            first,
+            backup_root(twin_dir, Path::new("/b/wt"), "20260101-000000-1-0")
+        );
+        assert_ne!(
+            first,
+            backup_root(twin_dir, Path::new("/a/wt"), "20260102-000000-1-0")
+        );
+    }
+
+    #[test]
+    fn test_template_mapping_renders_variables() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join(".env.template");
+        let target = temp.path().join("wt/.env");
+        fs::write(&source, "DB={{db_name}}\nBRANCH={{branch}}\n").unwrap();
+
+        let mut template_mapping = mapping(MappingType::Template, false);
+        template_mapping
+            .variables
+            .insert("db_name".to_string(), "app_{{branch}}".to_string());
+        let mut variables = HashMap::new();
+        variables.insert("branch".to_string(), "feature".to_string());
+
+        let outcome = apply_mapping(
+            &template_mapping,
+            &source,
+            &target,
+            create_symlink_manager().as_ref(),
+            &variables,
+        )
+        .unwrap();
+
+        assert_eq!(outcome, MappingOutcome::Rendered);
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            "DB=app_feature\nBRANCH=feature\n"
+        );
+
+        // 展開結果から変更されていなければバックアップしない
+        let backup_root = temp.path().join("backups");
+        let expected = fs::read_to_string(&target).unwrap();
+        assert!(
+            backup_rendered(&expected, &target, Path::new(".env"), &backup_root)
+                .unwrap()
+                .is_none()
+        );
+        fs::write(&target, "DB=edited\n").unwrap();
+        assert!(
+            backup_rendered(&expected, &target, Path::new(".env"), &backup_root)
+                .unwrap()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! 実行計画（ドライラン）モジュール
+//!
+//! このモジュールの役割：
+//! - `--dry-run`で表示する実行計画（gitコマンド・マッピング・フック・削除するファイル）の構築
+//! - 実行計画のテキスト・JSON形式での出力
+
+#![allow(dead_code)]
+use crate::core::{HookCommand, MappingType, TwinError, TwinResult};
+use crate::git::GitManager;
+use crate::hooks::{HookContext, HookExecutor, HookType};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 実行計画の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum PlanFormat {
+    /// 人が読むためのテキスト
+    #[default]
+    Text,
+    /// 機械処理のためのJSON
+    Json,
+}
+
+/// マッピングに対して行う操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingAction {
+    /// シンボリックリンクを作成
+    Link,
+    /// コピーを作成
+    Copy,
+    /// テンプレートを展開して作成
+    Template,
+    /// シンボリックリンクを作り直す
+    Relink,
+    /// シンボリックリンクを削除
+    Unlink,
+    /// 変更されたファイルをバックアップ
+    Backup,
+}
+
+impl MappingAction {
+    /// マッピングタイプに対応する作成操作
+    pub fn for_type(mapping_type: &MappingType) -> Self {
+        match mapping_type {
+            MappingType::Symlink => MappingAction::Link,
+            MappingType::Copy => MappingAction::Copy,
+            MappingType::Template => MappingAction::Template,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MappingAction::Link => "link",
+            MappingAction::Copy => "copy",
+            MappingAction::Template => "template",
+            MappingAction::Relink => "relink",
+            MappingAction::Unlink => "unlink",
+            MappingAction::Backup => "backup",
+        }
+    }
+}
+
+/// 実行計画の1ステップ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlanStep {
+    /// 実行するgitコマンド
+    Git { command: String },
+    /// マッピングの操作（`skip_reason`がある場合は実行されない）
+    Mapping {
+        action: MappingAction,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<PathBuf>,
+        target: PathBuf,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skip_reason: Option<String>,
+    },
+    /// 実行するフック（プレースホルダー展開後のコマンド）
+    Hook {
+        hook_type: String,
+        command: String,
+        worktree: PathBuf,
+    },
+    /// 削除するtwinの管理ファイル・gitの管理情報
+    Remove { path: PathBuf, reason: String },
+}
+
+/// コマンドの実行計画
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Plan {
+    /// 対象のコマンド名
+    pub command: String,
+    /// 実行順のステップ
+    pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    /// 空の実行計画を作成
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// ドライランの`GitManager`が実行しなかったコマンドを追加
+    pub fn git_commands(&mut self, git: &mut GitManager) {
+        for command in git.take_planned_commands() {
+            self.steps.push(PlanStep::Git { command });
+        }
+    }
+
+    /// マッピングの操作を追加
+    pub fn mapping(
+        &mut self,
+        action: MappingAction,
+        source: Option<&Path>,
+        target: &Path,
+        skip_reason: Option<&str>,
+    ) {
+        self.steps.push(PlanStep::Mapping {
+            action,
+            source: source.map(Path::to_path_buf),
+            target: target.to_path_buf(),
+            skip_reason: skip_reason.map(String::from),
+        });
+    }
+
+    /// フックを展開して追加
+    pub fn hooks(
+        &mut self,
+        executor: &HookExecutor,
+        hook_type: HookType,
+        hooks: &[HookCommand],
+        context: &HookContext,
+    ) -> TwinResult<()> {
+        for hook in hooks {
+            self.steps.push(PlanStep::Hook {
+                hook_type: hook_type.as_str().to_string(),
+                command: executor.expand(hook_type, hook, context)?,
+                worktree: context.worktree_path.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// 削除するファイルを追加
+    pub fn remove(&mut self, path: &Path, reason: impl Into<String>) {
+        self.steps.push(PlanStep::Remove {
+            path: path.to_path_buf(),
+            reason: reason.into(),
+        });
+    }
+
+    /// 指定した形式で標準出力に表示
+    pub fn print(&self, format: PlanFormat) -> TwinResult<()> {
+        match format {
+            PlanFormat::Text => print!("{}", self.render_text()),
+            PlanFormat::Json => {
+                let json = serde_json::to_string_pretty(self)
+                    .map_err(|e| TwinError::other(format!("Failed to serialize plan: {e}")))?;
+                println!("{json}");
+            }
+        }
+        Ok(())
+    }
+
+    /// テキスト形式に整形
+    pub fn render_text(&self) -> String {
+        let mut text = format!("📝 ドライラン: twin {}（何も変更しません）\n", self.command);
+        if self.steps.is_empty() {
+            text.push_str("  実行する操作はありません\n");
+        }
+
+        for step in &self.steps {
+            let line = match step {
+                PlanStep::Git { command } => format!("{:<9} {command}", "git"),
+                PlanStep::Mapping {
+                    action,
+                    source,
+                    target,
+                    skip_reason,
+                } => {
+                    let paths = match source {
+                        Some(source) => format!("{} -> {}", source.display(), target.display()),
+                        None => target.display().to_string(),
+                    };
+                    match skip_reason {
+                        Some(reason) => {
+                            format!("{:<9} {} {paths} ({reason})", "skip", action.as_str())
+                        }
+                        None => format!("{:<9} {paths}", action.as_str()),
+                    }
+                }
+                PlanStep::Hook {
+                    hook_type, command, ..
+                } => format!("{:<9} [{hook_type}] {command}", "hook"),
+                PlanStep::Remove { path, reason } => {
+                    format!("{:<9} {} ({reason})", "remove", path.display())
+                }
+            };
+            text.push_str(&format!("  {line}\n"));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_plan_text_and_json() {
+        let mut plan = Plan::new("add");
+        let context = HookContext::new("feature", "/work/feature", "feature", "/repo");
+        let hook = HookCommand {
+            command: "echo {{branch}}".to_string(),
+            args: vec![],
+            env: HashMap::new(),
+            timeout: 60,
+            continue_on_error: false,
+        };
+        plan.steps.push(PlanStep::Git {
+            command: "git worktree add -b feature /work/feature".to_string(),
+        });
+        plan.mapping(
+            MappingAction::Link,
+            Some(Path::new("/repo/.env")),
+            Path::new("/work/feature/.env"),
+            None,
+        );
+        plan.mapping(
+            MappingAction::for_type(&MappingType::Copy),
+            Some(Path::new("/repo/missing")),
+            Path::new("/work/feature/missing"),
+            Some("source not found"),
+        );
+        plan.hooks(
+            &HookExecutor::new(),
+            HookType::PostCreate,
+            &[hook],
+            &context,
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan.render_text(),
+            "📝 ドライラン: twin add（何も変更しません）\n\
+             \x20 git       git worktree add -b feature /work/feature\n\
+             \x20 link      /repo/.env -> /work/feature/.env\n\
+             \x20 skip      copy /repo/missing -> /work/feature/missing (source not found)\n\
+             \x20 hook      [post_create] echo feature\n"
+        );
+
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["command"], "add");
+        assert_eq!(json["steps"][0]["kind"], "git");
+        assert_eq!(json["steps"][1]["action"], "link");
+        assert!(json["steps"][1].get("skip_reason").is_none());
+        assert_eq!(json["steps"][2]["skip_reason"], "source not found");
+        assert_eq!(json["steps"][3]["command"], "echo feature");
+        assert_eq!(json["steps"][3]["hook_type"], "post_create");
+    }
+}
@@ -0,0 +1,262 @@
+//! 孤立した副作用のクリーンアップモジュール
+//!
+//! このモジュールの役割：
+//! - gitが管理していないワークツリーのマニフェスト・フックログの検出
+//! - 残ったワークツリーのディレクトリにあるtwinが作成したシンボリックリンクの検出
+//! - 孤立した副作用の削除
+
+#![allow(dead_code)]
+use crate::core::{MappingType, TwinResult};
+use crate::hook_log::HookLog;
+use crate::manifest::{same_worktree, worktree_key, worktree_key_name, Manifest};
+use crate::symlink::SymlinkManager;
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// gitが管理していないワークツリーに残った副作用
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Orphan {
+    /// ワークツリーのパス（記録から取得できない場合はログのファイル名）
+    pub worktree: PathBuf,
+    /// ブランチ名（記録から取得できた場合）
+    pub branch: Option<String>,
+    /// マニフェストのファイル
+    pub manifest: Option<PathBuf>,
+    /// フックログのファイル
+    pub log: Option<PathBuf>,
+    /// 残っているtwinが作成したシンボリックリンク
+    pub links: Vec<PathBuf>,
+}
+
+impl Orphan {
+    /// 表示用の名前（ワークツリーのディレクトリ名）
+    pub fn name(&self) -> String {
+        self.worktree
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.worktree.display().to_string())
+    }
+
+    /// 残っている副作用の説明
+    pub fn description(&self) -> String {
+        let mut items = Vec::new();
+        if !self.links.is_empty() {
+            items.push(format!("{} link(s)", self.links.len()));
+        }
+        if self.manifest.is_some() {
+            items.push("manifest".to_string());
+        }
+        if self.log.is_some() {
+            items.push("hook log".to_string());
+        }
+        items.join(", ")
+    }
+}
+
+/// gitが管理していないワークツリーの副作用を探す
+///
+/// `known`にはgitが管理している（pruneで削除されない）ワークツリーのパスを渡す。
+pub fn find_orphans(twin_dir: &Path, known: &[PathBuf]) -> TwinResult<Vec<Orphan>> {
+    let is_known = |worktree: &Path| known.iter().any(|path| same_worktree(path, worktree));
+    let known_keys: Vec<_> = known.iter().map(|path| worktree_key(path)).collect();
+    let has_known_name = |name: &str| {
+        known
+            .iter()
+            .any(|path| path.file_name().map(|n| n.to_string_lossy()) == Some(name.into()))
+    };
+    let mut orphans = Vec::new();
+
+    // マニフェストからワークツリーのパスと作成したリンクを取得する
+    for path in list_files(&twin_dir.join("manifests"), "json")? {
+        let manifest = match Manifest::read(&path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Skipping unreadable manifest {}: {e}", path.display());
+                continue;
+            }
+        };
+        if is_known(&manifest.worktree) {
+            continue;
+        }
+
+        let links = manifest
+            .mappings
+            .iter()
+            .filter(|applied| applied.mapping_type == MappingType::Symlink)
+            .map(|applied| manifest.worktree.join(&applied.target))
+            .filter(|target| target.is_symlink())
+            .collect();
+        orphans.push(Orphan {
+            worktree: manifest.worktree.clone(),
+            branch: Some(manifest.branch.clone()),
+            manifest: Some(path),
+            links,
+            log: None,
+        });
+    }
+
+    let manifest_key = |orphan: &Orphan| {
+        orphan
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+    };
+    // フックログはマニフェストと同じキーで保存されている。ワークツリー名だけで保存していた以前の形式のログは、
+    // 同名のワークツリーがないものを対象にする
+    for path in list_files(&twin_dir.join("logs"), "jsonl")? {
+        let Some(key) = path.file_stem().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let name = worktree_key_name(&key).to_string();
+        let legacy = name == key;
+        if known_keys.contains(&key) || (legacy && has_known_name(&name)) {
+            continue;
+        }
+
+        if let Some(orphan) = orphans.iter_mut().find(|orphan| {
+            manifest_key(orphan).as_ref() == Some(&key) || (legacy && orphan.name() == name)
+        }) {
+            orphan.log = Some(path);
+            continue;
+        }
+        let last = HookLog::new(&path)
+            .read()
+            .ok()
+            .and_then(|entries| entries.into_iter().last());
+        orphans.push(Orphan {
+            worktree: last
+                .as_ref()
+                .map(|entry| entry.worktree.clone())
+                .unwrap_or_else(|| PathBuf::from(&name)),
+            branch: last.map(|entry| entry.branch),
+            log: Some(path),
+            ..Default::default()
+        });
+    }
+
+    Ok(orphans)
+}
+
+/// 孤立した副作用を削除する
+///
+/// リンクを削除して空になったディレクトリは削除するが、ユーザーのファイルが残っているディレクトリは残す。
+pub fn clean_orphan(orphan: &Orphan, symlink_manager: &dyn SymlinkManager) -> TwinResult<()> {
+    for link in &orphan.links {
+        symlink_manager.remove_symlink(link)?;
+        let mut dir = link.parent();
+        while let Some(current) = dir.filter(|dir| dir.starts_with(&orphan.worktree)) {
+            if fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+
+    for file in [&orphan.manifest, &orphan.log].into_iter().flatten() {
+        if file.exists() {
+            fs::remove_file(file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// ディレクトリ内の指定した拡張子のファイルを名前順に取得（ディレクトリがない場合は空）
+fn list_files(dir: &Path, extension: &str) -> TwinResult<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) == Some(extension) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ConfigSettings;
+    use crate::manifest::AppliedMapping;
+    use crate::symlink::create_symlink_manager;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_and_clean_orphans() {
+        let temp = TempDir::new().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let twin_dir = root.join("repo/.git/twin");
+        let source = root.join("repo/.env");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, "A=1").unwrap();
+
+        // gitが管理しなくなったワークツリーのディレクトリにリンクが残っている
+        let gone = root.join("gone");
+        let manager = create_symlink_manager();
+        manager
+            .create_symlink(&source, &gone.join("config/.env"))
+            .unwrap();
+        let mut manifest = Manifest::new(&gone, "feature/gone", &ConfigSettings::default());
+        manifest.record_mapping(AppliedMapping::new(
+            MappingType::Symlink,
+            &source,
+            Path::new("config/.env"),
+            &gone,
+        ));
+        manifest.save(&twin_dir).unwrap();
+        let gone_log = HookLog::for_worktree(&twin_dir, &gone).path().to_path_buf();
+        fs::create_dir_all(twin_dir.join("logs")).unwrap();
+        fs::write(&gone_log, "").unwrap();
+
+        // 管理されているワークツリーと、ログのみ残っている同名のワークツリー・以前の形式のログ
+        let alive = root.join("alive");
+        Manifest::new(&alive, "alive", &ConfigSettings::default())
+            .save(&twin_dir)
+            .unwrap();
+        let alive_log = HookLog::for_worktree(&twin_dir, &alive)
+            .path()
+            .to_path_buf();
+        fs::write(&alive_log, "").unwrap();
+        let old_log = HookLog::for_worktree(&twin_dir, &root.join("old/alive"))
+            .path()
+            .to_path_buf();
+        fs::write(&old_log, "").unwrap();
+        fs::write(twin_dir.join("logs/alive.jsonl"), "").unwrap();
+        fs::write(twin_dir.join("logs/removed.jsonl"), "").unwrap();
+
+        let orphans = find_orphans(&twin_dir, &[root.join("repo"), alive]).unwrap();
+        assert_eq!(orphans.len(), 3);
+        assert_eq!(orphans[0].worktree, gone);
+        assert_eq!(orphans[0].branch.as_deref(), Some("feature/gone"));
+        assert_eq!(orphans[0].links, vec![gone.join("config/.env")]);
+        assert_eq!(orphans[0].log, Some(gone_log.clone()));
+        assert_eq!(orphans[0].description(), "1 link(s), manifest, hook log");
+        assert_eq!(orphans[1].log, Some(old_log.clone()));
+        assert_eq!(orphans[1].name(), "alive");
+        assert_eq!(orphans[2].name(), "removed");
+        assert_eq!(orphans[2].manifest, None);
+
+        for orphan in &orphans {
+            clean_orphan(orphan, manager.as_ref()).unwrap();
+        }
+        // 空になったディレクトリも削除される
+        assert!(!gone.exists());
+        assert!(source.exists());
+        assert!(!gone_log.exists());
+        assert!(!old_log.exists());
+        assert!(!twin_dir.join("logs/removed.jsonl").exists());
+        assert!(alive_log.exists());
+        assert!(twin_dir.join("logs/alive.jsonl").exists());
+        assert!(find_orphans(&twin_dir, &[root.join("alive")])
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+//! ロールバック処理モジュール
+//!
+//! このモジュールの役割：
+//! - `PartialFailureState`に記録されたステップ名と詳細情報の定義
+//! - 失敗した操作の成功済みステップを逆順に元に戻す
+//! - 元に戻した内容・戻せなかった内容の集計
+
+#![allow(dead_code)]
+use crate::core::{OperationStep, PartialFailureState, TwinError, TwinResult};
+use crate::git::GitManager;
+use crate::symlink::create_symlink_manager;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// pre_createフックの実行（元に戻せない）
+pub const STEP_PRE_CREATE: &str = "pre_create";
+/// ブランチの作成
+pub const STEP_CREATE_BRANCH: &str = "create_branch";
+/// git worktree addの実行
+pub const STEP_ADD_WORKTREE: &str = "add_worktree";
+/// ファイルマッピングの適用
+pub const STEP_APPLY_MAPPING: &str = "apply_mapping";
+/// post_createフックの実行（元に戻せない）
+pub const STEP_POST_CREATE: &str = "post_create";
+/// pre_removeフックの実行（元に戻せない）
+pub const STEP_PRE_REMOVE: &str = "pre_remove";
+/// シンボリックリンクマッピングの削除
+pub const STEP_REMOVE_MAPPING: &str = "remove_mapping";
+/// git worktree removeの実行（元に戻せない）
+pub const STEP_REMOVE_WORKTREE: &str = "remove_worktree";
+/// post_removeフックの実行（元に戻せない）
+pub const STEP_POST_REMOVE: &str = "post_remove";
+
+/// ステップの詳細情報のキー
+pub const DETAIL_BRANCH: &str = "branch";
+pub const DETAIL_PATH: &str = "path";
+pub const DETAIL_SOURCE: &str = "source";
+pub const DETAIL_TARGET: &str = "target";
+pub const DETAIL_MAPPING_TYPE: &str = "mapping_type";
+pub const DETAIL_COMMAND: &str = "command";
+
+/// ロールバックの結果
+#[derive(Debug, Default)]
+pub struct RollbackReport {
+    /// 元に戻したステップの説明
+    pub undone: Vec<String>,
+    /// 元に戻せなかったステップの説明とエラー
+    pub failed: Vec<(String, String)>,
+    /// 実行済みだが元に戻す方法がないステップの説明（フックなど）
+    pub not_undoable: Vec<String>,
+}
+
+impl RollbackReport {
+    /// すべてのステップを問題なく元に戻せたか
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// 何も行われなかったか
+    pub fn is_empty(&self) -> bool {
+        self.undone.is_empty() && self.failed.is_empty() && self.not_undoable.is_empty()
+    }
+}
+
+/// 成功済みのステップを逆順に元に戻す
+///
+/// 1つのステップで失敗しても残りのステップのロールバックは続行する。
+pub fn rollback(state: &PartialFailureState, git: &mut GitManager) -> RollbackReport {
+    let mut report = RollbackReport::default();
+
+    for step in state.succeeded_steps.iter().rev() {
+        let description = describe(step);
+        if !step.can_rollback {
+            report.not_undoable.push(description);
+            continue;
+        }
+
+        match undo_step(step, git) {
+            Ok(()) => report.undone.push(description),
+            Err(e) => report.failed.push((description, e.to_string())),
+        }
+    }
+
+    report
+}
+
+/// ステップの説明を取得
+pub fn describe(step: &OperationStep) -> String {
+    let detail = |key| step.detail(key).unwrap_or("?");
+    match step.name.as_str() {
+        STEP_PRE_CREATE | STEP_POST_CREATE | STEP_PRE_REMOVE | STEP_POST_REMOVE => {
+            format!("{} hook: {}", step.name, detail(DETAIL_COMMAND))
+        }
+        STEP_CREATE_BRANCH => format!("branch: {}", detail(DETAIL_BRANCH)),
+        STEP_ADD_WORKTREE | STEP_REMOVE_WORKTREE => {
+            format!("worktree: {}", detail(DETAIL_PATH))
+        }
+        STEP_APPLY_MAPPING | STEP_REMOVE_MAPPING => format!(
+            "{}: {} -> {}",
+            detail(DETAIL_MAPPING_TYPE),
+            detail(DETAIL_SOURCE),
+            detail(DETAIL_TARGET)
+        ),
+        other => other.to_string(),
+    }
+}
+
+/// 1つのステップを元に戻す
+fn undo_step(step: &OperationStep, git: &mut GitManager) -> TwinResult<()> {
+    match step.name.as_str() {
+        STEP_APPLY_MAPPING => match step.detail(DETAIL_TARGET) {
+            Some(target) => remove_mapping_target(Path::new(target)),
+            None => Ok(()),
+        },
+        STEP_ADD_WORKTREE => match step.detail(DETAIL_PATH) {
+            // マッピングで作成した未追跡ファイルが残っていても削除できるよう強制する
+            Some(path) if Path::new(path).exists() => git.remove_worktree(Path::new(path), true),
+            Some(_) => git.prune_worktrees(false).map(|_| ()),
+            None => Ok(()),
+        },
+        // 作成直後のブランチなので、マージされていなくても削除する
+        STEP_CREATE_BRANCH => match step.detail(DETAIL_BRANCH) {
+            Some(branch) => git.delete_branch(branch, true),
+            None => Ok(()),
+        },
+        // 削除したシンボリックリンクを作成し直す（削除後に置かれたファイルは上書きしない）
+        STEP_REMOVE_MAPPING => match (step.detail(DETAIL_SOURCE), step.detail(DETAIL_TARGET)) {
+            (Some(_), Some(target)) if Path::new(target).is_symlink() => Ok(()),
+            (Some(_), Some(target)) if Path::new(target).exists() => Err(TwinError::symlink(
+                format!("Refusing to overwrite {target}: it is not a symlink"),
+                Some(PathBuf::from(target)),
+            )),
+            (Some(source), Some(target)) => create_symlink_manager()
+                .create_symlink(Path::new(source), Path::new(target))
+                .map(|_| ()),
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// マッピングで作成したリンクまたはファイルを削除
+fn remove_mapping_target(target: &Path) -> TwinResult<()> {
+    if target.is_symlink() || target.is_file() {
+        fs::remove_file(target)?;
+    } else if target.is_dir() {
+        fs::remove_dir_all(target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::OperationType;
+    use crate::git_backend::FakeBackend;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rollback_undoes_steps_in_reverse() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        let worktree = temp.path().join("wt");
+        let mut git = GitManager::with_backend(&repo, Box::new(FakeBackend::new(&repo)));
+        git.add_worktree_with_options(&["-b", "feature/rollback", &worktree.to_string_lossy()])
+            .unwrap();
+        let target = worktree.join(".env");
+        fs::write(&target, "A=1").unwrap();
+
+        let mut state = PartialFailureState::new(OperationType::CreateEnvironment);
+        state
+            .record(OperationStep::new(STEP_PRE_CREATE, false).with_detail(DETAIL_COMMAND, "echo"));
+        state.record(
+            OperationStep::new(STEP_CREATE_BRANCH, true)
+                .with_detail(DETAIL_BRANCH, "feature/rollback"),
+        );
+        state.record(
+            OperationStep::new(STEP_ADD_WORKTREE, true)
+                .with_detail(DETAIL_PATH, worktree.to_string_lossy()),
+        );
+        state.record(
+            OperationStep::new(STEP_APPLY_MAPPING, true)
+                .with_detail(DETAIL_MAPPING_TYPE, "copy")
+                .with_detail(DETAIL_SOURCE, ".env")
+                .with_detail(DETAIL_TARGET, target.to_string_lossy()),
+        );
+        state.fail(OperationStep::new(STEP_POST_CREATE, false), "hook failed");
+
+        let report = rollback(&state, &mut git);
+
+        assert!(report.is_clean(), "{:?}", report.failed);
+        assert_eq!(report.undone.len(), 3);
+        assert!(report.undone[0].starts_with("copy"));
+        assert!(report.undone[2].contains("feature/rollback"));
+        assert_eq!(report.not_undoable, vec!["pre_create hook: echo"]);
+        assert!(!worktree.exists());
+        assert!(!git.branch_exists("feature/rollback").unwrap());
+    }
+
+    #[test]
+    fn test_rollback_recreates_removed_symlinks() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        let source = temp.path().join(".env");
+        let target = temp.path().join("wt/.env");
+        fs::write(&source, "A=1").unwrap();
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+
+        let mut state = PartialFailureState::new(OperationType::RemoveEnvironment);
+        state.record(
+            OperationStep::new(STEP_REMOVE_MAPPING, true)
+                .with_detail(DETAIL_MAPPING_TYPE, "symlink")
+                .with_detail(DETAIL_SOURCE, source.to_string_lossy())
+                .with_detail(DETAIL_TARGET, target.to_string_lossy()),
+        );
+        state.fail(
+            OperationStep::new(STEP_REMOVE_WORKTREE, false)
+                .with_detail(DETAIL_PATH, temp.path().join("wt").to_string_lossy()),
+            "worktree contains modified files",
+        );
+
+        let mut git = GitManager::with_backend(&repo, Box::new(FakeBackend::new(&repo)));
+        let report = rollback(&state, &mut git);
+
+        assert!(report.is_clean(), "{:?}", report.failed);
+        assert_eq!(report.undone.len(), 1);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "A=1");
+
+        // 削除後に置かれた通常のファイルは上書きしない
+        fs::remove_file(&target).unwrap();
+        fs::write(&target, "user edits").unwrap();
+        let report = rollback(&state, &mut git);
+        assert_eq!(report.failed.len(), 1);
+        assert!(!target.is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "user edits");
+    }
+}
@@ -0,0 +1,185 @@
+//! ワークツリー状態モジュール
+//!
+//! このモジュールの役割：
+//! - 各ワークツリーのgitの状態（HEAD、変更数、上流・ベースブランチとの差分、ロック）の収集
+//! - 適用済み（マニフェストに記録された）ファイルマッピングと、未適用の設定のマッピングの状態の確認
+//! - `twin status`で出力するデータ構造の定義
+
+#![allow(dead_code)]
+use crate::core::{Config, TwinResult};
+use crate::git::{GitManager, WorktreeInfo};
+use crate::manifest::Manifest;
+use crate::mapping::{check_mapping, MappingHealth};
+use crate::symlink::SymlinkManager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// ブランチとの差分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    /// 比較対象のブランチ名
+    pub branch: String,
+    /// 進んでいるコミット数
+    pub ahead: usize,
+    /// 遅れているコミット数
+    pub behind: usize,
+}
+
+/// マッピング1件の状態
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MappingStatus {
+    /// マッピングタイプ
+    pub mapping_type: String,
+    /// ソースのパス（絶対パス）
+    pub source: PathBuf,
+    /// ターゲットのパス（絶対パス）
+    pub target: PathBuf,
+    /// 状態
+    pub health: MappingHealth,
+}
+
+/// ワークツリー1件の状態
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorktreeStatus {
+    /// Worktreeのパス
+    pub path: PathBuf,
+    /// ブランチ名（detached HEADの場合はNone）
+    pub branch: Option<String>,
+    /// HEADのコミットハッシュ
+    pub head: String,
+    /// メインのワークツリーか
+    pub is_main: bool,
+    /// ロック状態
+    pub locked: bool,
+    /// プルーニング可能か（ディレクトリが存在しない）
+    pub prunable: bool,
+    /// `.git`ファイルのリンクが壊れているか（`git worktree repair`が必要）
+    pub git_link_broken: bool,
+    /// 変更されたファイル数
+    pub changed: usize,
+    /// 未追跡ファイル数
+    pub untracked: usize,
+    /// 上流ブランチとの差分
+    pub upstream: Option<Divergence>,
+    /// ベースブランチとの差分
+    pub base: Option<Divergence>,
+    /// ファイルマッピングの状態（メインのワークツリーでは空）
+    pub mappings: Vec<MappingStatus>,
+    /// 副作用の適用後に設定が変更されているか（`twin sync`で反映できる）
+    #[serde(default)]
+    pub config_outdated: bool,
+}
+
+impl WorktreeStatus {
+    /// 未コミットの変更または未追跡ファイルがあるか
+    pub fn is_dirty(&self) -> bool {
+        self.changed > 0 || self.untracked > 0
+    }
+
+    /// 正常でないマッピングの数
+    pub fn unhealthy_mappings(&self) -> usize {
+        self.mappings
+            .iter()
+            .filter(|mapping| !mapping.health.is_valid())
+            .count()
+    }
+}
+
+/// ワークツリーの状態を収集する
+///
+/// `base`にはahead/behindを比較するブランチ（通常はメインのワークツリーのブランチ）を指定する。
+/// `manifest`がある場合は記録された適用済みのマッピングを確認し、
+/// 設定にあって記録にないマッピングは未適用のものとして確認する。
+#[allow(clippy::too_many_arguments)]
+pub fn collect_status(
+    git: &mut GitManager,
+    worktree: &WorktreeInfo,
+    is_main: bool,
+    base: Option<&str>,
+    config: &Config,
+    project_root: &Path,
+    manifest: Option<&Manifest>,
+    symlink_manager: &dyn SymlinkManager,
+) -> TwinResult<WorktreeStatus> {
+    let branch = worktree
+        .branch
+        .strip_prefix("refs/heads/")
+        .map(String::from);
+    let mut status = WorktreeStatus {
+        path: worktree.path.clone(),
+        branch: branch.clone(),
+        head: worktree.commit.clone(),
+        is_main,
+        locked: worktree.locked,
+        prunable: worktree.prunable,
+        git_link_broken: false,
+        changed: 0,
+        untracked: 0,
+        upstream: None,
+        base: None,
+        mappings: Vec::new(),
+        config_outdated: manifest.is_some_and(|manifest| manifest.is_outdated(&config.settings)),
+    };
+
+    // ディレクトリが存在しないワークツリーはgitの状態を取得できない
+    if worktree.prunable || !worktree.path.exists() {
+        return Ok(status);
+    }
+    status.git_link_broken = git.is_worktree_link_broken(&worktree.path);
+
+    let git_status = if status.git_link_broken {
+        Default::default()
+    } else {
+        git.get_worktree_status(&worktree.path)?
+    };
+    status.changed = git_status.changed;
+    status.untracked = git_status.untracked;
+    if let Some(upstream) = git_status.upstream {
+        status.upstream = Some(Divergence {
+            branch: upstream,
+            ahead: git_status.ahead,
+            behind: git_status.behind,
+        });
+    }
+
+    if let Some(base) =
+        base.filter(|base| !status.git_link_broken && branch.as_deref() != Some(*base))
+    {
+        if let Ok((ahead, behind)) = git.ahead_behind(&worktree.path, base) {
+            status.base = Some(Divergence {
+                branch: base.to_string(),
+                ahead,
+                behind,
+            });
+        }
+    }
+
+    // マッピングのソースはメインのワークツリーにあるため、メインでは確認しない
+    if !is_main {
+        let applied = manifest
+            .map(|manifest| manifest.mappings.as_slice())
+            .unwrap_or_default();
+        let pending = config
+            .settings
+            .files
+            .iter()
+            .filter(|mapping| manifest.is_none_or(|m| m.find(mapping.target_path()).is_none()))
+            .map(|mapping| (mapping.clone(), project_root.join(&mapping.source)));
+        let mappings = applied
+            .iter()
+            .map(|applied| (applied.to_file_mapping(), applied.source.clone()))
+            .chain(pending);
+
+        for (mapping, source) in mappings {
+            let target = worktree.path.join(mapping.target_path());
+            status.mappings.push(MappingStatus {
+                mapping_type: mapping.mapping_type.as_str().to_string(),
+                health: check_mapping(&mapping, &source, &target, symlink_manager)?,
+                source,
+                target,
+            });
+        }
+    }
+
+    Ok(status)
+}
@@ -19,8 +19,7 @@ pub trait SymlinkManager {
     /// シンボリックリンクを削除
     fn remove_symlink(&self, path: &Path) -> TwinResult<()>;
 
-    /// シンボリックリンクを検証
-    #[allow(dead_code)]
+    /// シンボリックリンクを検証（リンクであり、リンク先が存在する場合にtrue）
     fn validate_symlink(&self, path: &Path) -> TwinResult<bool>;
 
     /// 手動作成方法の説明を取得
@@ -129,7 +128,6 @@ impl SymlinkManager for UnixSymlinkManager {
         Ok(())
     }
 
-    #[allow(dead_code)]
     fn validate_symlink(&self, path: &Path) -> TwinResult<bool> {
         if !path.exists() {
             return Ok(false);
@@ -325,7 +323,6 @@ impl SymlinkManager for WindowsSymlinkManager {
         Ok(())
     }
 
-    #[allow(dead_code)]
     fn validate_symlink(&self, path: &Path) -> TwinResult<bool> {
         if !path.exists() {
             return Ok(false);
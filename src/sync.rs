@@ -0,0 +1,398 @@
+//! 設定同期モジュール
+//!
+//! このモジュールの役割：
+//! - 現在の設定のマッピングと既存のワークツリーに適用済みの状態の差分の計算
+//! - 不足しているマッピングの適用と、壊れたリンクの作り直し
+//! - 設定から削除されたマッピングのリンクの削除（`--prune`）
+//! - 同期後の状態のマニフェストへの記録
+
+#![allow(dead_code)]
+use crate::core::{Config, MappingType, TwinResult};
+use crate::manifest::{config_hash, AppliedMapping, Manifest};
+use crate::mapping::{check_mapping, repair_mapping, MappingHealth, MappingOutcome};
+use crate::symlink::SymlinkManager;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 同期で行う変更
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncAction {
+    /// 不足しているマッピングを適用する（`mapping`は設定の`files`のインデックス）
+    Create { mapping: usize, target: PathBuf },
+    /// 壊れている、または異なるソースを指しているリンクを作り直す
+    Relink { mapping: usize, target: PathBuf },
+    /// 設定から削除されたマッピングのリンクを削除する
+    Remove { target: PathBuf },
+}
+
+impl SyncAction {
+    /// 対象のパス
+    pub fn target(&self) -> &Path {
+        match self {
+            SyncAction::Create { target, .. }
+            | SyncAction::Relink { target, .. }
+            | SyncAction::Remove { target } => target,
+        }
+    }
+}
+
+/// ワークツリー1件の同期計画
+#[derive(Debug, Clone, Default)]
+pub struct SyncPlan {
+    /// 対象のワークツリーのパス
+    pub worktree: PathBuf,
+    /// 行う変更
+    pub actions: Vec<SyncAction>,
+    /// ソースが存在しないため適用できないマッピング（設定の`files`のインデックスとソース）
+    pub missing_sources: Vec<(usize, PathBuf)>,
+}
+
+impl SyncPlan {
+    /// 変更がないか
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// 同期計画を作成する
+///
+/// `prune`が有効な場合、`manifest`に記録されているが現在の設定にないシンボリックリンクを削除対象にする。
+/// マニフェストがない（記録を始める前に作成された）ワークツリーでは、`untracked`に渡された
+/// 未追跡・無視されたパス（ワークツリーからの相対パス）のうち、プロジェクトルートを指しているリンクを対象にする。
+pub fn plan_sync(
+    worktree: &Path,
+    project_root: &Path,
+    config: &Config,
+    manifest: Option<&Manifest>,
+    untracked: &[PathBuf],
+    prune: bool,
+    symlink_manager: &dyn SymlinkManager,
+) -> TwinResult<SyncPlan> {
+    let mut plan = SyncPlan {
+        worktree: worktree.to_path_buf(),
+        ..Default::default()
+    };
+
+    let mut targets = Vec::with_capacity(config.settings.files.len());
+    for (index, mapping) in config.settings.files.iter().enumerate() {
+        let source = project_root.join(&mapping.source);
+        let target = worktree.join(mapping.target_path());
+        targets.push(target.clone());
+
+        let health = check_mapping(mapping, &source, &target, symlink_manager)?;
+        if matches!(health, MappingHealth::Valid | MappingHealth::ReplacedByFile) {
+            continue;
+        }
+        if !source.exists() {
+            plan.missing_sources.push((index, source));
+            continue;
+        }
+
+        plan.actions.push(match health {
+            MappingHealth::Missing => SyncAction::Create {
+                mapping: index,
+                target,
+            },
+            _ => SyncAction::Relink {
+                mapping: index,
+                target,
+            },
+        });
+    }
+
+    if !prune {
+        return Ok(plan);
+    }
+
+    let stale: Vec<PathBuf> = match manifest {
+        Some(manifest) => manifest
+            .mappings
+            .iter()
+            .filter(|applied| applied.mapping_type == MappingType::Symlink)
+            .map(|applied| worktree.join(&applied.target))
+            .collect(),
+        None => {
+            let project_root = fs::canonicalize(project_root).unwrap_or(project_root.to_path_buf());
+            untracked
+                .iter()
+                .map(|relative| worktree.join(relative))
+                .filter(|path| links_into(path, &project_root))
+                .collect()
+        }
+    };
+    for path in stale {
+        if path.is_symlink() && !targets.contains(&path) {
+            plan.actions.push(SyncAction::Remove { target: path });
+        }
+    }
+
+    Ok(plan)
+}
+
+/// 同期計画を適用する
+///
+/// 1件の失敗で中断せず、適用できなかった変更とエラーを返す。
+pub fn apply_sync(
+    plan: &SyncPlan,
+    project_root: &Path,
+    config: &Config,
+    symlink_manager: &dyn SymlinkManager,
+    variables: &HashMap<String, String>,
+) -> Vec<(SyncAction, String)> {
+    let mut failures = Vec::new();
+
+    for action in &plan.actions {
+        let result = match action {
+            SyncAction::Create { mapping, target } | SyncAction::Relink { mapping, target } => {
+                let mapping = &config.settings.files[*mapping];
+                repair_mapping(
+                    mapping,
+                    &project_root.join(&mapping.source),
+                    target,
+                    symlink_manager,
+                    variables,
+                )
+                .map(|_: Option<MappingOutcome>| ())
+            }
+            SyncAction::Remove { target } => symlink_manager.remove_symlink(target),
+        };
+
+        if let Err(e) = result {
+            failures.push((action.clone(), e.to_string()));
+        }
+    }
+
+    failures
+}
+
+/// 同期後の状態をマニフェストに記録する
+///
+/// 現在の設定のうち正常に適用されているマッピングを記録し、削除したリンクの記録を削除する。
+/// 既に記録されている同じマッピングは、作成時のチェックサムを保持するためそのままにする。
+pub fn record_sync(
+    manifest: Option<Manifest>,
+    worktree: &Path,
+    branch: &str,
+    project_root: &Path,
+    config: &Config,
+    symlink_manager: &dyn SymlinkManager,
+) -> TwinResult<Manifest> {
+    let mut manifest =
+        manifest.unwrap_or_else(|| Manifest::new(worktree, branch, &config.settings));
+
+    let removed: Vec<PathBuf> = manifest
+        .mappings
+        .iter()
+        .filter(|applied| {
+            let target = worktree.join(&applied.target);
+            !target.exists() && !target.is_symlink()
+        })
+        .map(|applied| applied.target.clone())
+        .collect();
+    for target in removed {
+        manifest.forget_mapping(&target);
+    }
+
+    for mapping in &config.settings.files {
+        let source = project_root.join(&mapping.source);
+        let target = worktree.join(mapping.target_path());
+        if !check_mapping(mapping, &source, &target, symlink_manager)?.is_valid() {
+            continue;
+        }
+        let unchanged = manifest.find(mapping.target_path()).is_some_and(|applied| {
+            applied.mapping_type == mapping.mapping_type && applied.source == source
+        });
+        if !unchanged {
+            manifest.record_mapping(AppliedMapping::new(
+                mapping.mapping_type.clone(),
+                &source,
+                mapping.target_path(),
+                worktree,
+            ));
+        }
+    }
+
+    manifest.config_hash = config_hash(&config.settings);
+    manifest.updated_at = chrono::Utc::now();
+    Ok(manifest)
+}
+
+/// シンボリックリンクが指定したディレクトリ配下を指しているか
+fn links_into(link: &Path, dir: &Path) -> bool {
+    let Ok(destination) = fs::read_link(link) else {
+        return false;
+    };
+    let destination = match link.parent() {
+        Some(parent) if destination.is_relative() => parent.join(destination),
+        _ => destination,
+    };
+    // リンク切れでもパスで判定できるよう、正規化できない場合はそのまま比較する
+    fs::canonicalize(&destination)
+        .unwrap_or(destination)
+        .starts_with(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileMapping, MappingType};
+    use crate::symlink::create_symlink_manager;
+    use tempfile::TempDir;
+
+    fn config(sources: &[&str]) -> Config {
+        let mut config = Config::new();
+        config.settings.files = sources
+            .iter()
+            .map(|source| FileMapping {
+                source: PathBuf::from(source),
+                target: None,
+                mapping_type: MappingType::Symlink,
+                description: None,
+                skip_if_exists: false,
+                variables: HashMap::new(),
+            })
+            .collect();
+        config
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sync_prunes_links_recorded_in_manifest() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("repo");
+        let worktree = temp.path().join("wt");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&worktree).unwrap();
+        for name in [".env", ".tool", ".old"] {
+            fs::write(project.join(name), name).unwrap();
+        }
+        let manager = create_symlink_manager();
+        let old_config = config(&[".env", ".old"]);
+        let mut manifest = Manifest::new(&worktree, "feature", &old_config.settings);
+        for name in [".env", ".old"] {
+            manager
+                .create_symlink(&project.join(name), &worktree.join(name))
+                .unwrap();
+            manifest.record_mapping(AppliedMapping::new(
+                MappingType::Symlink,
+                &project.join(name),
+                Path::new(name),
+                &worktree,
+            ));
+        }
+
+        let config = config(&[".env", ".tool"]);
+        assert!(manifest.is_outdated(&config.settings));
+        let plan = plan_sync(
+            &worktree,
+            &project,
+            &config,
+            Some(&manifest),
+            &[],
+            true,
+            manager.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            plan.actions,
+            vec![
+                SyncAction::Create {
+                    mapping: 1,
+                    target: worktree.join(".tool")
+                },
+                SyncAction::Remove {
+                    target: worktree.join(".old")
+                },
+            ]
+        );
+
+        let failures = apply_sync(&plan, &project, &config, manager.as_ref(), &HashMap::new());
+        assert!(failures.is_empty(), "{failures:?}");
+        let manifest = record_sync(
+            Some(manifest),
+            &worktree,
+            "feature",
+            &project,
+            &config,
+            manager.as_ref(),
+        )
+        .unwrap();
+        let targets: Vec<_> = manifest.mappings.iter().map(|m| m.target.clone()).collect();
+        assert_eq!(targets, vec![PathBuf::from(".env"), PathBuf::from(".tool")]);
+        assert!(!manifest.is_outdated(&config.settings));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sync_creates_missing_and_prunes_dropped_links() {
+        let temp = TempDir::new().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let project = root.join("repo");
+        let worktree = root.join("wt");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&worktree).unwrap();
+        for name in [".env", ".tool", ".old"] {
+            fs::write(project.join(name), name).unwrap();
+        }
+        let manager = create_symlink_manager();
+        manager
+            .create_symlink(&project.join(".env"), &worktree.join(".env"))
+            .unwrap();
+        manager
+            .create_symlink(&project.join(".old"), &worktree.join(".old"))
+            .unwrap();
+        // プロジェクト外を指すユーザーのリンクは削除しない
+        std::os::unix::fs::symlink("/tmp", worktree.join("scratch")).unwrap();
+
+        let config = config(&[".env", ".tool", ".missing"]);
+        let untracked = vec![
+            PathBuf::from(".env"),
+            PathBuf::from(".old"),
+            PathBuf::from("scratch"),
+        ];
+        let plan = plan_sync(
+            &worktree,
+            &project,
+            &config,
+            None,
+            &untracked,
+            true,
+            manager.as_ref(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan.actions,
+            vec![
+                SyncAction::Create {
+                    mapping: 1,
+                    target: worktree.join(".tool")
+                },
+                SyncAction::Remove {
+                    target: worktree.join(".old")
+                },
+            ]
+        );
+        assert_eq!(plan.missing_sources, vec![(2, project.join(".missing"))]);
+
+        let failures = apply_sync(&plan, &project, &config, manager.as_ref(), &HashMap::new());
+        assert!(failures.is_empty(), "{failures:?}");
+        assert_eq!(fs::read_to_string(worktree.join(".tool")).unwrap(), ".tool");
+        assert!(!worktree.join(".old").is_symlink());
+        assert!(worktree.join("scratch").is_symlink());
+
+        // 同期後は変更がない
+        let plan = plan_sync(
+            &worktree,
+            &project,
+            &config,
+            None,
+            &untracked,
+            true,
+            manager.as_ref(),
+        )
+        .unwrap();
+        assert!(plan.is_empty());
+    }
+}
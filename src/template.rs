@@ -0,0 +1,239 @@
+//! テンプレート処理モジュール
+//!
+//! このモジュールの役割：
+//! - `{{変数名}}` 形式のプレースホルダーの展開
+//! - フックコンテキストからのテンプレート変数の構築
+//! - `{{env.NAME}}` による環境変数の参照
+//! - 未定義のプレースホルダーのエラー報告
+
+#![allow(dead_code)]
+use crate::core::{TwinError, TwinResult};
+use crate::hooks::HookContext;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 環境変数を参照するプレースホルダーの接頭辞
+const ENV_PREFIX: &str = "env.";
+
+/// フックコンテキストからテンプレート変数を構築
+///
+/// `agent_name`、`branch`、`worktree_path`、`project_root`などの標準の変数に加え、
+/// コンテキストの追加環境変数を`env.NAME`として参照できるようにする。
+pub fn context_variables(context: &HookContext) -> HashMap<String, String> {
+    let mut variables: HashMap<String, String> = context
+        .variables()
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+
+    for (key, value) in &context.env_vars {
+        variables.insert(format!("{ENV_PREFIX}{key}"), value.clone());
+    }
+
+    variables
+}
+
+/// マッピング固有の変数を追加する
+///
+/// 変数の値にもプレースホルダーを使用でき、コンテキストの変数で展開される。
+pub fn with_mapping_variables(
+    mut variables: HashMap<String, String>,
+    mapping_variables: &HashMap<String, String>,
+) -> TwinResult<HashMap<String, String>> {
+    let mut rendered = Vec::with_capacity(mapping_variables.len());
+    for (key, value) in mapping_variables {
+        rendered.push((key.clone(), render(value, &variables)?));
+    }
+    variables.extend(rendered);
+    Ok(variables)
+}
+
+/// テンプレート文字列を展開する
+///
+/// 未定義のプレースホルダーがある場合は、すべての名前を含むエラーを返す。
+pub fn render(template: &str, variables: &HashMap<String, String>) -> TwinResult<String> {
+    render_with_env(template, variables, |key| std::env::var(key).ok())
+}
+
+/// テンプレート文字列を展開する（`env.NAME`の参照先を`env`で指定する）
+pub fn render_with_env(
+    template: &str,
+    variables: &HashMap<String, String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> TwinResult<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut unknown = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            return Err(TwinError::config(
+                format!("Unclosed placeholder in template: {}", &rest[start..]),
+                None,
+            ));
+        };
+
+        let name = after[..end].trim();
+        match lookup(name, variables, &env) {
+            Some(value) => output.push_str(&value),
+            None => {
+                if !unknown.iter().any(|n| n == name) {
+                    unknown.push(name.to_string());
+                }
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+
+    if !unknown.is_empty() {
+        return Err(TwinError::config(
+            format!("Unknown template variable(s): {}", unknown.join(", ")),
+            None,
+        ));
+    }
+
+    Ok(output)
+}
+
+/// テンプレートファイルを展開して書き出す
+pub fn render_file(
+    source: &Path,
+    target: &Path,
+    variables: &HashMap<String, String>,
+) -> TwinResult<()> {
+    let content = render_source(source, variables)?;
+
+    // 既存のシンボリックリンクを経由してソースを書き換えないよう先に削除する
+    if target.is_symlink() {
+        std::fs::remove_file(target)?;
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(target, content)?;
+
+    // 実行権限などはテンプレートのものを引き継ぐ
+    std::fs::set_permissions(target, std::fs::metadata(source)?.permissions())?;
+    Ok(())
+}
+
+/// テンプレートファイルを読み込んで展開した内容を返す
+pub fn render_source(source: &Path, variables: &HashMap<String, String>) -> TwinResult<String> {
+    if source.is_dir() {
+        return Err(TwinError::config(
+            "Template mapping source must be a file",
+            Some(source.to_path_buf()),
+        ));
+    }
+
+    let template = std::fs::read_to_string(source).map_err(|e| {
+        TwinError::io(
+            format!("Failed to read template {}: {e}", source.display()),
+            Some(source.to_path_buf()),
+        )
+    })?;
+
+    render(&template, variables).map_err(|e| match e {
+        TwinError::Config { message, .. } => TwinError::config(message, Some(source.to_path_buf())),
+        other => other,
+    })
+}
+
+/// 変数を検索（`env.NAME`は未定義なら`env`で環境変数を参照）
+fn lookup(
+    name: &str,
+    variables: &HashMap<String, String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    if let Some(value) = variables.get(name) {
+        return Some(value.clone());
+    }
+
+    name.strip_prefix(ENV_PREFIX).and_then(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context() -> HookContext {
+        let mut context = HookContext::new("agent-1", "/work/agent-1", "feature/x", "/repo");
+        context.add_env_var("DB_PORT", "5433");
+        context
+    }
+
+    #[test]
+    fn test_render_context_variables() {
+        let variables = context_variables(&context());
+        let rendered = render(
+            "BRANCH={{branch}}\nPATH={{ worktree_path }}\nPORT={{env.DB_PORT}}\n",
+            &variables,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rendered,
+            "BRANCH=feature/x\nPATH=/work/agent-1\nPORT=5433\n"
+        );
+    }
+
+    #[test]
+    fn test_render_process_env() {
+        let env = |key: &str| (key == "TWIN_TEMPLATE_TEST_VAR").then(|| "from-env".to_string());
+        let rendered =
+            render_with_env("{{env.TWIN_TEMPLATE_TEST_VAR}}", &HashMap::new(), env).unwrap();
+        assert_eq!(rendered, "from-env");
+        assert!(render_with_env("{{env.OTHER}}", &HashMap::new(), env).is_err());
+    }
+
+    #[test]
+    fn test_unknown_placeholders_are_errors() {
+        let variables = context_variables(&context());
+        let err = render(
+            "{{branch}} {{missing}} {{env.TWIN_UNDEFINED_VAR}}",
+            &variables,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("missing"));
+        assert!(err.contains("env.TWIN_UNDEFINED_VAR"));
+    }
+
+    #[test]
+    fn test_unclosed_placeholder_is_error() {
+        assert!(render("value = {{branch", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_mapping_variables_can_reference_context() {
+        let mut mapping_variables = HashMap::new();
+        mapping_variables.insert("db_name".to_string(), "app_{{agent_name}}".to_string());
+
+        let variables =
+            with_mapping_variables(context_variables(&context()), &mapping_variables).unwrap();
+        assert_eq!(
+            render("DATABASE={{db_name}}", &variables).unwrap(),
+            "DATABASE=app_agent-1"
+        );
+    }
+
+    #[test]
+    fn test_render_file() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join(".env.template");
+        let target = temp.path().join("wt/.env");
+        std::fs::write(&source, "BRANCH={{branch}}\n").unwrap();
+
+        render_file(&source, &target, &context_variables(&context())).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&target).unwrap(),
+            "BRANCH=feature/x\n"
+        );
+    }
+}
@@ -7,9 +7,805 @@
 /// - キーボード操作での環境管理
 /// - リアルタイムステータス表示
 use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use crate::cli::commands::{handle_add, handle_remove};
+use crate::cli::{AddArgs, RemoveArgs};
+use crate::git::{GitManager, WorktreeInfo};
+use crate::hook_log::HookLog;
+use crate::hooks::{HookContext, HookExecutor, HookType};
+
+/// キー入力を待つ間隔
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// 一覧を自動更新する間隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 一覧に表示するWorktreeの情報
+#[derive(Debug, Clone)]
+pub struct WorktreeEntry {
+    /// git worktree listから取得した情報
+    pub info: WorktreeInfo,
+    /// 未コミット変更の件数（取得できなかった場合はNone）
+    pub changes: Option<usize>,
+    /// メインのWorktreeかどうか
+    pub is_main: bool,
+}
+
+/// TUIの入力モード
+#[derive(Debug, Clone, PartialEq)]
+pub enum TuiMode {
+    /// 一覧の操作
+    List,
+    /// 新しいWorktreeのブランチ名を入力中
+    Input(String),
+    /// 削除の確認中
+    ConfirmRemove,
+    /// 実行するフックの種類を選択中
+    SelectHook,
+}
+
+/// キー入力の結果として実行するアクション
+#[derive(Debug, Clone, PartialEq)]
+pub enum TuiAction {
+    /// TUIを終了
+    Quit,
+    /// 一覧を再読み込み
+    Refresh,
+    /// Worktreeを追加
+    Add(String),
+    /// Worktreeを削除
+    Remove(PathBuf),
+    /// ロック状態を切り替え（trueならロックする）
+    SetLock(PathBuf, bool),
+    /// Worktreeでシェルを開く
+    OpenShell(PathBuf),
+    /// Worktreeに対してフックを実行
+    RunHooks(PathBuf, HookType),
+}
+
+/// TUIアプリケーションの状態
+pub struct TuiApp {
+    /// 表示中のWorktree一覧
+    worktrees: Vec<WorktreeEntry>,
+    /// 選択中の行
+    selected: usize,
+    /// 現在の入力モード
+    mode: TuiMode,
+    /// ステータスバーに表示するメッセージ
+    message: Option<String>,
+}
+
+impl Default for TuiApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuiApp {
+    /// 空の状態でアプリケーションを作成
+    pub fn new() -> Self {
+        Self {
+            worktrees: Vec::new(),
+            selected: 0,
+            mode: TuiMode::List,
+            message: None,
+        }
+    }
+
+    /// Worktree一覧を置き換える（選択位置は範囲内に補正）
+    pub fn set_worktrees(&mut self, worktrees: Vec<WorktreeEntry>) {
+        self.worktrees = worktrees;
+        if self.selected >= self.worktrees.len() {
+            self.selected = self.worktrees.len().saturating_sub(1);
+        }
+    }
+
+    /// 選択中のWorktreeを取得
+    pub fn selected(&self) -> Option<&WorktreeEntry> {
+        self.worktrees.get(self.selected)
+    }
+
+    /// 現在の入力モードを取得
+    pub fn mode(&self) -> &TuiMode {
+        &self.mode
+    }
+
+    /// ステータスメッセージを設定
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = Some(message.into());
+    }
+
+    /// キー入力を処理し、必要であれば実行するアクションを返す
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<TuiAction> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(TuiAction::Quit);
+        }
+
+        match self.mode.clone() {
+            TuiMode::List => self.handle_list_key(key),
+            TuiMode::Input(buffer) => self.handle_input_key(key, buffer),
+            TuiMode::ConfirmRemove => self.handle_confirm_key(key),
+            TuiMode::SelectHook => self.handle_hook_key(key),
+        }
+    }
+
+    fn handle_list_key(&mut self, key: KeyEvent) -> Option<TuiAction> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(TuiAction::Quit),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.select_next();
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.select_previous();
+                None
+            }
+            KeyCode::Char('r') => Some(TuiAction::Refresh),
+            KeyCode::Char('a') => {
+                self.mode = TuiMode::Input(String::new());
+                None
+            }
+            KeyCode::Char('d') => {
+                match self.selected() {
+                    Some(entry) if entry.is_main => {
+                        self.set_message("メインのWorktreeは削除できません");
+                    }
+                    Some(_) => self.mode = TuiMode::ConfirmRemove,
+                    None => {}
+                }
+                None
+            }
+            KeyCode::Char('l') => self
+                .selected()
+                .map(|entry| TuiAction::SetLock(entry.info.path.clone(), !entry.info.locked)),
+            KeyCode::Char('s') | KeyCode::Enter => self
+                .selected()
+                .filter(|entry| entry.info.path.exists())
+                .map(|entry| TuiAction::OpenShell(entry.info.path.clone())),
+            KeyCode::Char('h') => {
+                if self.selected().is_some() {
+                    self.mode = TuiMode::SelectHook;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent, mut buffer: String) -> Option<TuiAction> {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = TuiMode::List;
+                None
+            }
+            KeyCode::Enter => {
+                self.mode = TuiMode::List;
+                let branch = buffer.trim().to_string();
+                if branch.is_empty() {
+                    None
+                } else {
+                    Some(TuiAction::Add(branch))
+                }
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+                self.mode = TuiMode::Input(buffer);
+                None
+            }
+            KeyCode::Char(c) => {
+                buffer.push(c);
+                self.mode = TuiMode::Input(buffer);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_confirm_key(&mut self, key: KeyEvent) -> Option<TuiAction> {
+        self.mode = TuiMode::List;
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => self
+                .selected()
+                .map(|entry| TuiAction::Remove(entry.info.path.clone())),
+            _ => {
+                self.set_message("削除をキャンセルしました");
+                None
+            }
+        }
+    }
+
+    fn handle_hook_key(&mut self, key: KeyEvent) -> Option<TuiAction> {
+        self.mode = TuiMode::List;
+        let hook_type = match key.code {
+            KeyCode::Char('1') => HookType::PreCreate,
+            KeyCode::Char('2') => HookType::PostCreate,
+            KeyCode::Char('3') => HookType::PreRemove,
+            KeyCode::Char('4') => HookType::PostRemove,
+            _ => return None,
+        };
+        self.selected()
+            .map(|entry| TuiAction::RunHooks(entry.info.path.clone(), hook_type))
+    }
+
+    fn select_next(&mut self) {
+        if !self.worktrees.is_empty() {
+            self.selected = (self.selected + 1) % self.worktrees.len();
+        }
+    }
+
+    fn select_previous(&mut self) {
+        if !self.worktrees.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.worktrees.len() - 1);
+        }
+    }
+}
+
+/// TUIを起動
 pub async fn run_tui() -> Result<()> {
-    // TODO: TUI実装
-    println!("TUI mode is not implemented yet");
+    let config = crate::config::Config::load(None).await?;
+    let mut git = GitManager::new(Path::new("."))?;
+    git.set_branch_template(crate::branch_name::BranchTemplate::for_listing(
+        &config.settings,
+    )?);
+    let mut app = TuiApp::new();
+    app.set_worktrees(load_worktrees(&mut git)?);
+
+    let mut terminal = ratatui::init();
+    let result = run_event_loop(&mut terminal, &mut app, &mut git).await;
+    ratatui::restore();
+
+    result
+}
+
+/// メインのイベントループ
+async fn run_event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut TuiApp,
+    git: &mut GitManager,
+) -> Result<()> {
+    let mut last_refresh = Instant::now();
+
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if event::poll(TICK_RATE)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match app.handle_key(key) {
+                    Some(TuiAction::Quit) => return Ok(()),
+                    Some(action) => {
+                        perform_action(terminal, app, git, action).await?;
+                        refresh(app, git);
+                        last_refresh = Instant::now();
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        if last_refresh.elapsed() >= REFRESH_INTERVAL {
+            refresh(app, git);
+            last_refresh = Instant::now();
+        }
+    }
+}
+
+/// リポジトリ単位のロックを取得する（TUIの表示を崩さないよう、他のプロセスが保持している場合は待たずにエラーにする）
+fn lock_repository(git: &mut GitManager) -> crate::core::TwinResult<crate::utils::FileLock> {
+    let lock = crate::utils::FileLock::for_repo(&git.get_twin_dir()?);
+    if !lock.try_acquire()? {
+        return Err(crate::core::TwinError::lock(
+            "The lock is held by another twin process",
+            Some(lock.path().to_path_buf()),
+        ));
+    }
+    Ok(lock)
+}
+
+/// アクションを実行
+async fn perform_action(
+    terminal: &mut DefaultTerminal,
+    app: &mut TuiApp,
+    git: &mut GitManager,
+    action: TuiAction,
+) -> Result<()> {
+    match action {
+        TuiAction::Quit | TuiAction::Refresh => {}
+        TuiAction::SetLock(path, lock) => {
+            let result = lock_repository(git).and_then(|_lock| {
+                if lock {
+                    git.lock_worktree(&path, Some("locked from twin tui"))
+                } else {
+                    git.unlock_worktree(&path)
+                }
+            });
+            match result {
+                Ok(_) if lock => app.set_message(format!("🔒 ロックしました: {}", path.display())),
+                Ok(_) => app.set_message(format!("🔓 ロックを解除しました: {}", path.display())),
+                Err(e) => app.set_message(format!("エラー: {e}")),
+            }
+        }
+        TuiAction::Add(branch) => {
+            suspend();
+            let args = AddArgs {
+                branch: branch.clone(),
+                config: None,
+                ..Default::default()
+            };
+            let result = handle_add(args).await;
+            report(app, result, format!("✓ Worktreeを追加しました: {branch}"));
+            *terminal = resume();
+        }
+        TuiAction::Remove(path) => {
+            suspend();
+            // TUIで確認済みのためプロンプトは省略するが、未コミットの変更があるworktreeはgitに削除を拒否させる
+            let args = RemoveArgs {
+                worktree: Some(path.to_string_lossy().to_string()),
+                yes: true,
+                config: None,
+                ..Default::default()
+            };
+            let result = handle_remove(args).await;
+            report(
+                app,
+                result,
+                format!("✓ Worktreeを削除しました: {}", path.display()),
+            );
+            *terminal = resume();
+        }
+        TuiAction::OpenShell(path) => {
+            suspend();
+            let shell = default_shell();
+            println!(
+                "📂 {} でシェルを開きます（exitでTUIに戻ります）",
+                path.display()
+            );
+            match std::process::Command::new(&shell)
+                .current_dir(&path)
+                .status()
+            {
+                Ok(_) => app.set_message(format!("シェルを終了しました: {}", path.display())),
+                Err(e) => app.set_message(format!("シェルを起動できませんでした ({shell}): {e}")),
+            }
+            *terminal = ratatui::init();
+        }
+        TuiAction::RunHooks(path, hook_type) => {
+            suspend();
+            let result = run_hooks(git, &path, hook_type).await;
+            report(
+                app,
+                result,
+                format!("✓ {} フックを実行しました", hook_type.as_str()),
+            );
+            *terminal = resume();
+        }
+    }
+
+    Ok(())
+}
+
+/// 選択したWorktreeに対して設定済みのフックを実行
+async fn run_hooks(
+    git: &mut GitManager,
+    path: &Path,
+    hook_type: HookType,
+) -> crate::core::TwinResult<()> {
+    let config = crate::config::Config::load(None).await?;
+
+    let hooks = match hook_type {
+        HookType::PreCreate => &config.settings.hooks.pre_create,
+        HookType::PostCreate => &config.settings.hooks.post_create,
+        HookType::PreRemove => &config.settings.hooks.pre_remove,
+        HookType::PostRemove => &config.settings.hooks.post_remove,
+    };
+
+    if hooks.is_empty() {
+        println!("{} フックは設定されていません", hook_type.as_str());
+        return Ok(());
+    }
+
+    let info = git.get_worktree_info(path).ok();
+    let branch = info
+        .as_ref()
+        .map(|info| info.branch.trim_start_matches("refs/heads/").to_string())
+        .unwrap_or_default();
+    let mut context = HookContext::new(
+        branch.clone(),
+        path.to_path_buf(),
+        branch,
+        git.get_repo_path().to_path_buf(),
+    );
+    if let Some(info) = info {
+        context.set_commit(info.commit);
+    }
+    if let Ok(base_branch) = git.get_current_branch() {
+        context.set_base_branch(base_branch);
+    }
+
+    // TUIは一時停止中のため、フックの出力はそのままリアルタイムに表示される
+    let mut executor = HookExecutor::new();
+    executor.set_log(HookLog::for_worktree(&git.get_twin_dir()?, path));
+    for result in executor.execute_hooks(hook_type, hooks, &context)? {
+        let mark = if result.success { "✓" } else { "✗" };
+        println!("{mark} {} ({}ms)", result.command, result.duration_ms);
+    }
+
     Ok(())
 }
+
+/// Worktree一覧を読み込み、変更件数を付与
+fn load_worktrees(git: &mut GitManager) -> Result<Vec<WorktreeEntry>> {
+    let worktrees = git.list_worktrees()?;
+
+    Ok(worktrees
+        .into_iter()
+        .enumerate()
+        .map(|(index, info)| {
+            let changes = if info.prunable || !info.path.exists() {
+                None
+            } else {
+                git.count_changes(&info.path).ok()
+            };
+            WorktreeEntry {
+                info,
+                changes,
+                // git worktree listは常にメインのWorktreeを最初に出力する
+                is_main: index == 0,
+            }
+        })
+        .collect())
+}
+
+/// 一覧を再読み込み（失敗した場合はメッセージとして表示）
+fn refresh(app: &mut TuiApp, git: &mut GitManager) {
+    match load_worktrees(git) {
+        Ok(worktrees) => app.set_worktrees(worktrees),
+        Err(e) => app.set_message(format!("一覧の取得に失敗しました: {e}")),
+    }
+}
+
+/// 使用するシェルを決定
+fn default_shell() -> String {
+    if cfg!(windows) {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd".to_string())
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string())
+    }
+}
+
+/// 外部コマンドの出力を表示するためにTUIを一時停止
+fn suspend() {
+    ratatui::restore();
+}
+
+/// Enterキーの入力を待ってからTUIを再開
+fn resume() -> DefaultTerminal {
+    use std::io::{self, Write};
+
+    print!("\nEnterキーでTUIに戻ります...");
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok();
+
+    ratatui::init()
+}
+
+/// 実行結果をステータスメッセージに反映
+fn report<E: std::fmt::Display>(app: &mut TuiApp, result: Result<(), E>, success: String) {
+    match result {
+        Ok(_) => app.set_message(success),
+        Err(e) => {
+            eprintln!("❌ {e}");
+            app.set_message(format!("エラー: {e}"));
+        }
+    }
+}
+
+/// 画面を描画
+fn draw(frame: &mut Frame, app: &TuiApp) {
+    let [list_area, detail_area, help_area] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_worktree_table(frame, app, list_area);
+    draw_detail(frame, app, detail_area);
+
+    let help = match app.mode {
+        TuiMode::List => {
+            "[a] 追加  [d] 削除  [l] ロック切替  [s] シェル  [h] フック  [r] 更新  [q] 終了"
+        }
+        TuiMode::Input(_) => "[Enter] 作成  [Esc] キャンセル",
+        TuiMode::ConfirmRemove => "[y] 削除  [n] キャンセル",
+        TuiMode::SelectHook => "[1-4] フックを選択  [Esc] キャンセル",
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        help_area,
+    );
+
+    match &app.mode {
+        TuiMode::List => {}
+        TuiMode::Input(buffer) => {
+            let area = centered_rect(frame.area(), 60, 3);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(format!("{buffer}▏"))
+                    .block(Block::bordered().title(" 新しいWorktreeのブランチ名 ")),
+                area,
+            );
+        }
+        TuiMode::ConfirmRemove => {
+            let area = centered_rect(frame.area(), 60, 4);
+            let mut lines = vec![];
+            if let Some(entry) = app.selected() {
+                lines.push(Line::from(format!(
+                    "'{}' を削除しますか？ [y/N]",
+                    entry.info.path.display()
+                )));
+                if let Some(changes) = entry.changes.filter(|c| *c > 0) {
+                    lines.push(Line::from(format!(
+                        "⚠️  {changes} 件の未コミット変更があるため削除できません"
+                    )));
+                }
+            }
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title(" 削除の確認 ")),
+                area,
+            );
+        }
+        TuiMode::SelectHook => {
+            let area = centered_rect(frame.area(), 40, 6);
+            let lines = vec![
+                Line::from("[1] pre_create"),
+                Line::from("[2] post_create"),
+                Line::from("[3] pre_remove"),
+                Line::from("[4] post_remove"),
+            ];
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title(" 実行するフック ")),
+                area,
+            );
+        }
+    }
+}
+
+/// Worktree一覧のテーブルを描画
+fn draw_worktree_table(frame: &mut Frame, app: &TuiApp, area: Rect) {
+    let header = Row::new(["Branch", "Status", "Changes", "Commit", "Path"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = app.worktrees.iter().map(|entry| {
+        let wt = &entry.info;
+        let (status, color) = if wt.locked {
+            ("🔒 locked", Color::Yellow)
+        } else if wt.prunable {
+            ("⚠️  prunable", Color::Red)
+        } else {
+            ("✓ active", Color::Green)
+        };
+        let changes = match entry.changes {
+            Some(0) => "clean".to_string(),
+            Some(n) => format!("{n} dirty"),
+            None => "-".to_string(),
+        };
+        let branch = if wt.branch.is_empty() {
+            "(no branch)".to_string()
+        } else {
+            wt.branch.trim_start_matches("refs/heads/").to_string()
+        };
+        let branch = if entry.is_main {
+            format!("{branch} (main)")
+        } else {
+            branch
+        };
+
+        Row::new(vec![
+            Cell::from(branch),
+            Cell::from(status).style(Style::default().fg(color)),
+            Cell::from(changes),
+            Cell::from(wt.commit[..8.min(wt.commit.len())].to_string()),
+            Cell::from(wt.path.to_string_lossy().to_string()),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(25),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title(format!(" 🌲 Worktrees ({}) ", app.worktrees.len())))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .highlight_symbol("> ");
+
+    let mut state = TableState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// 選択中のWorktreeの詳細とメッセージを描画
+fn draw_detail(frame: &mut Frame, app: &TuiApp, area: Rect) {
+    let text = if let Some(message) = &app.message {
+        message.clone()
+    } else if let Some(entry) = app.selected() {
+        format!(
+            "{}  {}",
+            entry.info.path.display(),
+            entry
+                .info
+                .agent_name
+                .as_ref()
+                .map(|name| format!("agent: {name}"))
+                .unwrap_or_default()
+        )
+    } else {
+        "Worktreeがありません".to_string()
+    };
+
+    frame.render_widget(Paragraph::new(text).block(Block::bordered()), area);
+}
+
+/// 中央に配置した領域を計算
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, is_main: bool, locked: bool) -> WorktreeEntry {
+        WorktreeEntry {
+            info: WorktreeInfo {
+                path: PathBuf::from(path),
+                branch: format!("refs/heads/{path}"),
+                commit: "0123456789abcdef".to_string(),
+                locked,
+                ..Default::default()
+            },
+            changes: Some(0),
+            is_main,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn app_with_entries() -> TuiApp {
+        let mut app = TuiApp::new();
+        app.set_worktrees(vec![
+            entry("main", true, false),
+            entry("wt-1", false, false),
+            entry("wt-2", false, true),
+        ]);
+        app
+    }
+
+    #[test]
+    fn test_navigation_wraps_around() {
+        let mut app = app_with_entries();
+
+        assert_eq!(app.handle_key(key(KeyCode::Up)), None);
+        assert_eq!(app.selected().unwrap().info.path, PathBuf::from("wt-2"));
+
+        app.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(app.selected().unwrap().info.path, PathBuf::from("main"));
+    }
+
+    #[test]
+    fn test_add_input_mode() {
+        let mut app = app_with_entries();
+
+        app.handle_key(key(KeyCode::Char('a')));
+        for c in "agent-1x".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        app.handle_key(key(KeyCode::Backspace));
+
+        let action = app.handle_key(key(KeyCode::Enter));
+        assert_eq!(action, Some(TuiAction::Add("agent-1".to_string())));
+        assert_eq!(app.mode(), &TuiMode::List);
+    }
+
+    #[test]
+    fn test_remove_requires_confirmation_and_protects_main() {
+        let mut app = app_with_entries();
+
+        // メインのWorktreeは削除できない
+        app.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(app.mode(), &TuiMode::List);
+
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(app.mode(), &TuiMode::ConfirmRemove);
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('y'))),
+            Some(TuiAction::Remove(PathBuf::from("wt-1")))
+        );
+
+        app.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(app.handle_key(key(KeyCode::Char('n'))), None);
+        assert_eq!(app.mode(), &TuiMode::List);
+    }
+
+    #[test]
+    fn test_lock_toggle_and_hook_selection() {
+        let mut app = app_with_entries();
+        app.handle_key(key(KeyCode::Up));
+
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('l'))),
+            Some(TuiAction::SetLock(PathBuf::from("wt-2"), false))
+        );
+
+        app.handle_key(key(KeyCode::Char('h')));
+        assert_eq!(app.mode(), &TuiMode::SelectHook);
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('2'))),
+            Some(TuiAction::RunHooks(
+                PathBuf::from("wt-2"),
+                HookType::PostCreate
+            ))
+        );
+    }
+
+    #[test]
+    fn test_lock_repository_fails_while_another_process_holds_it() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut git = GitManager::with_backend(
+            temp.path(),
+            Box::new(crate::git_backend::FakeBackend::new(temp.path())),
+        );
+        let held = crate::utils::FileLock::for_repo(&temp.path().join(".git").join("twin"));
+        assert!(held.try_acquire().unwrap());
+        assert!(lock_repository(&mut git).is_err());
+
+        drop(held);
+        let lock = lock_repository(&mut git).unwrap();
+        assert!(lock.is_held());
+    }
+
+    #[test]
+    fn test_set_worktrees_clamps_selection() {
+        let mut app = app_with_entries();
+        app.handle_key(key(KeyCode::Up));
+
+        app.set_worktrees(vec![entry("main", true, false)]);
+        assert_eq!(app.selected().unwrap().info.path, PathBuf::from("main"));
+
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(TuiAction::Quit)
+        );
+    }
+}
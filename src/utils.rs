@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use crate::core::TwinResult;
+use crate::core::{TwinError, TwinResult};
 /// ユーティリティモジュール
 ///
 /// このモジュールの役割：
@@ -7,25 +7,345 @@ use crate::core::TwinResult;
 /// - パス操作のユーティリティ
 /// - ロック機能の実装（並行実行制御）
 /// - 出力フォーマット（テーブル、JSON）
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// リポジトリ単位のロックファイル名（`<git common dir>/twin/twin.lock`）
+pub const LOCK_FILE_NAME: &str = "twin.lock";
+/// ロック待ちのタイムアウト秒数を指定する環境変数
+pub const LOCK_TIMEOUT_ENV: &str = "TWIN_LOCK_TIMEOUT";
+/// ロック待ちのデフォルトのタイムアウト
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+/// ロック取得を再試行する間隔
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+/// 内容を書き込み中のロックファイルを待つ時間（これより古い空のファイルは壊れているとみなす）
+const LOCK_WRITE_GRACE: Duration = Duration::from_secs(5);
+
+/// ロックファイルに記録される保持者の情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockOwner {
+    /// ロックを保持しているプロセスのID
+    pub pid: u32,
+    /// 実行中のコマンド
+    pub command: String,
+    /// ロックの取得時刻
+    pub acquired_at: DateTime<Utc>,
+}
 
 /// ファイルベースのロック機能
+///
+/// ロックファイルを排他的に作成できたプロセスがロックを保持する。
+/// 保持者のプロセスが終了している場合は、残ったロックファイルを削除して取得し直す。
+/// 取得したロックは`release`またはドロップ時に解放される。
 pub struct FileLock {
     lock_path: PathBuf,
+    timeout: Duration,
+    held: AtomicBool,
 }
 
 impl FileLock {
     pub fn new(lock_path: PathBuf) -> Self {
-        Self { lock_path }
+        let timeout = std::env::var(LOCK_TIMEOUT_ENV)
+            .ok()
+            .and_then(|value| value.parse::<f64>().ok())
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .unwrap_or(DEFAULT_LOCK_TIMEOUT);
+        Self {
+            lock_path,
+            timeout,
+            held: AtomicBool::new(false),
+        }
+    }
+
+    /// twinディレクトリのリポジトリ単位のロックを作成（すべてのworktreeで共有される）
+    pub fn for_repo(twin_dir: &Path) -> Self {
+        Self::new(twin_dir.join(LOCK_FILE_NAME))
     }
 
+    /// ロック待ちのタイムアウトを設定
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// ロックファイルのパスを取得
+    pub fn path(&self) -> &Path {
+        &self.lock_path
+    }
+
+    /// ロックを取得する（他のプロセスが保持している場合はタイムアウトまで待つ）
     pub async fn acquire(&self) -> TwinResult<()> {
-        // TODO: ロック取得の実装
-        Ok(())
+        let deadline = Instant::now() + self.timeout;
+        let mut waiting = false;
+        loop {
+            if self.try_acquire()? {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                let holder = match self.owner() {
+                    Some(owner) => format!(
+                        "pid {} (`{}`, since {})",
+                        owner.pid,
+                        owner.command,
+                        owner
+                            .acquired_at
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M:%S")
+                    ),
+                    None => "another process".to_string(),
+                };
+                return Err(TwinError::lock(
+                    format!(
+                        "Timed out after {:.1}s waiting for the lock held by {holder}",
+                        self.timeout.as_secs_f64()
+                    ),
+                    Some(self.lock_path.clone()),
+                ));
+            }
+            if !waiting {
+                waiting = true;
+                if let Some(owner) = self.owner() {
+                    eprintln!(
+                        "⏳ 他のtwinプロセス (pid {}) の完了を待っています...",
+                        owner.pid
+                    );
+                }
+            }
+            tokio::time::sleep(LOCK_RETRY_INTERVAL).await;
+        }
+    }
+
+    /// ロックの取得を1回だけ試みる（取得できた場合はtrue）
+    pub fn try_acquire(&self) -> TwinResult<bool> {
+        if let Some(parent) = self.lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let owner = LockOwner {
+            pid: std::process::id(),
+            command: std::env::args().collect::<Vec<_>>().join(" "),
+            acquired_at: Utc::now(),
+        };
+        let content = serde_json::to_string(&owner).map_err(|e| {
+            TwinError::lock(
+                format!("Failed to serialize lock owner: {e}"),
+                Some(self.lock_path.clone()),
+            )
+        })?;
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&self.lock_path)
+        {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                self.held.store(true, Ordering::SeqCst);
+                Ok(true)
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                // 保持者が終了していれば、残ったロックを削除してすぐに再試行する
+                if self.break_stale_lock()? {
+                    return self.try_acquire();
+                }
+                Ok(false)
+            }
+            Err(e) => Err(TwinError::lock(
+                format!("Failed to create lock file: {e}"),
+                Some(self.lock_path.clone()),
+            )),
+        }
     }
 
+    /// ロックを解放する
     pub async fn release(&self) -> TwinResult<()> {
-        // TODO: ロック解放の実装
-        Ok(())
+        self.release_sync()
+    }
+
+    /// 現在のロックの保持者を取得
+    pub fn owner(&self) -> Option<LockOwner> {
+        read_owner(&self.lock_path)
+    }
+
+    /// このインスタンスがロックを保持しているか
+    pub fn is_held(&self) -> bool {
+        self.held.load(Ordering::SeqCst)
+    }
+
+    fn release_sync(&self) -> TwinResult<()> {
+        if !self.held.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        match fs::remove_file(&self.lock_path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(TwinError::lock(
+                format!("Failed to remove lock file: {e}"),
+                Some(self.lock_path.clone()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// 保持者のプロセスが終了しているロックファイルを削除する（削除した場合はtrue）
+    fn break_stale_lock(&self) -> TwinResult<bool> {
+        let content = match fs::read_to_string(&self.lock_path) {
+            Ok(content) => content,
+            // 読み込む前に解放された
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(true),
+            // 読み込めないロックファイルは取得し直しても同じ結果になるため、エラーにする
+            Err(e) => {
+                return Err(TwinError::lock(
+                    format!("Failed to read lock file: {e}"),
+                    Some(self.lock_path.clone()),
+                ))
+            }
+        };
+        let stale = match serde_json::from_str::<LockOwner>(&content) {
+            Ok(owner) => !is_process_alive(owner.pid),
+            // 書き込み途中のファイルは少し待ち、古いままなら壊れているとみなす
+            Err(_) => fs::metadata(&self.lock_path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > LOCK_WRITE_GRACE),
+        };
+        if !stale {
+            return Ok(false);
+        }
+
+        // 他のプロセスが同時に削除して取得し直したロックを消さないよう、
+        // 一度リネームしてから内容が変わっていないことを確認する
+        let claimed = self
+            .lock_path
+            .with_extension(format!("lock.stale.{}", std::process::id()));
+        match fs::rename(&self.lock_path, &claimed) {
+            Ok(()) => {}
+            // 他のプロセスが先に削除した
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(true),
+            Err(e) => {
+                return Err(TwinError::lock(
+                    format!("Failed to remove stale lock file: {e}"),
+                    Some(self.lock_path.clone()),
+                ))
+            }
+        }
+        if fs::read_to_string(&claimed).is_ok_and(|claimed_content| claimed_content == content) {
+            log::warn!(
+                "Removed stale lock {} left by a terminated process",
+                self.lock_path.display()
+            );
+            fs::remove_file(&claimed)?;
+        } else {
+            // 取得し直されたロックだったので元に戻す（既に別のロックがある場合はそちらを優先）
+            let _ = fs::hard_link(&claimed, &self.lock_path);
+            fs::remove_file(&claimed)?;
+        }
+        Ok(true)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.release_sync() {
+            log::warn!("{e}");
+        }
+    }
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// 指定したPIDのプロセスが生存しているか確認
+#[cfg(unix)]
+pub fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // シグナル0は送信せずに存在確認のみ行う（権限がない場合も存在はしている）
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// 指定したPIDのプロセスが生存しているか確認
+#[cfg(windows)]
+pub fn is_process_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive_until_released() {
+        let temp = TempDir::new().unwrap();
+        let first = FileLock::for_repo(temp.path());
+        let second = FileLock::for_repo(temp.path()).with_timeout(Duration::from_millis(200));
+
+        first.acquire().await.unwrap();
+        assert!(first.is_held());
+        assert_eq!(first.owner().unwrap().pid, std::process::id());
+
+        let err = second.acquire().await.unwrap_err();
+        assert!(matches!(err, TwinError::Lock { .. }), "{err}");
+        assert!(err.to_string().contains(&std::process::id().to_string()));
+
+        first.release().await.unwrap();
+        assert!(!first.path().exists());
+        second.acquire().await.unwrap();
+        assert!(second.is_held());
+    }
+
+    #[tokio::test]
+    async fn test_lock_is_released_on_drop() {
+        let temp = TempDir::new().unwrap();
+        {
+            let lock = FileLock::for_repo(temp.path());
+            lock.acquire().await.unwrap();
+        }
+        assert!(!temp.path().join(LOCK_FILE_NAME).exists());
+    }
+
+    #[tokio::test]
+    async fn test_stale_lock_of_dead_process_is_taken_over() {
+        let temp = TempDir::new().unwrap();
+        let stale = LockOwner {
+            pid: u32::MAX,
+            command: "twin add crashed".to_string(),
+            acquired_at: Utc::now(),
+        };
+        fs::write(
+            temp.path().join(LOCK_FILE_NAME),
+            serde_json::to_string(&stale).unwrap(),
+        )
+        .unwrap();
+
+        let lock = FileLock::for_repo(temp.path()).with_timeout(Duration::ZERO);
+        lock.acquire().await.unwrap();
+        assert_eq!(lock.owner().unwrap().pid, std::process::id());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_lock_is_an_error() {
+        // ロックファイルの位置にあるディレクトリは作成も読み込みもできない
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join(LOCK_FILE_NAME)).unwrap();
+
+        let lock = FileLock::for_repo(temp.path());
+        let err = lock.try_acquire().unwrap_err();
+        assert!(matches!(err, TwinError::Lock { .. }), "{err}");
+        assert!(!lock.is_held());
     }
 }
@@ -80,6 +80,18 @@ impl TestRepo {
             .expect("Failed to run twin")
     }
 
+    /// twinコマンドを終了を待たずに起動
+    #[allow(dead_code)]
+    pub fn spawn_twin(&self, args: &[&str]) -> std::process::Child {
+        Command::new(Self::get_twin_binary())
+            .args(args)
+            .current_dir(self.temp_dir.path())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("Failed to spawn twin")
+    }
+
     /// 一意のworktreeパスを生成
     #[allow(dead_code)]
     pub fn worktree_path(&self, name: &str) -> String {
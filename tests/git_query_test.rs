@@ -0,0 +1,152 @@
+//! 読み取り専用のGit問い合わせ（git2とgitコマンド）の結合テスト
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use twin_cli::git::{GitManager, WorktreeInfo};
+use twin_cli::git_backend::CliBackend;
+
+mod common;
+use common::TestRepo;
+
+/// `git worktree add`などに渡した相対パスを絶対パスに変換
+fn absolute(repo: &TestRepo, worktree_path: &str) -> PathBuf {
+    repo.path().parent().unwrap().join(&worktree_path[3..])
+}
+
+/// git2を使うGitManagerとgitコマンドだけを使うGitManagerを作成
+fn managers(path: &Path) -> (GitManager, GitManager) {
+    let libgit2 = GitManager::new(path).unwrap();
+    let cli = GitManager::with_backend(path, Box::new(CliBackend::new(path)));
+    (libgit2, cli)
+}
+
+/// 比較する項目だけを取り出し、パス順に並べる
+fn summarize(worktrees: Vec<WorktreeInfo>) -> Vec<(PathBuf, String, String, bool, bool)> {
+    let mut summary: Vec<_> = worktrees
+        .into_iter()
+        .map(|wt| (wt.path, wt.branch, wt.commit, wt.locked, wt.prunable))
+        .collect();
+    summary.sort();
+    summary
+}
+
+#[test]
+fn test_libgit2_queries_match_git_command() {
+    let repo = TestRepo::new();
+    let feature = repo.worktree_path("feature");
+    let detached = repo.worktree_path("detached");
+    let locked = repo.worktree_path("locked");
+    let missing = repo.worktree_path("missing");
+    repo.exec(&["git", "worktree", "add", "-b", "agent/feature", &feature]);
+    repo.exec(&["git", "worktree", "add", "--detach", &detached]);
+    repo.exec(&["git", "worktree", "add", "-b", "locked", &locked]);
+    repo.exec(&["git", "worktree", "lock", &locked]);
+    repo.exec(&["git", "worktree", "add", "-b", "missing", &missing]);
+    std::fs::remove_dir_all(absolute(&repo, &missing)).unwrap();
+
+    let (mut libgit2, mut cli) = managers(repo.path());
+    let worktrees = summarize(libgit2.list_worktrees().unwrap());
+    assert_eq!(worktrees, summarize(cli.list_worktrees().unwrap()));
+    assert_eq!(worktrees.len(), 5);
+
+    for branch in ["main", "agent/feature", "agent", "origin/main", "no such"] {
+        assert_eq!(
+            libgit2.branch_exists(branch).unwrap(),
+            cli.branch_exists(branch).unwrap(),
+            "{branch}"
+        );
+    }
+    assert_eq!(libgit2.get_current_branch().unwrap(), "main");
+
+    let feature_path = absolute(&repo, &feature);
+    let info = libgit2.get_worktree_info(&feature_path).unwrap();
+    let expected = cli.get_worktree_info(&feature_path).unwrap();
+    assert_eq!(info.path, expected.path);
+    assert_eq!(info.branch, "refs/heads/agent/feature");
+    assert_eq!(info.commit, expected.commit);
+    assert_eq!(info.agent_name.as_deref(), Some("feature"));
+
+    // Worktreeごとの差分とコミットの日時・件名
+    repo.exec(&[
+        "git",
+        "-C",
+        &feature,
+        "commit",
+        "--allow-empty",
+        "-m",
+        "feature work",
+    ]);
+    assert_eq!(libgit2.ahead_behind(&feature_path, "main").unwrap(), (1, 0));
+    assert_eq!(cli.ahead_behind(&feature_path, "main").unwrap(), (1, 0));
+    let summary = libgit2.commit_summary("agent/feature").unwrap();
+    assert_eq!(summary, cli.commit_summary("agent/feature").unwrap());
+    assert_eq!(summary.1, "feature work");
+
+    // リンクされたWorktreeから開いても同じ一覧と現在のブランチが得られる
+    let (mut libgit2, mut cli) = managers(&absolute(&repo, &detached));
+    assert_eq!(
+        summarize(libgit2.list_worktrees().unwrap()),
+        summarize(cli.list_worktrees().unwrap())
+    );
+    assert_eq!(libgit2.get_current_branch().unwrap(), "HEAD");
+    assert_eq!(cli.get_current_branch().unwrap(), "HEAD");
+}
+
+/// 多数のブランチ（`bench/0`〜）を持つリポジトリを作成
+fn repo_with_branches(count: usize) -> TestRepo {
+    let repo = TestRepo::new();
+    let mut update_ref = Command::new("git")
+        .args(["update-ref", "--stdin"])
+        .current_dir(repo.path())
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdin = update_ref.stdin.as_mut().unwrap();
+    for i in 0..count {
+        writeln!(stdin, "create refs/heads/bench/{i} HEAD").unwrap();
+    }
+    assert!(update_ref.wait().unwrap().success());
+    repo
+}
+
+/// `branch_exists`を繰り返し呼び出した時間を計測
+fn time_branch_exists(git: &mut GitManager, iterations: usize) -> Duration {
+    let start = Instant::now();
+    for i in 0..iterations {
+        assert!(git.branch_exists(&format!("bench/{i}")).unwrap());
+        assert!(!git.branch_exists(&format!("absent/{i}")).unwrap());
+    }
+    start.elapsed()
+}
+
+#[test]
+fn test_libgit2_branch_exists_matches_git_command_with_many_branches() {
+    let repo = repo_with_branches(500);
+
+    let (mut libgit2, mut cli) = managers(repo.path());
+    for branch in ["bench/0", "bench/499", "bench/500", "bench", "absent/0"] {
+        assert_eq!(
+            libgit2.branch_exists(branch).unwrap(),
+            cli.branch_exists(branch).unwrap(),
+            "{branch}"
+        );
+    }
+}
+
+/// 実行時間の比較は環境の負荷に左右されるため、`cargo test -- --ignored`で明示的に実行する
+#[test]
+#[ignore]
+fn test_libgit2_branch_exists_is_faster_than_git_command() {
+    const ITERATIONS: usize = 50;
+
+    let repo = repo_with_branches(500);
+
+    let (mut libgit2, mut cli) = managers(repo.path());
+    let libgit2_elapsed = time_branch_exists(&mut libgit2, ITERATIONS);
+    let cli_elapsed = time_branch_exists(&mut cli, ITERATIONS);
+    assert!(
+        libgit2_elapsed < cli_elapsed,
+        "libgit2 {libgit2_elapsed:?} should be faster than git command {cli_elapsed:?}"
+    );
+}
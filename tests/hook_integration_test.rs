@@ -65,6 +65,7 @@ async fn test_hooks_execution_on_add() {
         quiet: false,
         print_path: false,
         cd_command: false,
+        dry_run: None,
     };
 
     // フックが実行されることを確認（エラーが出ないこと）
@@ -117,6 +118,7 @@ async fn test_hooks_execution_on_remove() {
         quiet: true,
         print_path: false,
         cd_command: false,
+        dry_run: None,
     };
 
     let result = handle_add(add_args).await;
@@ -125,11 +127,14 @@ async fn test_hooks_execution_on_remove() {
 
     // worktreeを削除
     let remove_args = RemoveArgs {
-        worktree: worktree_path.to_string_lossy().to_string(),
+        worktree: Some(worktree_path.to_string_lossy().to_string()),
         force: true,
+        yes: false,
         config: Some(config_path),
         git_only: false,
         quiet: false,
+        filter: Default::default(),
+        dry_run: None,
     };
 
     // フックが実行されることを確認
@@ -178,6 +183,7 @@ pre_create = [
         quiet: false,
         print_path: false,
         cd_command: false,
+        dry_run: None,
     };
 
     // continue_on_error=trueなのでworktree作成は成功するはず
@@ -219,6 +225,7 @@ pre_create = [
         quiet: false,
         print_path: false,
         cd_command: false,
+        dry_run: None,
     };
 
     // フックが失敗してworktree作成も失敗するはず
@@ -229,3 +236,69 @@ pre_create = [
     );
     assert!(!worktree_path.exists());
 }
+
+#[cfg(unix)]
+#[test]
+fn test_sigint_during_hook_kills_hook_and_rolls_back() {
+    use std::time::{Duration, Instant};
+
+    let repo = TestRepo::new();
+    let pid_file = repo.path().join("hook.pid");
+    // continue_on_errorでも中断された場合は処理を止める
+    fs::write(
+        repo.path().join(".twin.toml"),
+        format!(
+            r#"
+[hooks]
+post_create = [{{ command = "echo $$ > {} && exec sleep 30", timeout = 60, continue_on_error = true }}]
+"#,
+            pid_file.display()
+        ),
+    )
+    .unwrap();
+
+    let worktree_path = repo.worktree_path("interrupted");
+    let mut twin = repo.spawn_twin(&["add", "interrupted", &worktree_path]);
+
+    // フックが起動するのを待ってからtwinにCtrl-C相当のSIGINTを送る
+    let start = Instant::now();
+    let hook_pid = loop {
+        if let Some(pid) = fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|content| content.trim().parse::<i32>().ok())
+        {
+            break pid;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "hook did not start"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    // SAFETY: 起動したtwinにシグナルを送るだけ
+    unsafe { libc::kill(twin.id() as libc::pid_t, libc::SIGINT) };
+
+    let status = loop {
+        if let Some(status) = twin.try_wait().unwrap() {
+            break status;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(20),
+            "twin did not stop"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    assert!(!status.success());
+
+    // フックのプロセスは終了し、作成したworktreeとブランチはロールバックされる
+    // SAFETY: シグナル0はプロセスの存在確認のみ
+    assert_ne!(unsafe { libc::kill(hook_pid, 0) }, 0);
+    assert!(!repo
+        .path()
+        .parent()
+        .unwrap()
+        .join(&worktree_path[3..])
+        .exists());
+    let output = repo.exec(&["git", "branch", "--list", "interrupted"]);
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+}
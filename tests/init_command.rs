@@ -1,8 +1,12 @@
+mod common;
+
 use anyhow::Result;
+use common::TestRepo;
 use std::path::PathBuf;
 use tempfile::TempDir;
 use twin_cli::cli::commands::handle_init;
 use twin_cli::cli::InitArgs;
+use twin_cli::config::Config;
 
 #[tokio::test]
 async fn test_init_command_creates_config_file() -> Result<()> {
@@ -113,3 +117,38 @@ async fn test_init_command_default_filename() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_init_output_round_trips_through_library() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let config_path = temp_dir.path().join("twin.toml");
+
+    let args = InitArgs {
+        path: Some(config_path.clone()),
+        force: false,
+    };
+    handle_init(args).await?;
+
+    // initが書き出した設定は、add/removeと同じローダーでそのまま読み込める
+    let loaded = Config::load_layers(None, Some(&config_path), None)?;
+    assert_eq!(loaded.settings, Config::example().settings);
+    assert_eq!(loaded.path, Some(config_path));
+
+    Ok(())
+}
+
+#[test]
+fn test_init_output_round_trips_through_cli() {
+    let repo = TestRepo::new();
+
+    let output = repo.run_twin(&["init"]);
+    assert!(output.status.success());
+    assert!(repo.path().join("twin.toml").exists());
+
+    // 生成された設定が自動的に探索され、エラーなく読み込まれる
+    let output = repo.run_twin(&["config", "--show"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("twin.toml"));
+    assert!(stdout.contains(".env.local"));
+}
@@ -6,6 +6,8 @@ mod common;
 
 use common::TestRepo;
 use std::process::Command;
+use twin_cli::hook_log::HookLog;
+use twin_cli::manifest::Manifest;
 
 // =============================================================================
 // Git操作との結合テスト
@@ -111,6 +113,190 @@ mapping_type = "symlink"
     assert!(worktree_path.join("data/test.txt").exists());
 }
 
+#[test]
+fn test_project_config_discovered_without_flag() {
+    let repo = TestRepo::new();
+
+    // --configを指定しなくてもプロジェクトのtwin.tomlが適用される
+    let config = r#"
+[[files]]
+path = "config.json"
+mapping_type = "copy"
+"#;
+
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join("config.json"), "{}").unwrap();
+
+    let worktree_path_str = repo.worktree_path("discovered");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/discovered",
+        &worktree_path_str,
+        "-b",
+        "feature/discovered",
+    ]);
+    assert!(output.status.success());
+
+    let worktree_path = repo.path().parent().unwrap().join(&worktree_path_str[3..]);
+    assert!(worktree_path.join("config.json").exists());
+}
+
+#[test]
+fn test_copy_mapping_with_config() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[files]]
+path = ".env"
+mapping_type = "copy"
+
+[[files]]
+path = "scripts"
+mapping_type = "copy"
+"#;
+
+    std::fs::write(repo.path().join(".twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join(".env"), "PORT=3000").unwrap();
+    std::fs::create_dir(repo.path().join("scripts")).unwrap();
+    std::fs::write(repo.path().join("scripts/setup.sh"), "echo setup").unwrap();
+
+    let worktree_path_str = repo.worktree_path("with-copies");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/copies",
+        &worktree_path_str,
+        "-b",
+        "feature/copies",
+        "--config",
+        ".twin.toml",
+    ]);
+    assert!(output.status.success());
+
+    // コピーはシンボリックリンクではなく独立した実体として作成される
+    let worktree_path = repo.path().parent().unwrap().join(&worktree_path_str[3..]);
+    let env_copy = worktree_path.join(".env");
+    assert!(!env_copy.is_symlink());
+    assert!(worktree_path.join("scripts/setup.sh").exists());
+    assert!(!worktree_path.join("scripts").is_symlink());
+
+    // コピーを編集してもソースは変更されない
+    std::fs::write(&env_copy, "PORT=4000").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join(".env")).unwrap(),
+        "PORT=3000"
+    );
+
+    // 削除時には変更されたコピーがバックアップされる
+    let output = repo.run_twin(&[
+        "remove",
+        &worktree_path_str,
+        "--force",
+        "--config",
+        ".twin.toml",
+    ]);
+    assert!(output.status.success());
+    // バックアップはworktreeと削除操作ごとのディレクトリに保存される
+    let backups: Vec<_> = std::fs::read_dir(repo.path().join(".git/twin/backups"))
+        .unwrap()
+        .flat_map(|entry| std::fs::read_dir(entry.unwrap().path()).unwrap())
+        .map(|entry| entry.unwrap().path().join(".env"))
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), "PORT=4000");
+}
+
+#[test]
+fn test_mapping_with_distinct_source_and_target() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[files]]
+source = ".env.template"
+target = ".env"
+mapping_type = "copy"
+"#;
+
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join(".env.template"), "PORT=3000").unwrap();
+
+    let worktree_path_str = repo.worktree_path("source-target");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/source-target",
+        &worktree_path_str,
+        "-b",
+        "feature/source-target",
+    ]);
+    assert!(output.status.success());
+
+    // sourceのファイルがtargetのパスに配置される
+    let worktree_path = repo.path().parent().unwrap().join(&worktree_path_str[3..]);
+    assert_eq!(
+        std::fs::read_to_string(worktree_path.join(".env")).unwrap(),
+        "PORT=3000"
+    );
+    assert!(!worktree_path.join(".env.template").exists());
+}
+
+#[test]
+fn test_template_mapping_renders_worktree_variables() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[files]]
+source = ".env.template"
+target = ".env"
+mapping_type = "template"
+variables = { db_name = "app_{{agent_name}}" }
+"#;
+
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(
+        repo.path().join(".env.template"),
+        "BRANCH={{branch}}\nDATABASE={{ db_name }}\n",
+    )
+    .unwrap();
+    std::fs::write(repo.path().join("broken.template"), "{{no_such_var}}").unwrap();
+
+    let worktree_path_str = repo.worktree_path("templated");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/templated",
+        &worktree_path_str,
+        "-b",
+        "feature/templated",
+    ]);
+    assert!(output.status.success());
+
+    let worktree_path = repo.path().parent().unwrap().join(&worktree_path_str[3..]);
+    assert_eq!(
+        std::fs::read_to_string(worktree_path.join(".env")).unwrap(),
+        "BRANCH=feature/templated\nDATABASE=app_feature/templated\n"
+    );
+
+    // 未定義のプレースホルダーはエラーとなり、作成したworktreeはロールバックされる
+    let config = r#"
+[[files]]
+source = "broken.template"
+target = "broken.txt"
+mapping_type = "template"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    let worktree_path_str = repo.worktree_path("broken-template");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/broken-template",
+        &worktree_path_str,
+        "-b",
+        "feature/broken-template",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no_such_var"));
+    let worktree_path = repo.path().parent().unwrap().join(&worktree_path_str[3..]);
+    assert!(!worktree_path.exists());
+}
+
 #[test]
 fn test_no_symlinks_without_config() {
     let repo = TestRepo::new();
@@ -169,7 +355,960 @@ post_create = [
     assert!(output.status.success() || stderr.contains("Hook executed"));
 }
 
+#[test]
+fn test_hook_output_streamed_with_prefix() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[hooks.post_create]]
+command = "echo streamed-output"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+
+    let worktree_path = repo.worktree_path("streamed");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/streamed",
+        &worktree_path,
+        "-b",
+        "feature/streamed",
+    ]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[post_create] streamed-output"));
+
+    // --quietではフックの出力を表示しない
+    let worktree_path = repo.worktree_path("quiet");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/quiet",
+        &worktree_path,
+        "-b",
+        "feature/quiet",
+        "--quiet",
+    ]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("streamed-output"));
+}
+
+#[test]
+fn test_hook_placeholders_from_docs_are_expanded() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[hooks.post_create]]
+command = "echo 'created {name} at {path}' [{hook_type}] ${BASE_BRANCH}"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+
+    let worktree_path = repo.worktree_path("placeholders");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/placeholders",
+        &worktree_path,
+        "-b",
+        "feature/placeholders",
+    ]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("created feature/placeholders at /"));
+    assert!(stderr.contains("[post_create] main"));
+    assert!(!stderr.contains("{name}"));
+
+    // 未定義のプレースホルダーはエラーになる
+    let config = r#"
+[[hooks.pre_create]]
+command = "echo {nmae}"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    let worktree_path = repo.worktree_path("unknown-placeholder");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/unknown-placeholder",
+        &worktree_path,
+        "-b",
+        "feature/unknown-placeholder",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("{nmae}"));
+}
+
+#[test]
+fn test_hook_results_are_logged() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[hooks.post_create]]
+command = "echo setup-done"
+
+[[hooks.post_create]]
+command = "echo setup-broken >&2; exit 7"
+continue_on_error = true
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+
+    let worktree_path = repo.worktree_path("logged");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/logged",
+        &worktree_path,
+        "-b",
+        "feature/logged",
+        "--quiet",
+    ]);
+    assert!(output.status.success());
+
+    // ログは共通gitディレクトリ配下にワークツリー名とパスのハッシュで保存される
+    let worktree_name = &worktree_path[3..];
+    let absolute = repo.path().parent().unwrap().join(worktree_name);
+    let log_path = HookLog::for_worktree(&repo.path().join(".git/twin"), &absolute)
+        .path()
+        .to_path_buf();
+    assert_eq!(
+        std::fs::read_to_string(&log_path).unwrap().lines().count(),
+        2
+    );
+
+    let output = repo.run_twin(&["logs", "feature/logged"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("echo setup-done"));
+    assert!(stdout.contains("setup-broken"));
+
+    // --failedでは失敗したフックのみ表示する
+    let output = repo.run_twin(&["logs", worktree_name, "--failed"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("setup-done"));
+    assert!(stdout.contains("exit 7"));
+
+    let output = repo.run_twin(&["logs", worktree_name, "--hook-type", "pre_create"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("setup-done"));
+
+    // 削除後もワークツリー名で参照できる
+    let output = repo.run_twin(&["remove", &worktree_path, "--force", "--git-only"]);
+    assert!(output.status.success(), "{output:?}");
+    let output = repo.run_twin(&["logs", worktree_name]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("echo setup-done"));
+}
+
+#[test]
+fn test_add_rolls_back_when_post_create_fails() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[files]]
+source = ".env"
+mapping_type = "symlink"
+
+[[hooks.post_create]]
+command = "exit 4"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join(".env"), "A=1").unwrap();
+
+    let worktree_path = repo.worktree_path("rollback");
+    let output = repo.run_twin(&[
+        "add",
+        "feature/rollback",
+        &worktree_path,
+        "-b",
+        "feature/rollback",
+    ]);
+    assert!(!output.status.success());
+
+    // リンク・worktree・自動作成されたブランチがすべて元に戻される
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ロールバック"), "stderr: {stderr}");
+    assert!(stderr.contains("symlink:"));
+    assert!(stderr.contains("branch: feature/rollback"));
+
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+    assert!(!absolute.exists());
+    let output = repo.exec(&["git", "branch", "--list", "feature/rollback"]);
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+    let output = repo.exec(&["git", "worktree", "list"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("rollback"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interrupted_add_can_be_rolled_back() {
+    let repo = TestRepo::new();
+
+    // post_createフックの実行中にtwin自身を強制終了させる
+    let config = r#"
+[[files]]
+source = ".env"
+mapping_type = "symlink"
+
+[[hooks.post_create]]
+command = "kill -9 $PPID"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join(".env"), "A=1").unwrap();
+
+    let worktree_path = repo.worktree_path("interrupted");
+    let output = repo.run_twin(&["add", "feature/interrupted", &worktree_path]);
+    assert!(!output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+    assert!(absolute.exists());
+
+    // 中断された操作として一覧に表示される
+    let output = repo.run_twin(&["recover"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("[add] feature/interrupted (中断)"),
+        "{stdout}"
+    );
+
+    let output = repo.run_twin(&["recover", "--all", "--rollback"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!absolute.exists());
+    let output = repo.exec(&["git", "branch", "--list", "feature/interrupted"]);
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+
+    let output = repo.run_twin(&["recover"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("中断された操作はありません"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interrupted_add_can_be_finished() {
+    let repo = TestRepo::new();
+
+    // 1回目の実行でのみtwin自身を強制終了させる
+    let config = r#"
+[[hooks.post_create]]
+command = "if [ ! -f interrupted ]; then touch interrupted; kill -9 $PPID; fi"
+
+[[hooks.post_create]]
+command = "touch finished"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+
+    let worktree_path = repo.worktree_path("finish");
+    let output = repo.run_twin(&["add", "feature/finish", &worktree_path]);
+    assert!(!output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+    assert!(absolute.join("interrupted").exists());
+    assert!(!absolute.join("finished").exists());
+
+    let output = repo.run_twin(&["recover", "--all", "--finish"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(absolute.join("finished").exists());
+
+    let output = repo.run_twin(&["recover"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("中断された操作はありません"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_parallel_adds_are_serialized() {
+    let repo = TestRepo::new();
+
+    // ロックを保持している間はフックが重なって実行されない
+    let log = repo.path().join("hooks.log");
+    let config = format!(
+        r#"
+[[hooks.pre_create]]
+command = "echo start >> {log}; sleep 0.2; echo end >> {log}"
+"#,
+        log = log.display()
+    );
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+
+    let names = ["parallel-a", "parallel-b", "parallel-c"];
+    let paths: Vec<String> = names.iter().map(|name| repo.worktree_path(name)).collect();
+    let outputs: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = names
+            .iter()
+            .zip(&paths)
+            .map(|(name, path)| {
+                let repo = &repo;
+                scope.spawn(move || repo.run_twin(&["add", &format!("feature/{name}"), path]))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    for output in &outputs {
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let log = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(log, "start\nend\n".repeat(names.len()));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_status_reports_git_state_and_mapping_health() {
+    let repo = TestRepo::new();
+
+    let config = r#"
+[[files]]
+source = ".env"
+mapping_type = "symlink"
+
+[[files]]
+source = ".secret"
+mapping_type = "symlink"
+
+[[files]]
+source = ".tool"
+mapping_type = "symlink"
+"#;
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join(".env"), "A=1").unwrap();
+    std::fs::write(repo.path().join(".secret"), "token").unwrap();
+    std::fs::write(repo.path().join(".tool"), "tool").unwrap();
+
+    let worktree_path = repo.worktree_path("status");
+    let output = repo.run_twin(&["add", "feature/status", &worktree_path]);
+    assert!(output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+
+    // リンク切れ・実ファイルへの置き換え・未追跡ファイルを作る
+    std::fs::remove_file(repo.path().join(".env")).unwrap();
+    std::fs::remove_file(absolute.join(".secret")).unwrap();
+    std::fs::write(absolute.join(".secret"), "local").unwrap();
+    std::fs::write(absolute.join("notes.txt"), "memo").unwrap();
+
+    let output = repo.run_twin(&["status", "feature/status", "--format", "json"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let statuses: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let status = &statuses[0];
+    assert_eq!(status["branch"], "feature/status");
+    assert_eq!(status["is_main"], false);
+    assert_eq!(status["base"]["branch"], "main");
+    assert_eq!(status["base"]["ahead"], 0);
+    // 未追跡のマッピング（.secret, .tool, .env）とnotes.txt
+    assert!(status["untracked"].as_u64().unwrap() >= 1);
+
+    let health: Vec<&str> = status["mappings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["health"].as_str().unwrap())
+        .collect();
+    assert_eq!(health, vec!["broken_link", "replaced_by_file", "valid"]);
+
+    let output = repo.run_twin(&["status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("📁 main"), "{stdout}");
+    assert!(stdout.contains("broken_link"), "{stdout}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_and_repair_drifted_worktree() {
+    let repo = TestRepo::new();
+
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "[[files]]\nsource = \".env\"\nmapping_type = \"symlink\"\n",
+    )
+    .unwrap();
+    std::fs::write(repo.path().join(".env"), "A=1").unwrap();
+    std::fs::write(repo.path().join(".tool"), "tool").unwrap();
+
+    let worktree_path = repo.worktree_path("drift");
+    let output = repo.run_twin(&["add", "feature/drift", &worktree_path]);
+    assert!(output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+
+    // 作成後に設定へマッピングを追加し、既存のリンクは移動前のパスを指すようにする
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "[[files]]\nsource = \".env\"\nmapping_type = \"symlink\"\n\n[[files]]\nsource = \".tool\"\nmapping_type = \"copy\"\n",
+    )
+    .unwrap();
+    std::fs::remove_file(absolute.join(".env")).unwrap();
+    std::os::unix::fs::symlink("/nonexistent/old-repo/.env", absolute.join(".env")).unwrap();
+
+    let output = repo.run_twin(&["check"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✗ feature/drift"), "{stdout}");
+    assert!(
+        stdout.contains("リンク先が設定と異なります: .env"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("マッピングが適用されていません: .tool"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("twin repair feature/drift"), "{stdout}");
+
+    let output = repo.run_twin(&["repair", "feature/drift"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(absolute.join(".env")).unwrap(),
+        "A=1"
+    );
+    assert!(!absolute.join(".tool").is_symlink());
+    assert_eq!(
+        std::fs::read_to_string(absolute.join(".tool")).unwrap(),
+        "tool"
+    );
+
+    // 修復後は問題がなく、再実行しても何も変更しない
+    let output = repo.run_twin(&["check"]);
+    assert!(output.status.success());
+    let output = repo.run_twin(&["repair", "--all"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("修復が必要なマッピングはありません"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sync_applies_config_changes_to_existing_worktree() {
+    let repo = TestRepo::new();
+
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "[[files]]\nsource = \".env\"\n\n[[files]]\nsource = \".old\"\n",
+    )
+    .unwrap();
+    for name in [".env", ".old", ".tool"] {
+        std::fs::write(repo.path().join(name), name).unwrap();
+    }
+
+    let worktree_path = repo.worktree_path("sync");
+    let output = repo.run_twin(&["add", "feature/sync", &worktree_path]);
+    assert!(output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+    assert!(absolute.join(".old").is_symlink());
+
+    // 作成後に.oldのマッピングを削除し、.toolのマッピングを追加する
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "[[files]]\nsource = \".env\"\n\n[[files]]\nsource = \".tool\"\n",
+    )
+    .unwrap();
+
+    // dry-runは計画のみ表示する
+    let output = repo.run_twin(&["sync", "feature/sync", "--prune", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let has_step = |action: &str, name: &str| {
+        stdout
+            .lines()
+            .any(|line| line.trim_start().starts_with(action) && line.ends_with(name))
+    };
+    assert!(has_step("link ", "/.tool"), "{stdout}");
+    assert!(has_step("unlink ", "/.old"), "{stdout}");
+    assert!(!absolute.join(".tool").exists());
+    assert!(absolute.join(".old").is_symlink());
+
+    // --pruneなしでは削除されたマッピングのリンクは残す
+    let output = repo.run_twin(&["sync", "--all"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(absolute.join(".tool")).unwrap(),
+        ".tool"
+    );
+    assert!(absolute.join(".old").is_symlink());
+
+    let output = repo.run_twin(&["sync", "feature/sync", "--prune"]);
+    assert!(output.status.success());
+    assert!(!absolute.join(".old").exists());
+    assert!(absolute.join(".env").is_symlink());
+
+    let output = repo.run_twin(&["sync", "--all", "--prune"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("✓ feature/sync: 同期済みです"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_remove_uses_manifest_instead_of_current_config() {
+    let repo = TestRepo::new();
+
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "[[files]]\nsource = \".env\"\n\n[[files]]\nsource = \".old\"\n\n[[files]]\nsource = \".tool\"\nmapping_type = \"copy\"\n",
+    )
+    .unwrap();
+    for name in [".env", ".old", ".tool"] {
+        std::fs::write(repo.path().join(name), name).unwrap();
+    }
+
+    let worktree_path = repo.worktree_path("manifest");
+    let output = repo.run_twin(&["add", "feature/manifest", &worktree_path]);
+    assert!(output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+
+    // 適用したマッピングがマニフェストに記録される
+    let manifest_path = Manifest::path_for(&repo.path().join(".git/twin"), &absolute);
+    let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+    assert!(manifest.contains(".old"), "{manifest}");
+    assert!(manifest.contains("\"checksum\""), "{manifest}");
+
+    // 作成後に.oldのマッピングを削除し、コピー元の内容を変更する
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "[[files]]\nsource = \".env\"\n\n[[files]]\nsource = \".tool\"\nmapping_type = \"copy\"\n",
+    )
+    .unwrap();
+    std::fs::write(repo.path().join(".tool"), "tool v2").unwrap();
+
+    // statusは適用済みのマッピングと設定の変更を表示する
+    let output = repo.run_twin(&["status", "feature/manifest"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(".old"), "{stdout}");
+    assert!(stdout.contains("twin sync"), "{stdout}");
+
+    // 設定から削除されたリンクも削除し、作成時から変更されていないコピーはバックアップしない
+    let output = repo.run_twin(&["remove", &worktree_path, "--force"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Removed symlink"), "{stderr}");
+    assert!(stderr.contains(".old"), "{stderr}");
+    assert!(!stderr.contains("Backed up"), "{stderr}");
+    assert!(!manifest_path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_prune_cleans_orphaned_side_effects() {
+    let repo = TestRepo::new();
+
+    let marker = repo.path().join("removed.txt");
+    let config = format!(
+        r#"
+[[files]]
+source = ".env"
+
+[[hooks.post_remove]]
+command = "echo {{branch}} >> {}"
+"#,
+        marker.display()
+    );
+    std::fs::write(repo.path().join("twin.toml"), config).unwrap();
+    std::fs::write(repo.path().join(".env"), "A=1").unwrap();
+
+    let worktree_path = repo.worktree_path("orphan");
+    let output = repo.run_twin(&["add", "feature/orphan", &worktree_path]);
+    assert!(output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+    let name = absolute.file_name().unwrap().to_string_lossy().to_string();
+    let manifest_path = Manifest::path_for(&repo.path().join(".git/twin"), &absolute);
+    let manifest_name = manifest_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    assert!(manifest_path.exists());
+
+    // .gitファイルが失われ、twinが作成したリンクだけが残ったディレクトリ
+    std::fs::remove_file(absolute.join(".git")).unwrap();
+    for entry in std::fs::read_dir(&absolute).unwrap() {
+        let path = entry.unwrap().path();
+        if !path.is_symlink() {
+            if path.is_dir() {
+                std::fs::remove_dir_all(&path).unwrap();
+            } else {
+                std::fs::remove_file(&path).unwrap();
+            }
+        }
+    }
+
+    let output = repo.run_twin(&["prune", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("git worktree prune --verbose"), "{stdout}");
+    assert!(stdout.contains("worktree metadata"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("{manifest_name} (manifest)")),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("[post_remove] echo feature/orphan"),
+        "{stdout}"
+    );
+    assert!(absolute.join(".env").is_symlink());
+    assert!(!marker.exists());
+
+    let output = repo.run_twin(&["prune"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✓ 管理情報"), "{stdout}");
+    assert!(!absolute.exists());
+    assert!(!manifest_path.exists());
+    assert_eq!(
+        std::fs::read_to_string(&marker).unwrap().trim(),
+        "feature/orphan"
+    );
+    let output = repo.exec(&["git", "worktree", "list"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains(&name));
+
+    let output = repo.run_twin(&["prune"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("削除対象はありません"));
+}
+
 // =============================================================================
+#[test]
+fn test_global_dry_run_prints_plan_without_changes() {
+    let repo = TestRepo::new();
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        r#"
+[[files]]
+source = ".env"
+
+[[files]]
+source = ".missing"
+mapping_type = "copy"
+
+[[hooks.post_create]]
+command = "echo {{branch}}"
+"#,
+    )
+    .unwrap();
+    std::fs::write(repo.path().join(".env"), "A=1").unwrap();
+
+    // --dry-run=json はgitコマンド・マッピング・展開済みのフックをJSONで出力する
+    let worktree_path = repo.worktree_path("dry-run");
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+    let output = repo.run_twin(&["--dry-run=json", "add", "feature/dry-run", &worktree_path]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let steps = plan["steps"].as_array().unwrap();
+    assert_eq!(plan["command"], "add");
+    assert_eq!(steps[0]["kind"], "git");
+    assert_eq!(
+        steps[0]["command"],
+        format!("git worktree add -b feature/dry-run {worktree_path}")
+    );
+    assert_eq!(steps[1]["action"], "link");
+    assert!(steps[1].get("skip_reason").is_none());
+    assert_eq!(steps[2]["action"], "copy");
+    assert_eq!(steps[2]["skip_reason"], "source not found");
+    assert_eq!(steps[3]["hook_type"], "post_create");
+    assert_eq!(steps[3]["command"], "echo feature/dry-run");
+
+    // 何も作成されない
+    assert!(!absolute.exists());
+    let output = repo.exec(&["git", "branch", "--list", "feature/dry-run"]);
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+    assert!(!repo.path().join(".git/twin/manifests").exists());
+
+    // removeは確認なしで計画を表示し、worktreeを残す
+    let output = repo.run_twin(&["add", "feature/dry-run", &worktree_path]);
+    assert!(output.status.success());
+    let output = repo.run_twin(&["remove", &worktree_path, "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ドライラン"), "{stdout}");
+    assert!(stdout.contains("git worktree remove"), "{stdout}");
+    assert!(stdout.contains("(manifest)"), "{stdout}");
+    assert!(absolute.join(".env").is_symlink());
+
+    // 実行計画を表示できないコマンドではエラーにする
+    let output = repo.run_twin(&["repair", "--all", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--dry-run is not supported"));
+}
+
+#[test]
+fn test_add_applies_branch_template() {
+    let repo = TestRepo::new();
+    std::fs::write(
+        repo.path().join("twin.toml"),
+        "branch_prefix = \"agent\"\nbranch_template = \"{prefix}{name}-{seq}\"\n",
+    )
+    .unwrap();
+
+    // 作成するブランチにテンプレートが適用され、{seq}は既存のブランチと重複しない番号になる
+    repo.exec(&["git", "branch", "agent/claude-1"]);
+    let worktree_path = repo.worktree_path("template");
+    let output = repo.run_twin(&["add", "claude", &worktree_path]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = repo.exec(&["git", "branch", "--list", "agent/claude-2"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).trim().is_empty());
+
+    // 一覧では同じテンプレートからエージェント名を抽出する
+    let output = repo.run_twin(&["list", "--format", "json"]);
+    assert!(output.status.success());
+    let worktrees: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let worktree = worktrees
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["branch"] == "refs/heads/agent/claude-2")
+        .unwrap();
+    assert_eq!(worktree["agent_name"], "claude");
+}
+
+#[test]
+fn test_list_shows_upstream_tracking() {
+    let repo = TestRepo::new();
+    let output = repo.exec(&["git", "rev-parse", "--abbrev-ref", "HEAD"]);
+    let main_branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let worktree_path = repo.worktree_path("tracking");
+    let output = repo.run_twin(&["add", "feature/tracking", &worktree_path]);
+    assert!(output.status.success());
+    let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+
+    // メインのブランチを上流に設定し、1コミット進める
+    let worktree = absolute.to_string_lossy().to_string();
+    repo.exec(&[
+        "git",
+        "-C",
+        &worktree,
+        "branch",
+        "--set-upstream-to",
+        &main_branch,
+    ]);
+    repo.exec(&[
+        "git",
+        "-C",
+        &worktree,
+        "commit",
+        "--allow-empty",
+        "-m",
+        "ahead",
+    ]);
+
+    let output = repo.run_twin(&["list", "--format", "json"]);
+    assert!(output.status.success());
+    let worktrees: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let worktree = worktrees
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["branch"] == "refs/heads/feature/tracking")
+        .unwrap();
+    assert_eq!(worktree["upstream"], main_branch.as_str());
+    assert_eq!(worktree["ahead"], 1);
+    assert_eq!(worktree["behind"], 0);
+    assert!(worktree["last_updated"].is_string());
+
+    let output = repo.run_twin(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("{main_branch} ↑1")), "{stdout}");
+}
+
+#[test]
+fn test_list_columns_and_sort() {
+    let repo = TestRepo::new();
+    let mut worktrees = Vec::new();
+    for name in ["fresh", "stale"] {
+        let worktree_path = repo.worktree_path(name);
+        let output = repo.run_twin(&["add", name, &worktree_path]);
+        assert!(output.status.success());
+        let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+        worktrees.push(absolute.to_string_lossy().to_string());
+    }
+    let (fresh, stale) = (&worktrees[0], &worktrees[1]);
+
+    // staleは古い日時でコミットし、ロックする。freshには未コミットの変更を作る
+    let output = Command::new("git")
+        .args(["-C", stale, "commit", "--allow-empty", "-m", "old work"])
+        .env("GIT_COMMITTER_DATE", "2020-01-01T00:00:00Z")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    repo.exec(&["git", "worktree", "lock", "--reason", "on hold", stale]);
+    std::fs::write(std::path::Path::new(fresh).join("wip.txt"), "wip").unwrap();
+    repo.exec(&[
+        "git",
+        "-C",
+        fresh,
+        "commit",
+        "--allow-empty",
+        "-m",
+        "new work",
+    ]);
+
+    let output = repo.run_twin(&[
+        "list",
+        "--format",
+        "json",
+        "--columns",
+        "dirty,subject,size,lock",
+        "--sort",
+        "updated",
+    ]);
+    assert!(output.status.success());
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+    let branches: Vec<_> = entries.iter().map(|e| e["branch"].clone()).collect();
+    assert_eq!(branches[1..], ["refs/heads/stale", "refs/heads/fresh"]);
+    assert_eq!(entries[1]["last_commit_subject"], "old work");
+    assert_eq!(entries[1]["lock_reason"], "on hold");
+    assert_eq!(entries[1]["dirty"], 0);
+    assert_eq!(entries[2]["dirty"], 1);
+    assert!(entries[2]["disk_size"].as_u64().unwrap() > 0);
+    assert!(entries[2]["created_at"].is_string());
+    // 指定されていない列の情報は収集しない
+    assert!(entries[2].get("base").is_none());
+
+    let output = repo.run_twin(&[
+        "list",
+        "--columns",
+        "branch,lock,created",
+        "--sort",
+        "updated",
+        "--reverse",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Created"), "{stdout}");
+    assert!(!stdout.contains("Upstream"), "{stdout}");
+    assert!(
+        stdout.find("fresh").unwrap() < stdout.find("stale").unwrap(),
+        "{stdout}"
+    );
+    assert!(stdout.contains("on hold"), "{stdout}");
+}
+
+#[test]
+fn test_list_filters_and_bulk_remove() {
+    let repo = TestRepo::new();
+    let output = repo.exec(&["git", "rev-parse", "--abbrev-ref", "HEAD"]);
+    let main_branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let mut worktrees = Vec::new();
+    for name in ["agent/merged", "agent/wip", "fix/login"] {
+        let worktree_path = repo.worktree_path(&name.replace('/', "-"));
+        let output = repo.run_twin(&["add", name, &worktree_path]);
+        assert!(output.status.success());
+        let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+        worktrees.push(absolute.to_string_lossy().to_string());
+    }
+    // agent/wipだけが未マージのコミットと未コミットの変更を持つ
+    repo.exec(&[
+        "git",
+        "-C",
+        &worktrees[1],
+        "commit",
+        "--allow-empty",
+        "-m",
+        "wip",
+    ]);
+    std::fs::write(std::path::Path::new(&worktrees[1]).join("wip.txt"), "wip").unwrap();
+
+    let list = |args: &[&str]| -> Vec<String> {
+        let mut command = vec!["list", "--format", "simple"];
+        command.extend(args);
+        let output = repo.run_twin(&command);
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect()
+    };
+    assert_eq!(list(&[]).len(), 4);
+    assert_eq!(list(&["--no-main"]).len(), 3);
+    assert_eq!(list(&["--dirty"]), ["refs/heads/agent/wip"]);
+    assert_eq!(
+        list(&["--branch-glob", "agent/*", "--merged", &main_branch]),
+        ["refs/heads/agent/merged"]
+    );
+    assert_eq!(
+        list(&["--merged", &main_branch, "--main", "--sort", "branch"]),
+        [
+            format!("refs/heads/{main_branch}"),
+            "refs/heads/agent/merged".to_string(),
+            "refs/heads/fix/login".to_string()
+        ]
+    );
+    assert!(list(&["--older-than", "7d"]).is_empty());
+    let output = repo.run_twin(&["list", "--older-than", "7years"]);
+    assert!(!output.status.success());
+
+    // 同じ条件でagentのワークツリーをまとめて削除する（未コミットの変更があるものは削除しない）
+    let output = repo.run_twin(&["remove", "--branch-glob", "agent/*", "--yes"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("未コミットの変更"), "{stdout}");
+    assert!(!std::path::Path::new(&worktrees[0]).exists());
+    assert!(std::path::Path::new(&worktrees[1]).join("wip.txt").exists());
+    assert!(std::path::Path::new(&worktrees[2]).exists());
+
+    // --forceを指定すると未コミットの変更があっても削除する
+    let output = repo.run_twin(&["remove", "--branch-glob", "agent/*", "--yes", "--force"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!std::path::Path::new(&worktrees[1]).exists());
+    assert!(std::path::Path::new(&worktrees[2]).exists());
+
+    // 対象も条件も指定しない場合はエラー
+    let output = repo.run_twin(&["remove", "--force"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_prune_with_filter_only_prunes_matching_worktrees() {
+    let repo = TestRepo::new();
+    let mut worktrees = Vec::new();
+    for name in ["agent/gone", "fix/gone"] {
+        let worktree_path = repo.worktree_path(&name.replace('/', "-"));
+        let output = repo.run_twin(&["add", name, &worktree_path]);
+        assert!(output.status.success());
+        let absolute = repo.path().parent().unwrap().join(&worktree_path[3..]);
+        std::fs::remove_dir_all(&absolute).unwrap();
+        worktrees.push(absolute);
+    }
+
+    let output = repo.run_twin(&["prune", "--branch-glob", "agent/*"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("agent/gone"), "{stdout}");
+    assert!(!stdout.contains("fix/gone"), "{stdout}");
+
+    let output = repo.run_twin(&["list", "--prunable", "--format", "simple"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "refs/heads/fix/gone");
+}
+
 // worktree削除の結合テスト
 // =============================================================================
 